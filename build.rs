@@ -2,11 +2,121 @@
 extern crate riscv_target;
 
 use riscv_target::Target;
+use std::collections::HashSet;
 use std::env;
 use std::fs;
 use std::path::PathBuf;
 
+/// Features that only work together with another feature, as `(feature, required)`
+const REQUIRES: &[(&str, &str)] = &[("nxti", "clic")];
+
+/// Features that cannot be combined, as `(feature, feature, reason)`
+const CONFLICTS: &[(&str, &str, &str)] = &[
+    (
+        "clic",
+        "s-mode",
+        "the CLIC is only supported in machine mode",
+    ),
+    (
+        "std",
+        "clic",
+        "`std` only builds the host tooling parts of the crate",
+    ),
+    (
+        "std",
+        "s-mode",
+        "`std` only builds the host tooling parts of the crate",
+    ),
+    (
+        "ram-test",
+        "ram",
+        "the RAM test would destroy the .data loaded in place",
+    ),
+    (
+        "full-trap-frame",
+        "fp-trap-save",
+        "both replace the trap entry `_start_trap`",
+    ),
+    (
+        "full-trap-frame",
+        "interrupt-stack",
+        "both replace the trap entry `_start_trap`",
+    ),
+    (
+        "full-trap-frame",
+        "stack-overflow-hook",
+        "both replace the trap entry `_start_trap`",
+    ),
+    (
+        "fp-trap-save",
+        "stack-overflow-hook",
+        "both replace the trap entry `_start_trap`",
+    ),
+    (
+        "interrupt-stack",
+        "emergency-stack",
+        "both keep the top of a per-hart stack in `mscratch`",
+    ),
+    (
+        "interrupt-stack",
+        "fp-trap-save",
+        "both replace the trap entry `_start_trap`",
+    ),
+    (
+        "interrupt-stack",
+        "clic",
+        "the CLIC vectored handlers do not go through `_start_trap`",
+    ),
+    (
+        "v-trap",
+        "clic",
+        "the CLIC replaces the vectored mode of the CLINT",
+    ),
+    (
+        "v-trap",
+        "fp-trap-save",
+        "the trampolines of the core interrupts do not go through `_start_trap`",
+    ),
+    (
+        "v-trap",
+        "interrupt-stack",
+        "the trampolines of the core interrupts do not go through `_start_trap`",
+    ),
+    (
+        "v-trap",
+        "stack-overflow-hook",
+        "the trampolines of the core interrupts do not go through `_start_trap`",
+    ),
+    (
+        "v-trap",
+        "full-trap-frame",
+        "the trampolines of the core interrupts do not go through `_start_trap`",
+    ),
+    (
+        "v-trap",
+        "nesting-limit",
+        "the depth is only counted by the `#[interrupt_handler]` wrappers of the CLIC",
+    ),
+    (
+        "ram",
+        "single-load-segment",
+        "`ram` places the load address of `.data` in `REGION_DATA`",
+    ),
+    (
+        "init-stack",
+        "paint-stack",
+        "both define the initial content of the stacks",
+    ),
+    (
+        "bootrom",
+        "paint-stack",
+        "the stack paint pattern would have to be loaded into RAM",
+    ),
+];
+
 fn main() {
+    check_features(&defines());
+
     let target = env::var("TARGET").unwrap();
     let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
     let name = env::var("CARGO_PKG_NAME").unwrap();
@@ -26,16 +136,229 @@ fn main() {
         fs::copy(&archive, out_dir.join(format!("lib{}.a", name))).unwrap();
         println!("cargo:rerun-if-changed={}", archive);
         println!("cargo:rustc-link-lib=static={}", name);
-        
-        if cfg!(feature = "clic") {
-            println!("cargo:rustc-link-arg=-DCLIC");
-        }
     }
     
 
+    let max_hart_id: usize = knob("RISCV_RT_MAX_HART_ID", "0")
+        .parse()
+        .expect("RISCV_RT_MAX_HART_ID must be an integer");
+    fs::write(
+        out_dir.join("config.rs"),
+        format!(
+            "/// Maximum hart id configured at build time (`RISCV_RT_MAX_HART_ID`, default 0)\n\
+             pub const MAX_HART_ID: usize = {};\n",
+            max_hart_id
+        ),
+    )
+    .unwrap();
+
+    // A linker script expression, e.g. `4K` or `0x1000`
+    let hart_stack_size = knob("RISCV_RT_HART_STACK_SIZE", "2K");
+    let heap_size = knob("RISCV_RT_HEAP_SIZE", "0");
+
+    // Alignment of the CLIC vector table, the required value depends on the core
+    let vector_align = knob("RISCV_RT_VECTOR_ALIGN", "1024");
+
+    // Memory regions from the environment, for targets without a `memory.x`. `link.x` includes
+    // them, as `rustc-link-arg` would only apply to the binaries of this package.
+    let env_memory_x = match env_memory_x() {
+        Some(memory_x) => {
+            fs::write(out_dir.join("riscv-rt-memory.x"), memory_x).unwrap();
+            "INCLUDE riscv-rt-memory.x"
+        }
+        None => "",
+    };
+
     // Put the linker script somewhere the linker can find it
-    fs::write(out_dir.join("link.x"), include_bytes!("link.x")).unwrap();
+    let link_x = preprocess(&fs::read_to_string("link.x").unwrap(), &defines())
+        .replace("$ENV_MEMORY_X", env_memory_x)
+        .replace("$MAX_HART_ID", &max_hart_id.to_string())
+        .replace("$HART_STACK_SIZE", &hart_stack_size)
+        .replace("$HEAP_SIZE", &heap_size)
+        .replace("$VECTOR_ALIGN", &vector_align)
+        .replace("$HART_STACK_SECTIONS", &hart_stack_sections(max_hart_id));
+    fs::write(out_dir.join("link.x"), link_x).unwrap();
     println!("cargo:rustc-link-search={}", out_dir.display());
     println!("cargo:rerun-if-changed=link.x");
     println!("cargo:rustc-link-arg=-Tlink.x");
 }
+
+/// Generates a `memory.x` from `RISCV_RT_RAM_{ORIGIN,LENGTH}` and, optionally,
+/// `RISCV_RT_FLASH_{ORIGIN,LENGTH}`.
+///
+/// Returns `None` if the RAM region is not configured, or if the application already passes its
+/// own linker script (e.g. `-C link-arg=-Tmemory.x`), which takes precedence.
+fn env_memory_x() -> Option<String> {
+    let region = |name: &str| {
+        let origin = optional_knob(&format!("RISCV_RT_{}_ORIGIN", name));
+        let length = optional_knob(&format!("RISCV_RT_{}_LENGTH", name));
+        match (origin, length) {
+            (Some(origin), Some(length)) => Some((origin, length)),
+            (None, None) => None,
+            _ => panic!(
+                "riscv-rt: RISCV_RT_{0}_ORIGIN and RISCV_RT_{0}_LENGTH must be set together",
+                name
+            ),
+        }
+    };
+    let ram = region("RAM");
+    let flash = region("FLASH");
+
+    let (ram_origin, ram_length) = match ram {
+        Some(ram) => ram,
+        None if flash.is_some() => {
+            panic!(
+                "riscv-rt: RISCV_RT_FLASH_* requires RISCV_RT_RAM_ORIGIN and RISCV_RT_RAM_LENGTH"
+            )
+        }
+        None => return None,
+    };
+    if user_linker_script() {
+        return None;
+    }
+
+    let mut memory_x = format!(
+        "/* Generated by riscv-rt from RISCV_RT_RAM_* and RISCV_RT_FLASH_* */\n\
+         MEMORY\n{{\n  RAM : ORIGIN = {}, LENGTH = {}\n",
+        ram_origin, ram_length
+    );
+    let code = match flash {
+        Some((flash_origin, flash_length)) => {
+            memory_x += &format!(
+                "  FLASH : ORIGIN = {}, LENGTH = {}\n",
+                flash_origin, flash_length
+            );
+            "FLASH"
+        }
+        None => "RAM",
+    };
+    memory_x += "}\n\n";
+    for (alias, region) in &[
+        ("REGION_TEXT", code),
+        ("REGION_RODATA", code),
+        ("REGION_DATA", "RAM"),
+        ("REGION_BSS", "RAM"),
+        ("REGION_HEAP", "RAM"),
+        ("REGION_STACK", "RAM"),
+    ] {
+        memory_x += &format!("REGION_ALIAS(\"{}\", {});\n", alias, region);
+    }
+    Some(memory_x)
+}
+
+/// Returns whether the rustflags pass a linker script other than `link.x` (e.g. `memory.x`).
+fn user_linker_script() -> bool {
+    env::var("CARGO_ENCODED_RUSTFLAGS")
+        .unwrap_or_default()
+        .split('\x1f')
+        .filter_map(|flag| flag.split("link-arg=").nth(1))
+        .any(|arg| arg.starts_with("-T") && !arg.ends_with("link.x"))
+}
+
+/// Generates the `.stack.hartN` sections of the `stack-sections` feature, from the highest
+/// hart id (lowest address) up to hart 0, which ends at `_stack_start`.
+fn hart_stack_sections(max_hart_id: usize) -> String {
+    let paint = env::var_os("CARGO_FEATURE_PAINT_STACK").is_some();
+    let kind = if paint { "" } else { "(NOLOAD) " };
+    let mut sections = String::new();
+    for hart in (0..=max_hart_id).rev() {
+        sections += &format!("  .stack.hart{} {}:\n  {{\n", hart, kind);
+        sections += &format!("    _estack_hart{} = .;\n", hart);
+        if paint {
+            sections += "    FILL(0xCCCCCCCC);\n    LONG(0xCCCCCCCC);\n";
+            sections += "    . += _hart_stack_size - 4;\n";
+        } else {
+            sections += "    . += _hart_stack_size;\n";
+        }
+        sections += &format!("    _sstack_hart{} = .;\n  }} > REGION_STACK\n\n", hart);
+    }
+    sections
+}
+
+/// Reads a build knob from the environment, falling back to `default` if it is not set.
+fn knob(var: &str, default: &str) -> String {
+    println!("cargo:rerun-if-env-changed={}", var);
+    env::var(var).unwrap_or_else(|_| default.to_string())
+}
+
+/// Reads a build knob that has no default from the environment.
+fn optional_knob(var: &str) -> Option<String> {
+    println!("cargo:rerun-if-env-changed={}", var);
+    env::var(var).ok().filter(|value| !value.trim().is_empty())
+}
+
+/// Names that can be tested with `#if defined(NAME)` in `link.x`.
+///
+/// Every enabled Cargo feature is defined, in upper case and with dashes replaced by
+/// underscores (e.g. `single-load-segment` is `SINGLE_LOAD_SEGMENT`).
+fn defines() -> HashSet<String> {
+    env::vars()
+        .filter_map(|(key, _)| key.strip_prefix("CARGO_FEATURE_").map(String::from))
+        .collect()
+}
+
+/// Fails the build if the enabled features form a combination that would compile into a
+/// broken image, e.g. a trap handler that is never installed.
+fn check_features(defines: &HashSet<String>) {
+    let enabled = |feature: &str| defines.contains(&feature.to_uppercase().replace('-', "_"));
+
+    for (feature, required) in REQUIRES {
+        if enabled(feature) && !enabled(required) {
+            panic!(
+                "riscv-rt: the `{}` feature requires the `{}` feature",
+                feature, required
+            );
+        }
+    }
+    for (a, b, reason) in CONFLICTS {
+        if enabled(a) && enabled(b) {
+            panic!(
+                "riscv-rt: the `{}` and `{}` features cannot be combined: {}",
+                a, b, reason
+            );
+        }
+    }
+}
+
+/// Resolves the `#if defined(NAME)`, `#if !defined(NAME)`, `#else` and `#endif` lines of the
+/// linker script, as the linker itself does not run a preprocessor.
+fn preprocess(script: &str, defines: &HashSet<String>) -> String {
+    // one entry per open `#if`: (condition of this block, all enclosing blocks are active)
+    let mut stack: Vec<(bool, bool)> = Vec::new();
+    let mut output = String::with_capacity(script.len());
+
+    for (number, line) in script.lines().enumerate() {
+        let active = stack.last().map_or(true, |&(cond, outer)| cond && outer);
+        let directive = line.trim();
+
+        if let Some(cond) = directive.strip_prefix("#if ") {
+            let cond = cond.trim();
+            let (negated, cond) = match cond.strip_prefix('!') {
+                Some(cond) => (true, cond.trim()),
+                None => (false, cond),
+            };
+            let name = cond
+                .strip_prefix("defined(")
+                .and_then(|c| c.strip_suffix(')'))
+                .unwrap_or_else(|| {
+                    panic!("link.x:{}: unsupported condition `{}`", number + 1, cond)
+                });
+            stack.push((defines.contains(name) != negated, active));
+        } else if directive == "#else" {
+            let top = stack
+                .last_mut()
+                .unwrap_or_else(|| panic!("link.x:{}: `#else` without `#if`", number + 1));
+            top.0 = !top.0;
+        } else if directive == "#endif" {
+            stack
+                .pop()
+                .unwrap_or_else(|| panic!("link.x:{}: `#endif` without `#if`", number + 1));
+        } else if active {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+    assert!(stack.is_empty(), "link.x: unterminated `#if`");
+
+    output
+}
@@ -0,0 +1,196 @@
+#!/bin/bash
+
+# Builds the example firmware for every valid feature combination and checks
+# that the resulting ELF exports the symbols expected for the selected dispatch mode
+
+set -euxo pipefail
+
+target=${TARGET:-riscv32imac-unknown-none-elf}
+nm=${NM:-riscv64-unknown-elf-nm}
+
+export RUSTFLAGS="-C link-arg=-T$(pwd)/examples/memory.x"
+
+# features[:example], the example defaults to `empty`
+combinations=('' 's-mode' 'clic' 'clic,nxti' 'clic,nxti:clic_direct' 'v-trap:vtrap_timer')
+
+for combination in "${combinations[@]}"
+do
+    features=${combination%%:*}
+    example=empty
+    if [[ $combination == *:* ]]; then
+        example=${combination#*:}
+    fi
+
+    expected=('_start' 'main' '_start_rust' '_start_trap_rust')
+    # layout symbols, see `EXTERN` in link.x
+    expected+=('_stext' '_stack_start' '_max_hart_id' '_hart_stack_size' '_heap_size')
+    expected+=('_sdata' '_edata' '_sbss' '_ebss' '_sheap' '_estack' '_sstack')
+
+    case $combination in
+        *':clic_direct')
+            expected+=('_start_trap' 'interrupt_vector')
+            ;;
+        *'v-trap'*)
+            expected+=('_start_trap' '__INTERRUPTS' '_vector_table')
+            expected+=('_vtrap_MachineSoft' '_vtrap_MachineTimer')
+            ;;
+        *'nxti'*)
+            expected+=('_nxti_trap_handler' 'interrupt_vector')
+            ;;
+        *'clic'*)
+            expected+=('_start_trap' 'interrupt_vector')
+            ;;
+        *)
+            expected+=('_start_trap' '__INTERRUPTS')
+            ;;
+    esac
+
+    cargo build --target $target --example $example --features "$features"
+    symbols=$($nm target/$target/debug/examples/$example)
+
+    for symbol in ${expected[@]}
+    do
+        if ! grep -qw "$symbol" <<< "$symbols"; then
+            echo "missing symbol '$symbol' in '$example' with features '$features'"
+            exit 1
+        fi
+    done
+done
+
+# CLIC image that only defines `int_7`: every other entry of the vector table must resolve to the
+# default handler, `_default_int_wrapper` without `nxti` and `DefaultHandler` with it
+for features in 'clic' 'clic,nxti'
+do
+    case $features in
+        *'nxti'*)
+            default='DefaultHandler'
+            ;;
+        *)
+            default='_default_int_wrapper'
+            ;;
+    esac
+
+    cargo build --target $target --example clic_int7 --features "$features"
+    symbols=$($nm target/$target/debug/examples/clic_int7)
+    address() {
+        awk -v symbol="$1" '$3 == symbol { print $1 }' <<< "$symbols"
+    }
+
+    expected=$(address $default)
+    for n in $(seq 0 264)
+    do
+        actual=$(address int_$n)
+        if [ $n -eq 7 ] && [ "$actual" == "$expected" ]; then
+            echo "int_7 resolves to '$default' with features '$features'"
+            exit 1
+        fi
+        if [ $n -ne 7 ] && [ "$actual" != "$expected" ]; then
+            echo "int_$n does not resolve to '$default' with features '$features'"
+            exit 1
+        fi
+    done
+done
+
+# `no-default-abort` with an `abort` defined by the image: it must be the only `abort`, also
+# reached through `_excess_hart`
+cargo build --target $target --example custom_abort --features no-default-abort
+symbols=$($nm target/$target/debug/examples/custom_abort)
+abort=$(awk '$3 == "abort" { print $1 }' <<< "$symbols")
+if [ -z "$abort" ] || [ "$(awk '$3 == "abort" { print $2 }' <<< "$symbols")" != 'T' ]; then
+    echo "'abort' is not defined by the image with no-default-abort"
+    exit 1
+fi
+if grep -qw 'default_abort' <<< "$symbols"; then
+    echo "'default_abort' is linked with no-default-abort"
+    exit 1
+fi
+if [ "$(awk '$3 == "_excess_hart" { print $1 }' <<< "$symbols")" != "$abort" ]; then
+    echo "'_excess_hart' does not resolve to the 'abort' of the image"
+    exit 1
+fi
+
+# `single-load-segment` with `.rodata` in another region: the flat image must not contain padding
+# holes, i.e. it is as large as the contents of the loadable segments, up to the alignment
+objcopy=${OBJCOPY:-riscv64-unknown-elf-objcopy}
+readelf=${READELF:-riscv64-unknown-elf-readelf}
+
+RUSTFLAGS="-C link-arg=-T$(pwd)/examples/memory-split.x" \
+    cargo build --target $target --example empty --features single-load-segment
+elf=target/$target/debug/examples/empty
+$objcopy -O binary $elf $elf.bin
+
+loaded=0
+for size in $($readelf -lW $elf | awk '$1 == "LOAD" { print $5 }')
+do
+    loaded=$((loaded + size))
+done
+image=$(stat -c %s $elf.bin)
+if [ $((image - loaded)) -ge 4 ]; then
+    echo "the image of $loaded bytes has $((image - loaded)) bytes of padding"
+    exit 1
+fi
+
+# handler map of the CLIC image: a single record for `#[interrupt_handler(7)] fn uart()`, see
+# `riscv_rt::handler_map` for the format
+cargo build --target $target --example clic_int7 --features clic
+elf=target/$target/debug/examples/clic_int7
+map=$elf.handler_map
+$objcopy --dump-section .handler_map=$map $elf $elf.dumped
+number=$(od -An -tu4 -N4 $map | tr -d ' ')
+read -r symbol_len handler_len <<< "$(od -An -tu2 -j4 -N4 $map)"
+symbol=$(tail -c +9 $map | head -c $symbol_len)
+handler=$(tail -c +$((9 + symbol_len)) $map | head -c $handler_len)
+if [ "$number:$symbol:$handler" != '7:int_7:uart' ] || [ $(stat -c %s $map) -ne 20 ]; then
+    echo "unexpected handler map '$number:$symbol:$handler' of $(stat -c %s $map) bytes"
+    exit 1
+fi
+
+# CLIC interrupt wrapper of `#[interrupt_handler(7)]`: the context must be saved and restored
+# with full-width registers, in a frame of 32 registers, and with the compressed forms of the
+# loads and stores on these targets with the C extension
+objdump=${OBJDUMP:-riscv64-unknown-elf-objdump}
+
+for xlen in 32 64
+do
+    case $xlen in
+        32)
+            store='sw' load='lw' other='sd' frame=128
+            ;;
+        64)
+            store='sd' load='ld' other='sw' frame=256
+            ;;
+    esac
+
+    wrapper_target=riscv${xlen}imac-unknown-none-elf
+    cargo build --target $wrapper_target --example clic_int7 --features clic
+    wrapper=$($objdump -d --no-show-raw-insn --disassemble=int_7 \
+        target/$wrapper_target/debug/examples/clic_int7)
+
+    for pattern in "addi\s+sp,\s*sp,\s*-$frame\b" "\b$store\s+ra,\s*0\(sp\)" \
+        "\b$store\s+t6,\s*$((15 * xlen / 8))\(sp\)" "\b$load\s+ra,\s*0\(sp\)" \
+        "addi\s+sp,\s*sp,\s*$frame\b"
+    do
+        if ! grep -Eq "$pattern" <<< "$wrapper"; then
+            echo "'$pattern' not found in the RV$xlen interrupt wrapper"
+            exit 1
+        fi
+    done
+    if grep -Eq "\b$other\s" <<< "$wrapper"; then
+        echo "the RV$xlen interrupt wrapper uses '$other'"
+        exit 1
+    fi
+
+    wrapper=$($objdump -d -M no-aliases --no-show-raw-insn --disassemble=int_7 \
+        target/$wrapper_target/debug/examples/clic_int7)
+    for insn in "c.${store}sp" "c.${load}sp" "c.addi16sp"
+    do
+        if ! grep -qF "$insn" <<< "$wrapper"; then
+            echo "'$insn' not found in the RV$xlen interrupt wrapper"
+            exit 1
+        fi
+    done
+    if grep -Eq "\s($store|$load)\s+\w+,\s*-?[0-9]+\(sp\)" <<< "$wrapper"; then
+        echo "the RV$xlen interrupt wrapper has uncompressed stack accesses"
+        exit 1
+    fi
+done
@@ -0,0 +1,29 @@
+#!/bin/bash
+
+# Builds the example firmware against a core-only standard library and checks that the startup
+# code does not pull in `memcpy`/`memset`/`memmove`, which freestanding targets may not provide
+
+set -euxo pipefail
+
+target=${TARGET:-riscv32imac-unknown-none-elf}
+nm=${NM:-riscv64-unknown-elf-nm}
+
+export RUSTFLAGS="-C link-arg=-T$(pwd)/examples/memory.x"
+
+for profile in 'dev' 'release'
+do
+    cargo +nightly build -Zbuild-std=core --target $target --example empty --profile $profile
+    case $profile in
+        'dev') dir='debug' ;;
+        *) dir=$profile ;;
+    esac
+    symbols=$($nm target/$target/$dir/examples/empty)
+
+    for symbol in 'memcpy' 'memset' 'memmove'
+    do
+        if grep -qw "$symbol" <<< "$symbols"; then
+            echo "unexpected symbol '$symbol' in the $profile build"
+            exit 1
+        fi
+    done
+done
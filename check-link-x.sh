@@ -0,0 +1,78 @@
+#!/bin/bash
+
+# Checks that the `link.x` generated by the build script provides the expected symbols for each
+# feature combination. `!` marks a line that must not be present.
+
+set -euo pipefail
+
+target=${TARGET:-riscv32imac-unknown-none-elf}
+
+common=(
+    'PROVIDE(_stext = ORIGIN(REGION_TEXT));'
+    'PROVIDE(_stack_start = ORIGIN(REGION_STACK) + LENGTH(REGION_STACK));'
+    'PROVIDE(_max_hart_id = 0);'
+    'PROVIDE(_hart_stack_size = 2K);'
+    'PROVIDE(_heap_size = 0);'
+    'PROVIDE(_mp_hook = default_mp_hook);'
+    'PROVIDE(_excess_hart = abort);'
+    'PROVIDE(_watchdog_feed = default_watchdog_feed);'
+    'PROVIDE(_setup_interrupts = default_setup_interrupts);'
+    'PROVIDE(ExceptionHandler = DefaultExceptionHandler);'
+    'PROVIDE(DefaultHandler = DefaultInterruptHandler);'
+)
+
+check() {
+    local features=$1
+    shift
+
+    local out_dir
+    out_dir=$(cargo check --target $target --features "$features" --message-format=json |
+        sed -n 's|.*"out_dir":"\([^"]*/build/riscv-rt-[^"/]*/out\)".*|\1|p' | tail -n 1)
+
+    for line in "${common[@]}" "$@"
+    do
+        if [ "${line:0:1}" == '!' ]; then
+            if grep -qF "${line:1}" "$out_dir/link.x"; then
+                echo "unexpected '${line:1}' with features '$features'"
+                exit 1
+            fi
+        elif ! grep -qxF "$line" "$out_dir/link.x"; then
+            echo "missing '$line' with features '$features'"
+            exit 1
+        fi
+    done
+}
+
+check '' \
+    'PROVIDE(abort = default_abort);' \
+    'PROVIDE(_start_trap = default_start_trap);' \
+    '!PROVIDE(_selftest_failed' \
+    '!PROVIDE(_ram_test_failed' \
+    '!PROVIDE(_ram_test_background' \
+    '!PROVIDE(_max_nesting_depth' \
+    '!PROVIDE(_stack_overflow'
+
+check 'boot-selftest' \
+    'PROVIDE(_selftest_failed = default_selftest_failed);'
+
+check 'ram-test' \
+    'PROVIDE(_ram_test_failed = default_ram_test_failed);' \
+    'PROVIDE(_ram_test_background = 0);'
+
+check 'nesting-limit' \
+    'PROVIDE(_max_nesting_depth = 4);' \
+    'PROVIDE(_max_nesting_exceeded = default_max_nesting_exceeded);' \
+    'ASSERT(_max_hart_id <= 0, "'
+
+check 'no-default-abort' \
+    '!PROVIDE(abort = default_abort);'
+
+check 'stack-overflow-hook' \
+    'PROVIDE(_start_trap = stack_guard_start_trap);' \
+    'PROVIDE(_stack_overflow = default_stack_overflow);'
+
+check 'interrupt-stack' \
+    'PROVIDE(_start_trap = interrupt_stack_start_trap);'
+
+check 'full-trap-frame' \
+    'PROVIDE(_start_trap = full_frame_start_trap);'
@@ -0,0 +1,52 @@
+#!/bin/bash
+
+# Runs the examples that test the runtime on the QEMU `virt` machine. Each example reports its
+# result through the `sifive_test` device, which sets the exit status of QEMU. With `-icount`,
+# `mcycle` counts the executed instructions, which makes the printed latencies reproducible.
+
+set -euxo pipefail
+
+target=${TARGET:-riscv32imac-unknown-none-elf}
+qemu=${QEMU:-qemu-system-riscv32}
+
+export RUSTFLAGS="-C link-arg=-T$(pwd)/examples/memory-qemu.x"
+
+# Builds and runs `example` with `features` (and `profile`), prints the UART output. `harts` sets
+# the number of harts, 1 by default.
+run() {
+    local example=$1 features=$2 profile=${3:-debug}
+    local flags=()
+    if [ "$profile" == 'release' ]; then
+        flags+=('--release')
+    fi
+
+    cargo build --target $target --example $example --features "$features" "${flags[@]}"
+    timeout 60 $qemu -machine virt -smp ${harts:-1} -nographic -bios none -icount shift=0 \
+        -kernel target/$target/$profile/examples/$example
+}
+
+# example:features
+tests=('vtrap_timer:v-trap' 'preset_gp:' 'preset_gp:preserve-gp' 'ram_data:ram' 'skip_ecall:')
+
+for test in "${tests[@]}"
+do
+    run ${test%%:*} "${test#*:}"
+done
+
+# `_mp_hook`, `main` and `hart_id()` on two harts
+RISCV_RT_MAX_HART_ID=1 harts=2 run hart_ids ''
+
+# FPU enabled by `start_rust`, on a target with the F extension
+target=riscv64gc-unknown-none-elf qemu=qemu-system-riscv64 run fpu ''
+
+# `interrupt-stack`, with `.data` right below the main stack
+RUSTFLAGS="-C link-arg=-T$(pwd)/examples/memory-istack.x" run interrupt_stack interrupt-stack
+
+# `.bss` initialization benchmark: `fast-bss` must take fewer cycles than the default loop
+cycles() {
+    run boot_bss "$1" release | tr -d '\r' | sed -n 's/^bss: \([0-9]*\) cycles$/\1/p'
+}
+loop_cycles=$(cycles '')
+fast_cycles=$(cycles 'fast-bss')
+echo "bss: $loop_cycles cycles with the loop, $fast_cycles cycles with fast-bss"
+[ "$fast_cycles" -lt "$loop_cycles" ]
@@ -0,0 +1,74 @@
+//! Measures the initialization of a large `.bss` on the QEMU `virt` machine, to compare the
+//! `fast-bss` feature with the default loop, see check-qemu.sh. `#[pre_init]` fills `.bss` and
+//! reads `mcycle`, `main` checks that `.bss` was zeroed and prints the cycles on the UART. The
+//! result is reported through the `sifive_test` device, which sets the exit status of QEMU.
+
+#![no_std]
+#![no_main]
+
+extern crate panic_halt;
+extern crate riscv;
+extern crate riscv_rt;
+
+use core::fmt::{self, Write};
+use core::mem::MaybeUninit;
+use core::ptr;
+
+use riscv::register::mcycle;
+use riscv_rt::{entry, pre_init};
+
+const UART: *mut u8 = 0x1000_0000 as *mut u8;
+const TEST: *mut u32 = 0x0010_0000 as *mut u32;
+
+const PASS: u32 = 0x5555;
+const FAIL: u32 = 0x3333;
+
+/// 64 KiB on RV32, odd to also cover the tail of the unrolled loop
+const WORDS: usize = 16 * 1024 + 3;
+
+static mut BSS: [usize; WORDS] = [0; WORDS];
+
+/// `mcycle` at the end of `#[pre_init]`, outside of `.bss`
+#[link_section = ".uninit.START"]
+static mut START: MaybeUninit<usize> = MaybeUninit::uninit();
+
+struct Uart;
+
+impl Write for Uart {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            unsafe { ptr::write_volatile(UART, byte) };
+        }
+        Ok(())
+    }
+}
+
+fn exit(code: u32, reason: u32) -> ! {
+    unsafe { ptr::write_volatile(TEST, code | reason << 16) };
+    loop {}
+}
+
+#[pre_init]
+unsafe fn before_main() {
+    // QEMU starts with zeroed RAM, which would hide a missing initialization
+    let bss = ptr::addr_of_mut!(BSS) as *mut usize;
+    for i in 0..WORDS {
+        ptr::write_volatile(bss.add(i), usize::MAX);
+    }
+    START = MaybeUninit::new(mcycle::read());
+}
+
+#[entry]
+fn main() -> ! {
+    let cycles = mcycle::read().wrapping_sub(unsafe { START.assume_init() });
+
+    let bss = unsafe { ptr::addr_of!(BSS) as *const usize };
+    for i in 0..WORDS {
+        if unsafe { ptr::read_volatile(bss.add(i)) } != 0 {
+            exit(FAIL, 1);
+        }
+    }
+
+    writeln!(Uart, "bss: {} cycles", cycles).ok();
+    exit(PASS, 0)
+}
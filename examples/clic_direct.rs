@@ -0,0 +1,21 @@
+//! CLIC image whose interrupts are dispatched one per trap by `_start_trap_rust` instead of the
+//! NXTI handler, see check-features.sh
+
+#![no_std]
+#![no_main]
+
+extern crate panic_halt;
+extern crate riscv_rt;
+
+use riscv_rt::{entry, interrupt_handler, setup_interrupts};
+
+#[setup_interrupts(mode = "clic_direct")]
+fn setup_interrupts() {}
+
+#[interrupt_handler(7)]
+fn uart() {}
+
+#[entry]
+fn main() -> ! {
+    loop {}
+}
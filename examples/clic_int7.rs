@@ -0,0 +1,18 @@
+//! Defines only the handler of the CLIC interrupt 7, see check-features.sh: every other `int_N`
+//! entry of the vector table must resolve to the default handler.
+
+#![no_std]
+#![no_main]
+
+extern crate panic_halt;
+extern crate riscv_rt;
+
+use riscv_rt::{entry, interrupt_handler};
+
+#[interrupt_handler(7)]
+fn uart() {}
+
+#[entry]
+fn main() -> ! {
+    loop {}
+}
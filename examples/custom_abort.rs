@@ -0,0 +1,20 @@
+//! Image defining its own `abort`, built with the `no-default-abort` feature, see
+//! check-features.sh.
+
+#![no_std]
+#![no_main]
+
+extern crate panic_halt;
+extern crate riscv_rt;
+
+use riscv_rt::entry;
+
+#[no_mangle]
+pub extern "C" fn abort() -> ! {
+    loop {}
+}
+
+#[entry]
+fn main() -> ! {
+    loop {}
+}
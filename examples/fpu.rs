@@ -0,0 +1,51 @@
+//! Checks on the QEMU `virt` machine that `start_rust` enables the FPU on targets with the F
+//! extension: `main` reads a cleared `fcsr` and computes with `f32`, which would raise illegal
+//! instruction exceptions with `mstatus.FS` Off. Run for `riscv64gc-unknown-none-elf`, see
+//! check-qemu.sh. The result is reported through the `sifive_test` device, which sets the exit
+//! status of QEMU.
+
+#![no_std]
+#![no_main]
+
+#[cfg(not(target_feature = "f"))]
+compile_error!("this example requires a target with the F extension");
+
+extern crate panic_halt;
+extern crate riscv_rt;
+
+use core::arch::asm;
+use core::ptr;
+
+use riscv_rt::entry;
+
+const TEST: *mut u32 = 0x0010_0000 as *mut u32;
+
+const PASS: u32 = 0x5555;
+const FAIL: u32 = 0x3333;
+
+fn exit(code: u32, reason: u32) -> ! {
+    unsafe { ptr::write_volatile(TEST, code | reason << 16) };
+    loop {}
+}
+
+/// Evaluates `a * b + c / d` with the FPU, the operands are read at run time.
+#[inline(never)]
+fn evaluate(operands: &[f32; 4]) -> f32 {
+    let [a, b, c, d] = unsafe { ptr::read_volatile(operands) };
+    a * b + c / d
+}
+
+#[entry]
+fn main() -> ! {
+    let fcsr: usize;
+    unsafe { asm!("csrr {0}, fcsr", out(reg) fcsr) };
+    if fcsr != 0 {
+        exit(FAIL, 1);
+    }
+
+    if evaluate(&[1.5, 4.0, 9.0, 2.0]) != 10.5 {
+        exit(FAIL, 2);
+    }
+
+    exit(PASS, 0)
+}
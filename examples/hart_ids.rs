@@ -0,0 +1,81 @@
+//! Checks on the QEMU `virt` machine with two harts that `_mp_hook`, `main` and `hart_id()` agree
+//! on the id of each hart. Built with `RISCV_RT_MAX_HART_ID=1` and run with `-smp 2`, see
+//! check-qemu.sh. The result is reported through the `sifive_test` device, which sets the exit
+//! status of QEMU.
+//!
+//! Only machine mode is covered: in supervisor mode, OpenSBI picks the boot hart at random and
+//! starts the other harts through its HSM extension, which the runtime does not drive.
+
+#![no_std]
+#![no_main]
+
+extern crate panic_halt;
+extern crate riscv;
+extern crate riscv_rt;
+
+use core::ptr;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use riscv::asm::wfi;
+use riscv::register::{mie, mip};
+use riscv_rt::{entry, hart_id};
+
+const MSIP: *mut u32 = 0x0200_0000 as *mut u32;
+const MTIME: *const u64 = 0x0200_bff8 as *const u64;
+const TEST: *mut u32 = 0x0010_0000 as *mut u32;
+
+const PASS: u32 = 0x5555;
+const FAIL: u32 = 0x3333;
+
+/// Set by hart 1 once it reached `main` with consistent ids
+static HART1_READY: AtomicBool = AtomicBool::new(false);
+
+fn exit(code: u32, reason: u32) -> ! {
+    unsafe { ptr::write_volatile(TEST, code | reason << 16) };
+    loop {}
+}
+
+#[export_name = "_mp_hook"]
+pub extern "Rust" fn user_mp_hook(hartid: usize) -> bool {
+    if hartid != hart_id() {
+        exit(FAIL, 1);
+    }
+    if hartid == 0 {
+        return true;
+    }
+
+    // wait for the IPI of hart 0, sent once `.data` and `.bss` are initialized
+    unsafe {
+        mie::set_msoft();
+        while !mip::read().msoft() {
+            wfi();
+        }
+        mie::clear_msoft();
+        ptr::write_volatile(MSIP.add(hartid), 0);
+    }
+    false
+}
+
+#[entry]
+fn main(hartid: usize) -> ! {
+    if hartid != hart_id() {
+        exit(FAIL, 2);
+    }
+    if hartid != 0 {
+        HART1_READY.store(true, Ordering::Release);
+        loop {
+            wfi();
+        }
+    }
+
+    unsafe { ptr::write_volatile(MSIP.add(1), 1) };
+    // up to 1 s at the 10 MHz timebase of `virt`
+    let deadline = unsafe { ptr::read_volatile(MTIME) } + 10_000_000;
+    while !HART1_READY.load(Ordering::Acquire) {
+        if unsafe { ptr::read_volatile(MTIME) } >= deadline {
+            exit(FAIL, 3);
+        }
+    }
+
+    exit(PASS, 0)
+}
@@ -0,0 +1,92 @@
+//! Checks on the QEMU `virt` machine that the `interrupt-stack` feature runs the handlers on the
+//! interrupt stack: the `MachineSoft` handler uses more stack than the 4K main stack, which ends
+//! right above `.data` (see memory-istack.x), and `.data` must be intact afterwards. The result is
+//! reported through the `sifive_test` device, which sets the exit status of QEMU.
+
+#![no_std]
+#![no_main]
+
+extern crate panic_halt;
+extern crate riscv;
+extern crate riscv_rt;
+
+use core::arch::asm;
+use core::ptr;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use riscv::register::{mie, mstatus};
+use riscv_rt::entry;
+
+const MSIP: *mut u32 = 0x0200_0000 as *mut u32;
+const MTIME: *const u64 = 0x0200_bff8 as *const u64;
+const TEST: *mut u32 = 0x0010_0000 as *mut u32;
+
+const PASS: u32 = 0x5555;
+const FAIL: u32 = 0x3333;
+
+/// Value of every word of `DATA`
+const PATTERN: u32 = 0x5a5a_5a5a;
+
+/// Initialized static, which the handler would overwrite if it ran on the main stack
+static mut DATA: [u32; 256] = [PATTERN; 256];
+/// `sp` of the last `MachineSoft` call, 0 until the interrupt is taken
+static HANDLER_SP: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" {
+    static _sinterrupt_stack: u8;
+    static _interrupt_stack_start: u8;
+}
+
+fn exit(code: u32, reason: u32) -> ! {
+    unsafe { ptr::write_volatile(TEST, code | reason << 16) };
+    loop {}
+}
+
+#[export_name = "MachineSoft"]
+fn machine_soft() {
+    let sp: usize;
+    unsafe { asm!("mv {0}, sp", out(reg) sp) };
+    unsafe { ptr::write_volatile(MSIP, 0) };
+    // twice the size of the main stack
+    let mut buffer = [0u8; 8 * 1024];
+    for byte in buffer.iter_mut() {
+        unsafe { ptr::write_volatile(byte, 0xa5) };
+    }
+    HANDLER_SP.store(sp, Ordering::SeqCst);
+}
+
+#[entry]
+fn main() -> ! {
+    unsafe {
+        mie::set_msoft();
+        mstatus::set_mie();
+        ptr::write_volatile(MSIP, 1);
+    }
+
+    // up to 1 s at the 10 MHz timebase of `virt`
+    let deadline = unsafe { ptr::read_volatile(MTIME) } + 10_000_000;
+    let sp = loop {
+        let sp = HANDLER_SP.load(Ordering::SeqCst);
+        if sp != 0 {
+            break sp;
+        }
+        if unsafe { ptr::read_volatile(MTIME) } >= deadline {
+            exit(FAIL, 1);
+        }
+    };
+
+    let stack = unsafe {
+        &_sinterrupt_stack as *const u8 as usize..&_interrupt_stack_start as *const u8 as usize
+    };
+    if !stack.contains(&sp) {
+        exit(FAIL, 2);
+    }
+    let data = unsafe { ptr::addr_of!(DATA) };
+    for i in 0..256 {
+        if unsafe { ptr::read_volatile(&(*data)[i]) } != PATTERN {
+            exit(FAIL, 3);
+        }
+    }
+
+    exit(PASS, 0)
+}
@@ -0,0 +1,18 @@
+/* Memory layout of the QEMU `virt` machine with `.data` right below the stack, used by the
+   `interrupt_stack` example, see check-qemu.sh */
+MEMORY
+{
+  RAM : ORIGIN = 0x80000000, LENGTH = 8M
+  DATA : ORIGIN = 0x80800000, LENGTH = 4K
+  STACK : ORIGIN = 0x80801000, LENGTH = 4K
+}
+
+REGION_ALIAS("REGION_TEXT", RAM);
+REGION_ALIAS("REGION_RODATA", RAM);
+REGION_ALIAS("REGION_DATA", DATA);
+REGION_ALIAS("REGION_BSS", RAM);
+REGION_ALIAS("REGION_HEAP", RAM);
+REGION_ALIAS("REGION_STACK", STACK);
+
+/* room for the 8K buffer of the handler */
+_interrupt_stack_size = 16K;
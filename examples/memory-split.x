@@ -0,0 +1,14 @@
+/* Memory layout with `.rodata` in another region than `.text`, see check-features.sh */
+MEMORY
+{
+  RAM : ORIGIN = 0x80000000, LENGTH = 16K
+  FLASH : ORIGIN = 0x20000000, LENGTH = 1M
+  ROM : ORIGIN = 0x21000000, LENGTH = 1M
+}
+
+REGION_ALIAS("REGION_TEXT", FLASH);
+REGION_ALIAS("REGION_RODATA", ROM);
+REGION_ALIAS("REGION_DATA", RAM);
+REGION_ALIAS("REGION_BSS", RAM);
+REGION_ALIAS("REGION_HEAP", RAM);
+REGION_ALIAS("REGION_STACK", RAM);
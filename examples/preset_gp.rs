@@ -0,0 +1,69 @@
+//! Checks on the QEMU `virt` machine that `_start` keeps a `gp` set up by a previous boot stage
+//! with the `preserve-gp` feature, and loads `__global_pointer$` without it, see check-qemu.sh.
+//! The first boot plays the previous stage: it sets `gp` to another value and jumps to `_start`.
+//! The result is reported through the `sifive_test` device, which sets the exit status of QEMU.
+
+#![no_std]
+#![no_main]
+
+extern crate panic_halt;
+extern crate riscv_rt;
+
+use core::arch::asm;
+use core::mem::MaybeUninit;
+use core::ptr;
+
+use riscv_rt::{entry, jump_to};
+
+const TEST: *mut u32 = 0x0010_0000 as *mut u32;
+
+const PASS: u32 = 0x5555;
+const FAIL: u32 = 0x3333;
+
+/// `gp` set by the first boot, outside of the statics that `_start` initializes again
+#[link_section = ".uninit.PRESET_GP"]
+static mut PRESET_GP: MaybeUninit<usize> = MaybeUninit::uninit();
+
+extern "C" {
+    fn _start();
+    #[link_name = "__global_pointer$"]
+    static GLOBAL_POINTER: u8;
+}
+
+fn exit(code: u32, reason: u32) -> ! {
+    unsafe { ptr::write_volatile(TEST, code | reason << 16) };
+    loop {}
+}
+
+#[entry]
+fn main(hartid: usize) -> ! {
+    let gp: usize;
+    unsafe { asm!("mv {0}, gp", out(reg) gp) };
+    let global_pointer = unsafe { ptr::addr_of!(GLOBAL_POINTER) as usize };
+
+    // QEMU starts with zeroed RAM
+    let preset = unsafe { PRESET_GP.assume_init() };
+    if preset == 0 {
+        if gp != global_pointer {
+            exit(FAIL, 1);
+        }
+        // any value that differs from `__global_pointer$`, nothing uses `gp` until it is checked
+        let preset = global_pointer + 16;
+        unsafe {
+            PRESET_GP = MaybeUninit::new(preset);
+            asm!("mv gp, {0}", in(reg) preset);
+            jump_to(_start as usize, hartid, 0)
+        }
+    }
+
+    let expected = if cfg!(feature = "preserve-gp") {
+        preset
+    } else {
+        global_pointer
+    };
+    if gp == expected {
+        exit(PASS, 0)
+    } else {
+        exit(FAIL, 2)
+    }
+}
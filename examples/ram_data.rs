@@ -0,0 +1,49 @@
+//! Checks on the QEMU `virt` machine, which loads the whole image into RAM, that the `ram` feature
+//! links `.data` in place (`_sidata == _sdata`) and that the initial values set by the loader are
+//! seen by `main`, although `start_rust` skips the copy. The result is reported through the
+//! `sifive_test` device, which sets the exit status of QEMU.
+
+#![no_std]
+#![no_main]
+
+extern crate panic_halt;
+extern crate riscv_rt;
+
+use core::arch::asm;
+use core::ptr;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use riscv_rt::entry;
+
+const TEST: *mut u32 = 0x0010_0000 as *mut u32;
+
+const PASS: u32 = 0x5555;
+const FAIL: u32 = 0x3333;
+
+static mut WORDS: [u32; 4] = [0x0123_4567, 0x89ab_cdef, 0xdead_beef, 0x5555_aaaa];
+static COUNTER: AtomicU32 = AtomicU32::new(42);
+
+fn exit(code: u32, reason: u32) -> ! {
+    unsafe { ptr::write_volatile(TEST, code | reason << 16) };
+    loop {}
+}
+
+#[entry]
+fn main() -> ! {
+    // loaded by the assembly, the compiler assumes that two symbols never have the same address
+    let (sidata, sdata): (usize, usize);
+    unsafe { asm!("la {0}, _sidata", "la {1}, _sdata", out(reg) sidata, out(reg) sdata) };
+    if sidata != sdata {
+        exit(FAIL, 1);
+    }
+
+    let words = unsafe { ptr::read_volatile(ptr::addr_of!(WORDS)) };
+    if words != [0x0123_4567, 0x89ab_cdef, 0xdead_beef, 0x5555_aaaa] {
+        exit(FAIL, 2);
+    }
+    if COUNTER.fetch_add(1, Ordering::SeqCst) != 42 {
+        exit(FAIL, 3);
+    }
+
+    exit(PASS, 0)
+}
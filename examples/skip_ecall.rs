@@ -0,0 +1,63 @@
+//! Checks on the QEMU `virt` machine that `#[exception]` handlers calling `skip_instruction`
+//! resume the trapped code right after the faulting instruction, a 4-byte `ecall` and a
+//! compressed `c.ebreak`. The result is reported through the `sifive_test` device, which sets the
+//! exit status of QEMU.
+
+#![no_std]
+#![no_main]
+
+extern crate panic_halt;
+extern crate riscv_rt;
+
+use core::arch::asm;
+use core::ptr;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use riscv_rt::{entry, exception, skip_instruction, TrapFrame};
+
+const TEST: *mut u32 = 0x0010_0000 as *mut u32;
+
+const PASS: u32 = 0x5555;
+const FAIL: u32 = 0x3333;
+
+/// `a0` of the last `ecall`
+static ECALL_ARGUMENT: AtomicUsize = AtomicUsize::new(0);
+/// Number of `Breakpoint` exceptions
+static BREAKPOINTS: AtomicUsize = AtomicUsize::new(0);
+
+fn exit(code: u32, reason: u32) -> ! {
+    unsafe { ptr::write_volatile(TEST, code | reason << 16) };
+    loop {}
+}
+
+#[exception(MachineEnvCall)]
+fn ecall(trap_frame: &TrapFrame) {
+    ECALL_ARGUMENT.store(trap_frame.a0, Ordering::SeqCst);
+    unsafe { skip_instruction() };
+}
+
+#[exception(Breakpoint)]
+fn breakpoint(_trap_frame: &TrapFrame) {
+    BREAKPOINTS.fetch_add(1, Ordering::SeqCst);
+    unsafe { skip_instruction() };
+}
+
+#[entry]
+fn main() -> ! {
+    // `resumed` is only set if the instruction following the faulting one runs: skipping 4 bytes
+    // after `c.ebreak` jumps over the compressed `li`, skipping 2 bytes after `ecall` resumes at
+    // its upper half, an illegal instruction
+    let resumed: usize;
+    unsafe { asm!("li {0}, 0", "ecall", "li {0}, 1", out(reg) resumed, in("a0") 7) };
+    if resumed != 1 || ECALL_ARGUMENT.load(Ordering::SeqCst) != 7 {
+        exit(FAIL, 1);
+    }
+
+    let resumed: usize;
+    unsafe { asm!("li {0}, 0", "c.ebreak", "li {0}, 1", out(reg) resumed) };
+    if resumed != 1 || BREAKPOINTS.load(Ordering::SeqCst) != 1 {
+        exit(FAIL, 2);
+    }
+
+    exit(PASS, 0)
+}
@@ -0,0 +1,145 @@
+//! Checks on the QEMU `virt` machine that `MachineTimer` is entered through its `v-trap`
+//! trampoline, and compares the entry latency of `MachineSoft` through its trampoline and through
+//! `_start_trap`, see check-qemu.sh. The latencies are printed on the UART, the result is reported
+//! through the `sifive_test` device, which sets the exit status of QEMU.
+
+#![no_std]
+#![no_main]
+
+extern crate panic_halt;
+extern crate riscv;
+extern crate riscv_rt;
+
+use core::arch::asm;
+use core::fmt::{self, Write};
+use core::ptr;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use riscv::register::{mcycle, mie, mstatus, mtvec};
+use riscv_rt::{entry, set_trap_mode, TrapMode};
+
+const MSIP: *mut u32 = 0x0200_0000 as *mut u32;
+const MTIMECMP: *mut u64 = 0x0200_4000 as *mut u64;
+const MTIME: *const u64 = 0x0200_bff8 as *const u64;
+const UART: *mut u8 = 0x1000_0000 as *mut u8;
+const TEST: *mut u32 = 0x0010_0000 as *mut u32;
+
+const PASS: u32 = 0x5555;
+const FAIL: u32 = 0x3333;
+
+/// Return address of the last handler call, 0 until an interrupt is taken
+static RETURN_ADDRESS: AtomicUsize = AtomicUsize::new(0);
+/// `mcycle` at the entry of the last `MachineSoft` call
+static ENTRY_CYCLE: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" {
+    fn _vector_table();
+    fn _vtrap_MachineSoft();
+    fn _vtrap_MachineTimer();
+}
+
+struct Uart;
+
+impl Write for Uart {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            unsafe { ptr::write_volatile(UART, byte) };
+        }
+        Ok(())
+    }
+}
+
+fn exit(code: u32, reason: u32) -> ! {
+    unsafe { ptr::write_volatile(TEST, code | reason << 16) };
+    loop {}
+}
+
+/// Whether `ra` returns into `trampoline`, i.e. the handler was not called by `_start_trap_rust`
+fn called_by(ra: usize, trampoline: unsafe extern "C" fn()) -> bool {
+    let trampoline = trampoline as usize;
+    ra > trampoline && ra - trampoline < 0x100
+}
+
+#[export_name = "MachineSoft"]
+fn machine_soft() {
+    // first, before any call of this handler overwrites `ra`
+    let ra: usize;
+    unsafe { asm!("mv {0}, ra", out(reg) ra) };
+    let cycle = mcycle::read();
+    unsafe { ptr::write_volatile(MSIP, 0) };
+    ENTRY_CYCLE.store(cycle, Ordering::SeqCst);
+    RETURN_ADDRESS.store(ra, Ordering::SeqCst);
+}
+
+#[export_name = "MachineTimer"]
+fn machine_timer() {
+    let ra: usize;
+    unsafe { asm!("mv {0}, ra", out(reg) ra) };
+    unsafe { ptr::write_volatile(MTIMECMP, u64::MAX) };
+    RETURN_ADDRESS.store(ra, Ordering::SeqCst);
+}
+
+/// Waits up to 1 s for an interrupt handler call and returns its return address.
+fn wait_for_interrupt() -> usize {
+    let deadline = unsafe { ptr::read_volatile(MTIME) } + 10_000_000;
+    while unsafe { ptr::read_volatile(MTIME) } < deadline {
+        let ra = RETURN_ADDRESS.swap(0, Ordering::SeqCst);
+        if ra != 0 {
+            return ra;
+        }
+    }
+    exit(FAIL, 1)
+}
+
+/// Raises `MachineSoft` and returns the cycles until its handler is entered and its return
+/// address.
+fn soft_latency() -> (usize, usize) {
+    let start = mcycle::read();
+    unsafe { ptr::write_volatile(MSIP, 1) };
+    let ra = wait_for_interrupt();
+    (ENTRY_CYCLE.load(Ordering::SeqCst).wrapping_sub(start), ra)
+}
+
+#[entry]
+fn main() -> ! {
+    if mtvec::read().bits() != _vector_table as usize | 1 {
+        exit(FAIL, 2);
+    }
+
+    unsafe {
+        // 1 ms at the 10 MHz timebase of `virt`
+        ptr::write_volatile(MTIMECMP, ptr::read_volatile(MTIME) + 10_000);
+        mie::set_mtimer();
+        mie::set_msoft();
+        mstatus::set_mie();
+    }
+
+    if !called_by(wait_for_interrupt(), _vtrap_MachineTimer) {
+        exit(FAIL, 3);
+    }
+
+    let (vectored, ra) = soft_latency();
+    if !called_by(ra, _vtrap_MachineSoft) {
+        exit(FAIL, 4);
+    }
+    if !unsafe { set_trap_mode(TrapMode::Direct) } {
+        exit(FAIL, 5);
+    }
+    let (direct, ra) = soft_latency();
+    if called_by(ra, _vtrap_MachineSoft) {
+        exit(FAIL, 6);
+    }
+
+    writeln!(
+        Uart,
+        "MachineSoft entry: {} cycles through v-trap, {} cycles through _start_trap",
+        vectored, direct
+    )
+    .ok();
+
+    if vectored < direct {
+        exit(PASS, 0)
+    } else {
+        exit(FAIL, 7)
+    }
+}
@@ -1,8 +1,52 @@
+/* Memory regions generated from RISCV_RT_RAM_* and RISCV_RT_FLASH_* by build.rs, if any */
+$ENV_MEMORY_X
+
 PROVIDE(_stext = ORIGIN(REGION_TEXT));
 PROVIDE(_stack_start = ORIGIN(REGION_STACK) + LENGTH(REGION_STACK));
-PROVIDE(_max_hart_id = 0);
-PROVIDE(_hart_stack_size = 2K);
-PROVIDE(_heap_size = 0);
+PROVIDE(_max_hart_id = $MAX_HART_ID);
+PROVIDE(_hart_stack_size = $HART_STACK_SIZE);
+PROVIDE(_heap_size = $HEAP_SIZE);
+PROVIDE(_emergency_stack_size = 256);
+PROVIDE(_interrupt_stack_size = 1K);
+PROVIDE(_flash_block_size = 4);
+PROVIDE(_hart_local_size = 0);
+PROVIDE(_boot_hartid = 0);
+PROVIDE(_clint_base = 0x2000000);
+PROVIDE(_stack_init_pattern = 0);
+PROVIDE(_cache_block_size = 64);
+
+/* Keep the layout symbols in the symbol table even if the program does not use them, so that
+   debuggers can resolve them (e.g. `print &_stack_start` in GDB) */
+EXTERN(_stext _stack_start _max_hart_id _hart_stack_size _heap_size);
+
+/* Fields of `mstatus` set by `start_rust` before `main`: the bits of `_mstatus_init_mask`
+   are replaced with the ones of `_mstatus_init_value`. A zero mask keeps the reset value. */
+PROVIDE(_mstatus_init_mask = 0);
+PROVIDE(_mstatus_init_value = 0);
+
+/* Same for `menvcfg` (`senvcfg` with the `s-mode` feature). On RV32, `_envcfgh_init_mask` and
+   `_envcfgh_init_value` cover `menvcfgh`. A zero mask leaves the CSR untouched. */
+PROVIDE(_envcfg_init_mask = 0);
+PROVIDE(_envcfg_init_value = 0);
+PROVIDE(_envcfgh_init_mask = 0);
+PROVIDE(_envcfgh_init_value = 0);
+
+/* Bits of `mie` (`sie` with the `s-mode` feature) set after `_setup_interrupts` */
+PROVIDE(_mie_init = 0);
+
+/* Non-zero if `_start` keeps a `gp` set up by a previous boot stage */
+#if defined(PRESERVE_GP)
+_preserve_gp = 1;
+#else
+_preserve_gp = 0;
+#endif
+
+/* Non-zero if `_start` fills the stacks with `_stack_init_pattern` (see the `init-stack` feature) */
+#if defined(INIT_STACK)
+_init_stack = 1;
+#else
+_init_stack = 0;
+#endif
 
 PROVIDE(UserSoft = DefaultHandler);
 PROVIDE(SupervisorSoft = DefaultHandler);
@@ -14,9 +58,30 @@ PROVIDE(UserExternal = DefaultHandler);
 PROVIDE(SupervisorExternal = DefaultHandler);
 PROVIDE(MachineExternal = DefaultHandler);
 
+/* Reserved interrupt codes, only used by non-standard cores */
+PROVIDE(CoreInterrupt2 = DefaultHandler);
+PROVIDE(CoreInterrupt6 = DefaultHandler);
+PROVIDE(CoreInterrupt10 = DefaultHandler);
+
 PROVIDE(DefaultHandler = DefaultInterruptHandler);
 PROVIDE(ExceptionHandler = DefaultExceptionHandler);
 
+/* Handlers of the standard exception codes, see `#[exception]` */
+PROVIDE(InstructionMisaligned = ExceptionHandler);
+PROVIDE(InstructionFault = ExceptionHandler);
+PROVIDE(IllegalInstruction = ExceptionHandler);
+PROVIDE(Breakpoint = ExceptionHandler);
+PROVIDE(LoadMisaligned = ExceptionHandler);
+PROVIDE(LoadFault = ExceptionHandler);
+PROVIDE(StoreMisaligned = ExceptionHandler);
+PROVIDE(StoreFault = ExceptionHandler);
+PROVIDE(UserEnvCall = ExceptionHandler);
+PROVIDE(SupervisorEnvCall = ExceptionHandler);
+PROVIDE(MachineEnvCall = ExceptionHandler);
+PROVIDE(InstructionPageFault = ExceptionHandler);
+PROVIDE(LoadPageFault = ExceptionHandler);
+PROVIDE(StorePageFault = ExceptionHandler);
+
 /* # Pre-initialization function */
 /* If the user overrides this using the `#[pre_init]` attribute or by creating a `__pre_init` function,
    then the function this points to will be called before the RAM is initialized. */
@@ -25,6 +90,32 @@ PROVIDE(__pre_init = default_pre_init);
 /* A PAC/HAL defined routine that should initialize custom interrupt controller if needed. */
 PROVIDE(_setup_interrupts = default_setup_interrupts);
 
+/* # Trap vector verification hook
+   fn _setup_failed();
+
+   Called by `verify_tvec` when `mtvec`/`stvec` does not hold the written value.
+*/
+PROVIDE(_setup_failed = default_setup_failed);
+
+/* # Boot self-test failure hook
+   fn _selftest_failed();
+
+   Called by `start_rust` when the trap path self-test of the `boot-selftest` feature fails.
+*/
+#if defined(BOOT_SELFTEST)
+PROVIDE(_selftest_failed = default_selftest_failed);
+#endif
+
+/* # RAM test failure hook
+   fn _ram_test_failed(addr: usize);
+
+   Called by `start_rust` with the first faulty word found by the `ram-test` feature.
+*/
+#if defined(RAM_TEST)
+PROVIDE(_ram_test_failed = default_ram_test_failed);
+PROVIDE(_ram_test_background = 0);
+#endif
+
 /* # Multi-processing hook function
    fn _mp_hook() -> bool;
 
@@ -34,11 +125,72 @@ PROVIDE(_setup_interrupts = default_setup_interrupts);
 */
 PROVIDE(_mp_hook = default_mp_hook);
 
+/* # Excess hart hook
+   extern "C" fn _excess_hart(hartid: usize) -> !;
+
+   Jumped to by `_start` on the harts whose id is greater than `_max_hart_id`, without a stack.
+*/
+PROVIDE(_excess_hart = abort);
+
+/* # Abort
+   extern "C" fn abort() -> !;
+
+   Yields to an `abort` defined by the application or another library (e.g. a libc shim).
+   The `no-default-abort` feature omits it entirely.
+*/
+#if !defined(NO_DEFAULT_ABORT)
+PROVIDE(abort = default_abort);
+#endif
+
+/* # Watchdog feed hook
+   fn _watchdog_feed();
+
+   This function is called from the busy-loops of the crate (default handlers and parked harts),
+   so that platforms with a hardware watchdog can keep it fed.
+*/
+PROVIDE(_watchdog_feed = default_watchdog_feed);
+
 /* # Start trap function override
   By default uses the riscv crates default trap handler
   but by providing the `_start_trap` symbol external crates can override.
 */
+#if defined(STACK_OVERFLOW_HOOK)
+PROVIDE(_start_trap = stack_guard_start_trap);
+#else
+#if defined(FP_TRAP_SAVE)
+PROVIDE(_start_trap = fp_save_start_trap);
+#else
+#if defined(INTERRUPT_STACK)
+PROVIDE(_start_trap = interrupt_stack_start_trap);
+#else
+#if defined(FULL_TRAP_FRAME)
+PROVIDE(_start_trap = full_frame_start_trap);
+#else
 PROVIDE(_start_trap = default_start_trap);
+#endif
+#endif
+#endif
+#endif
+
+/* # Stack overflow hook
+   extern "C" fn _stack_overflow(sp: usize, fault_addr: usize) -> !;
+
+   Called on the emergency stack by the trap entry of the `stack-overflow-hook` feature.
+*/
+#if defined(STACK_OVERFLOW_HOOK)
+PROVIDE(_stack_overflow = default_stack_overflow);
+#endif
+
+/* # Nesting limit hook
+   extern "C" fn _max_nesting_exceeded(depth: usize);
+
+   Called by the `#[interrupt_handler]` wrappers of the `nesting-limit` feature when the nesting
+   depth exceeds `_max_nesting_depth`. The handler then runs with interrupts disabled.
+*/
+#if defined(NESTING_LIMIT)
+PROVIDE(_max_nesting_depth = 4);
+PROVIDE(_max_nesting_exceeded = default_max_nesting_exceeded);
+#endif
 
 SECTIONS
 {
@@ -55,19 +207,25 @@ SECTIONS
     KEEP(*(.init));
     KEEP(*(.init.rust));
     . = ALIGN(4);
+    _strap = .;
     *(.trap);
+    /* trap entries only, `backtrace` stops at return addresses in _strap.._etrap */
+    _etrap = .;
     *(.trap.rust);
 
     #if defined(CLIC)
-    . = ALIGN(1024);
+#if !defined(VECTORS_REGION)
+    . = ALIGN($VECTOR_ALIGN);
     KEEP(*(.text.interrupt_vector));
+#endif
     . = ALIGN(1024);
     KEEP(*(.text.nxti_trap_handler));
     #endif
 
     *(.text .text.*);
 
-
+    /* end .text on a flash erase block boundary, see `_flash_block_size` */
+    . = ALIGN(_flash_block_size);
   } > REGION_TEXT
 
   .rodata : ALIGN(4)
@@ -75,11 +233,62 @@ SECTIONS
     *(.srodata .srodata.*);
     *(.rodata .rodata.*);
 
+    /* Values of `_envcfg_init_mask` and `_envcfg_init_value`, read by `start_rust`: on RV64,
+       bits such as 63 (`STCE`) do not fit the PC-relative relocations of symbol addresses */
+    . = ALIGN(8);
+    _envcfg_init = .;
+    QUAD(_envcfg_init_mask);
+    QUAD(_envcfg_init_value);
+    /* `_envcfgh_init_mask` and `_envcfgh_init_value`, stored for the same reason as the other
+       masks: the compiler assumes that the address of a symbol is never 0 */
+    _envcfgh_init = .;
+    LONG(_envcfgh_init_mask);
+    LONG(_envcfgh_init_value);
+    /* size of the `#[ramfunc]` code, `start_rust` only runs `fence.i` after copying some */
+    _ramfunc_size = .;
+    LONG(_eramfunc - _sramfunc);
+    /* `_hart_local_size`, which `hart_local` compares with the size of its type */
+    _hart_local_size_word = .;
+    LONG(_hart_local_size);
+    /* `_hart_stack_size`, which `validate_layout` checks for 0 */
+    _hart_stack_size_word = .;
+    LONG(_hart_stack_size);
+
+#if defined(RUN_INIT_ARRAY)
+    /* C/C++ static constructors and destructors, see the `run-init-array` feature.
+       `.preinit_array` must directly precede `.init_array`, both are run as one array. */
+    . = ALIGN(8);
+    __preinit_array_start = .;
+    KEEP(*(.preinit_array));
+    __preinit_array_end = .;
+    __init_array_start = .;
+    KEEP(*(SORT_BY_INIT_PRIORITY(.init_array.*) SORT_BY_INIT_PRIORITY(.ctors.*)));
+    KEEP(*(.init_array .ctors));
+    __init_array_end = .;
+    __fini_array_start = .;
+    KEEP(*(SORT_BY_INIT_PRIORITY(.fini_array.*) SORT_BY_INIT_PRIORITY(.dtors.*)));
+    KEEP(*(.fini_array .dtors));
+    __fini_array_end = .;
+#endif
+
+#if defined(IMAGE_CRC)
+    /* CRC-32 of the image, see the `image-crc` feature. The field is not part of the range. */
+    . = ALIGN(4);
+    _image_crc_rodata_end = .;
+    KEEP(*(.image_crc));
+#endif
+
     /* 4-byte align the end (VMA) of this section.
        This is required by LLD to ensure the LMA of the following .data
        section will have the correct alignment. */
     . = ALIGN(4);
+    /* start the LMA of .data on a flash erase block boundary */
+    . = ALIGN(_flash_block_size);
+#if defined(SINGLE_LOAD_SEGMENT)
+  } > REGION_TEXT
+#else
   } > REGION_RODATA
+#endif
 
   .data : ALIGN(4)
   {
@@ -89,18 +298,75 @@ SECTIONS
     PROVIDE(__global_pointer$ = . + 0x800);
     *(.sdata .sdata.* .sdata2 .sdata2.*);
     *(.data .data.*);
+    /* functions executed from RAM, see `#[ramfunc]` */
+    . = ALIGN(4);
+    _sramfunc = .;
+    *(.ramfunc .ramfunc.*);
+    _eramfunc = .;
     . = ALIGN(4);
     _edata = .;
+#if defined(RAM)
+  /* loaded at its final address, `start_rust` does not copy it */
+  } > REGION_DATA
+#else
+#if defined(SINGLE_LOAD_SEGMENT)
+  } > REGION_DATA AT > REGION_TEXT
+#else
   } > REGION_DATA AT > REGION_RODATA
+#endif
+#endif
 
   .bss (NOLOAD) :
   {
     _sbss = .;
     *(.sbss .sbss.* .bss .bss.*);
+    /* per-hart private data, see `hart_local` */
+    . = ALIGN(16);
+    _shart_local = .;
+    . += (_max_hart_id + 1) * _hart_local_size;
     . = ALIGN(4);
     _ebss = .;
   } > REGION_BSS
 
+  /* statics that are neither initialized nor zeroed at boot */
+  .uninit (NOLOAD) : ALIGN(4)
+  {
+    *(.uninit .uninit.*);
+    . = ALIGN(4);
+  } > REGION_BSS
+
+#if defined(EMERGENCY_STACK)
+  /* per-hart stacks for trap entry code that cannot trust `sp`, see `mscratch` */
+  .emergency_stack (NOLOAD) : ALIGN(16)
+  {
+    _semergency_stack = .;
+    . += (_max_hart_id + 1) * _emergency_stack_size;
+    _emergency_stack = .;
+  } > REGION_BSS
+
+#endif
+#if defined(INTERRUPT_STACK)
+  /* per-hart stacks the trap entry switches to, see `mscratch` */
+  .interrupt_stack (NOLOAD) : ALIGN(16)
+  {
+    _sinterrupt_stack = .;
+    . += (_max_hart_id + 1) * _interrupt_stack_size;
+    _interrupt_stack_start = .;
+  } > REGION_BSS
+
+#endif
+
+#if defined(PAYLOAD)
+  /* separately linked payload (e.g. an application image carried by a bootloader) */
+  .payload : ALIGN(4)
+  {
+    _spayload = .;
+    KEEP(*(.payload .payload.*));
+    . = ALIGN(4);
+    _epayload = .;
+  } > REGION_PAYLOAD
+
+#endif
   /* fictitious region that represents the memory available for the heap */
   .heap (NOLOAD) :
   {
@@ -110,13 +376,46 @@ SECTIONS
     _eheap = .;
   } > REGION_HEAP
 
-  /* fictitious region that represents the memory available for the stack */
+  /* region that represents the memory available for the stacks of all harts */
+#if defined(STACK_SECTIONS)
+  /* one section per hart below `_stack_start`, see the `stack-sections` feature. With
+     `paint-stack`, only the hart sections are painted. */
   .stack (NOLOAD) :
   {
     _estack = .;
+    . = ABSOLUTE(_stack_start - (_max_hart_id + 1) * _hart_stack_size);
+  } > REGION_STACK
+
+$HART_STACK_SECTIONS
+  _sstack = ABSOLUTE(_stack_start);
+#else
+#if defined(PAINT_STACK)
+  /* loadable, so that the loader fills the whole region with the paint pattern */
+  .stack :
+  {
+    _estack = .;
+    FILL(0xCCCCCCCC);
+    LONG(0xCCCCCCCC);
+#else
+  .stack (NOLOAD) :
+  {
+    _estack = .;
+#endif
     . = ABSOLUTE(_stack_start);
     _sstack = .;
   } > REGION_STACK
+#endif
+
+#if defined(CLIC)
+#if defined(VECTORS_REGION)
+  /* CLIC vector table (`mtvt`) pinned to a dedicated region */
+  .vectors : ALIGN($VECTOR_ALIGN)
+  {
+    KEEP(*(.text.interrupt_vector));
+  } > REGION_VECTORS
+
+#endif
+#endif
 
   /* fake output .got section */
   /* Dynamic relocations are unsupported. This section is only used to detect
@@ -127,10 +426,26 @@ SECTIONS
     KEEP(*(.got .got.*));
   }
 
+  /* references of `require_handlers!`, fail the link if a required handler is missing */
+  .required_handlers (INFO) : { KEEP(*(.riscv_rt.required_handlers)); }
+
+  /* records of `#[interrupt_handler]`, for offline analysis, see `handler_map` */
+  .handler_map (INFO) : { KEEP(*(.riscv_rt.handler_map)); }
+
   .eh_frame (INFO) : { KEEP(*(.eh_frame)) }
   .eh_frame_hdr (INFO) : { *(.eh_frame_hdr) }
 }
 
+/* Sizes of the output sections, see `sizes()` */
+_text_size = SIZEOF(.text);
+_rodata_size = SIZEOF(.rodata);
+_data_size = SIZEOF(.data);
+_bss_size = SIZEOF(.bss);
+
+#if defined(IMAGE_CRC)
+_image_crc_rodata_start = ADDR(.rodata);
+#endif
+
 /* Do not exceed this mark in the error messages above                                    | */
 ASSERT(ORIGIN(REGION_TEXT) % 4 == 0, "
 ERROR(riscv-rt): the start of the REGION_TEXT must be 4-byte aligned");
@@ -150,6 +465,12 @@ ERROR(riscv-rt): the start of the REGION_TEXT must be 4-byte aligned");
 ASSERT(ORIGIN(REGION_STACK) % 4 == 0, "
 ERROR(riscv-rt): the start of the REGION_STACK must be 4-byte aligned");
 
+ASSERT(_flash_block_size >= 4 && (_flash_block_size & (_flash_block_size - 1)) == 0, "
+ERROR(riscv-rt): `_flash_block_size` must be a power of 2, at least 4");
+
+ASSERT(_cache_block_size > 0 && (_cache_block_size & (_cache_block_size - 1)) == 0, "
+ERROR(riscv-rt): `_cache_block_size` must be a power of 2");
+
 ASSERT(_stext % 4 == 0, "
 ERROR(riscv-rt): `_stext` must be 4-byte aligned");
 
@@ -165,14 +486,86 @@ BUG(riscv-rt): .bss is not 4-byte aligned");
 ASSERT(_sheap % 4 == 0, "
 BUG(riscv-rt): start of .heap is not 4-byte aligned");
 
+ASSERT(_eheap <= ORIGIN(REGION_HEAP) + LENGTH(REGION_HEAP), "
+ERROR(riscv-rt): the heap does not fit into REGION_HEAP.
+Consider reducing `_heap_size` or `RISCV_RT_HEAP_SIZE`.");
+
 ASSERT(_stext + SIZEOF(.text) < ORIGIN(REGION_TEXT) + LENGTH(REGION_TEXT), "
 ERROR(riscv-rt): The .text section must be placed inside the REGION_TEXT region.
 Set _stext to an address smaller than 'ORIGIN(REGION_TEXT) + LENGTH(REGION_TEXT)'");
 
-ASSERT(SIZEOF(.stack) > (_max_hart_id + 1) * _hart_stack_size, "
+ASSERT(_sstack - _estack > (_max_hart_id + 1) * _hart_stack_size, "
 ERROR(riscv-rt): .stack section is too small for allocating stacks for all the harts.
 Consider changing `_max_hart_id` or `_hart_stack_size`.");
 
+ASSERT(_boot_hartid <= _max_hart_id, "
+ERROR(riscv-rt): `_boot_hartid` is greater than `_max_hart_id`, this hart would be parked
+by `_start` and no hart would initialize the memory.");
+
+/* Defined by `#[entry] fn main<const N: usize>()`, whose `N` is the build-time hart count */
+ASSERT(!DEFINED(__riscv_rt_const_hart_count) || _max_hart_id == $MAX_HART_ID, "
+ERROR(riscv-rt): the const generic of the `#[entry]` function is bound to the build-time
+RISCV_RT_MAX_HART_ID + 1. Set it instead of redefining `_max_hart_id`.");
+
+ASSERT(_hart_local_size % 16 == 0, "
+ERROR(riscv-rt): `_hart_local_size` must be a multiple of 16");
+
+#if defined(EMERGENCY_STACK)
+ASSERT(_emergency_stack_size % 16 == 0, "
+ERROR(riscv-rt): `_emergency_stack_size` must be a multiple of 16");
+
+#endif
+#if defined(INTERRUPT_STACK)
+ASSERT(_interrupt_stack_size % 16 == 0, "
+ERROR(riscv-rt): `_interrupt_stack_size` must be a multiple of 16");
+
+#endif
+#if defined(BOOTROM)
+ASSERT((_sdata >= ORIGIN(REGION_TEXT) + LENGTH(REGION_TEXT) || _edata <= ORIGIN(REGION_TEXT)) &&
+       (_sbss >= ORIGIN(REGION_TEXT) + LENGTH(REGION_TEXT) || _ebss <= ORIGIN(REGION_TEXT)) &&
+       (ADDR(.uninit) >= ORIGIN(REGION_TEXT) + LENGTH(REGION_TEXT) ||
+        ADDR(.uninit) + SIZEOF(.uninit) <= ORIGIN(REGION_TEXT)) &&
+       (_sheap >= ORIGIN(REGION_TEXT) + LENGTH(REGION_TEXT) || _eheap <= ORIGIN(REGION_TEXT)) &&
+       (_estack >= ORIGIN(REGION_TEXT) + LENGTH(REGION_TEXT) || _sstack <= ORIGIN(REGION_TEXT)), "
+ERROR(riscv-rt): with the `bootrom` feature, REGION_TEXT is read-only.
+.data, .bss, .uninit, .heap and .stack must be placed outside of it.");
+
+#endif
+#if defined(INIT_STACK)
+ASSERT(_estack % 4 == 0 && _sstack % 4 == 0 && _hart_stack_size % 4 == 0, "
+ERROR(riscv-rt): the `init-stack` feature requires a 4-byte aligned .stack section
+and a `_hart_stack_size` multiple of 4");
+
+#endif
+#if defined(STACK_SECTIONS)
+ASSERT(_max_hart_id == $MAX_HART_ID, "
+ERROR(riscv-rt): the `stack-sections` feature emits one section per hart up to the
+build-time RISCV_RT_MAX_HART_ID. Set it instead of redefining `_max_hart_id`.");
+
+#endif
+#if defined(NESTING_LIMIT)
+ASSERT(_max_hart_id <= $MAX_HART_ID, "
+ERROR(riscv-rt): the `nesting-limit` feature keeps one depth counter per hart up to the
+build-time RISCV_RT_MAX_HART_ID. Raise it instead of increasing `_max_hart_id`.");
+
+#endif
+#if defined(RAM)
+ASSERT(_sidata == _sdata, "
+BUG(riscv-rt): the LMA of .data differs from its VMA with the `ram` feature");
+
+#endif
+#if defined(RAM_TEST)
+ASSERT((_stack_start - (_max_hart_id + 1) * _hart_stack_size >= _edata || _stack_start <= _sdata) &&
+       (_stack_start - (_max_hart_id + 1) * _hart_stack_size >= _ebss || _stack_start <= _sbss), "
+ERROR(riscv-rt): the `ram-test` feature runs on the stack, which overlaps .data or .bss");
+
+#endif
+#if defined(SINGLE_LOAD_SEGMENT)
+ASSERT(LOADADDR(.rodata) - (_stext + SIZEOF(.text)) < 4 &&
+       LOADADDR(.data) == LOADADDR(.rodata) + SIZEOF(.rodata), "
+BUG(riscv-rt): .text, .rodata and the LMA of .data are not contiguous");
+#endif
+
 ASSERT(SIZEOF(.got) == 0, "
 .got section detected in the input files. Dynamic relocations are not
 supported. If you are linking to C code compiled using the `gcc` crate
@@ -182,269 +575,281 @@ details.");
 
 /* Do not exceed this mark in the error messages above                                    | */
 
-/* default interrupt handlers */
-PROVIDE(int_0 = DefaultHandler);
-PROVIDE(int_1 = DefaultHandler);
-PROVIDE(int_2 = DefaultHandler);
-PROVIDE(int_3 = DefaultHandler);
-PROVIDE(int_4 = DefaultHandler);
-PROVIDE(int_5 = DefaultHandler);
-PROVIDE(int_6 = DefaultHandler);
-PROVIDE(int_7 = DefaultHandler);
-PROVIDE(int_8 = DefaultHandler);
-PROVIDE(int_9 = DefaultHandler);
-PROVIDE(int_10 = DefaultHandler);
-PROVIDE(int_11 = DefaultHandler);
-PROVIDE(int_12 = DefaultHandler);
-PROVIDE(int_13 = DefaultHandler);
-PROVIDE(int_14 = DefaultHandler);
-PROVIDE(int_15 = DefaultHandler);
-PROVIDE(int_16 = DefaultHandler);
-PROVIDE(int_17 = DefaultHandler);
-PROVIDE(int_18 = DefaultHandler);
-PROVIDE(int_19 = DefaultHandler);
-PROVIDE(int_20 = DefaultHandler);
-PROVIDE(int_21 = DefaultHandler);
-PROVIDE(int_22 = DefaultHandler);
-PROVIDE(int_23 = DefaultHandler);
-PROVIDE(int_24 = DefaultHandler);
-PROVIDE(int_25 = DefaultHandler);
-PROVIDE(int_26 = DefaultHandler);
-PROVIDE(int_27 = DefaultHandler);
-PROVIDE(int_28 = DefaultHandler);
-PROVIDE(int_29 = DefaultHandler);
-PROVIDE(int_30 = DefaultHandler);
-PROVIDE(int_31 = DefaultHandler);
-PROVIDE(int_32 = DefaultHandler);
-PROVIDE(int_33 = DefaultHandler);
-PROVIDE(int_34 = DefaultHandler);
-PROVIDE(int_35 = DefaultHandler);
-PROVIDE(int_36 = DefaultHandler);
-PROVIDE(int_37 = DefaultHandler);
-PROVIDE(int_38 = DefaultHandler);
-PROVIDE(int_39 = DefaultHandler);
-PROVIDE(int_40 = DefaultHandler);
-PROVIDE(int_41 = DefaultHandler);
-PROVIDE(int_42 = DefaultHandler);
-PROVIDE(int_43 = DefaultHandler);
-PROVIDE(int_44 = DefaultHandler);
-PROVIDE(int_45 = DefaultHandler);
-PROVIDE(int_46 = DefaultHandler);
-PROVIDE(int_47 = DefaultHandler);
-PROVIDE(int_48 = DefaultHandler);
-PROVIDE(int_49 = DefaultHandler);
-PROVIDE(int_50 = DefaultHandler);
-PROVIDE(int_51 = DefaultHandler);
-PROVIDE(int_52 = DefaultHandler);
-PROVIDE(int_53 = DefaultHandler);
-PROVIDE(int_54 = DefaultHandler);
-PROVIDE(int_55 = DefaultHandler);
-PROVIDE(int_56 = DefaultHandler);
-PROVIDE(int_57 = DefaultHandler);
-PROVIDE(int_58 = DefaultHandler);
-PROVIDE(int_59 = DefaultHandler);
-PROVIDE(int_60 = DefaultHandler);
-PROVIDE(int_61 = DefaultHandler);
-PROVIDE(int_62 = DefaultHandler);
-PROVIDE(int_63 = DefaultHandler);
-PROVIDE(int_64 = DefaultHandler);
-PROVIDE(int_65 = DefaultHandler);
-PROVIDE(int_66 = DefaultHandler);
-PROVIDE(int_67 = DefaultHandler);
-PROVIDE(int_68 = DefaultHandler);
-PROVIDE(int_69 = DefaultHandler);
-PROVIDE(int_70 = DefaultHandler);
-PROVIDE(int_71 = DefaultHandler);
-PROVIDE(int_72 = DefaultHandler);
-PROVIDE(int_73 = DefaultHandler);
-PROVIDE(int_74 = DefaultHandler);
-PROVIDE(int_75 = DefaultHandler);
-PROVIDE(int_76 = DefaultHandler);
-PROVIDE(int_77 = DefaultHandler);
-PROVIDE(int_78 = DefaultHandler);
-PROVIDE(int_79 = DefaultHandler);
-PROVIDE(int_80 = DefaultHandler);
-PROVIDE(int_81 = DefaultHandler);
-PROVIDE(int_82 = DefaultHandler);
-PROVIDE(int_83 = DefaultHandler);
-PROVIDE(int_84 = DefaultHandler);
-PROVIDE(int_85 = DefaultHandler);
-PROVIDE(int_86 = DefaultHandler);
-PROVIDE(int_87 = DefaultHandler);
-PROVIDE(int_88 = DefaultHandler);
-PROVIDE(int_89 = DefaultHandler);
-PROVIDE(int_90 = DefaultHandler);
-PROVIDE(int_91 = DefaultHandler);
-PROVIDE(int_92 = DefaultHandler);
-PROVIDE(int_93 = DefaultHandler);
-PROVIDE(int_94 = DefaultHandler);
-PROVIDE(int_95 = DefaultHandler);
-PROVIDE(int_96 = DefaultHandler);
-PROVIDE(int_97 = DefaultHandler);
-PROVIDE(int_98 = DefaultHandler);
-PROVIDE(int_99 = DefaultHandler);
-PROVIDE(int_100 = DefaultHandler);
-PROVIDE(int_101 = DefaultHandler);
-PROVIDE(int_102 = DefaultHandler);
-PROVIDE(int_103 = DefaultHandler);
-PROVIDE(int_104 = DefaultHandler);
-PROVIDE(int_105 = DefaultHandler);
-PROVIDE(int_106 = DefaultHandler);
-PROVIDE(int_107 = DefaultHandler);
-PROVIDE(int_108 = DefaultHandler);
-PROVIDE(int_109 = DefaultHandler);
-PROVIDE(int_110 = DefaultHandler);
-PROVIDE(int_111 = DefaultHandler);
-PROVIDE(int_112 = DefaultHandler);
-PROVIDE(int_113 = DefaultHandler);
-PROVIDE(int_114 = DefaultHandler);
-PROVIDE(int_115 = DefaultHandler);
-PROVIDE(int_116 = DefaultHandler);
-PROVIDE(int_117 = DefaultHandler);
-PROVIDE(int_118 = DefaultHandler);
-PROVIDE(int_119 = DefaultHandler);
-PROVIDE(int_120 = DefaultHandler);
-PROVIDE(int_121 = DefaultHandler);
-PROVIDE(int_122 = DefaultHandler);
-PROVIDE(int_123 = DefaultHandler);
-PROVIDE(int_124 = DefaultHandler);
-PROVIDE(int_125 = DefaultHandler);
-PROVIDE(int_126 = DefaultHandler);
-PROVIDE(int_127 = DefaultHandler);
-PROVIDE(int_128 = DefaultHandler);
-PROVIDE(int_129 = DefaultHandler);
-PROVIDE(int_130 = DefaultHandler);
-PROVIDE(int_131 = DefaultHandler);
-PROVIDE(int_132 = DefaultHandler);
-PROVIDE(int_133 = DefaultHandler);
-PROVIDE(int_134 = DefaultHandler);
-PROVIDE(int_135 = DefaultHandler);
-PROVIDE(int_136 = DefaultHandler);
-PROVIDE(int_137 = DefaultHandler);
-PROVIDE(int_138 = DefaultHandler);
-PROVIDE(int_139 = DefaultHandler);
-PROVIDE(int_140 = DefaultHandler);
-PROVIDE(int_141 = DefaultHandler);
-PROVIDE(int_142 = DefaultHandler);
-PROVIDE(int_143 = DefaultHandler);
-PROVIDE(int_144 = DefaultHandler);
-PROVIDE(int_145 = DefaultHandler);
-PROVIDE(int_146 = DefaultHandler);
-PROVIDE(int_147 = DefaultHandler);
-PROVIDE(int_148 = DefaultHandler);
-PROVIDE(int_149 = DefaultHandler);
-PROVIDE(int_150 = DefaultHandler);
-PROVIDE(int_151 = DefaultHandler);
-PROVIDE(int_152 = DefaultHandler);
-PROVIDE(int_153 = DefaultHandler);
-PROVIDE(int_154 = DefaultHandler);
-PROVIDE(int_155 = DefaultHandler);
-PROVIDE(int_156 = DefaultHandler);
-PROVIDE(int_157 = DefaultHandler);
-PROVIDE(int_158 = DefaultHandler);
-PROVIDE(int_159 = DefaultHandler);
-PROVIDE(int_160 = DefaultHandler);
-PROVIDE(int_161 = DefaultHandler);
-PROVIDE(int_162 = DefaultHandler);
-PROVIDE(int_163 = DefaultHandler);
-PROVIDE(int_164 = DefaultHandler);
-PROVIDE(int_165 = DefaultHandler);
-PROVIDE(int_166 = DefaultHandler);
-PROVIDE(int_167 = DefaultHandler);
-PROVIDE(int_168 = DefaultHandler);
-PROVIDE(int_169 = DefaultHandler);
-PROVIDE(int_170 = DefaultHandler);
-PROVIDE(int_171 = DefaultHandler);
-PROVIDE(int_172 = DefaultHandler);
-PROVIDE(int_173 = DefaultHandler);
-PROVIDE(int_174 = DefaultHandler);
-PROVIDE(int_175 = DefaultHandler);
-PROVIDE(int_176 = DefaultHandler);
-PROVIDE(int_177 = DefaultHandler);
-PROVIDE(int_178 = DefaultHandler);
-PROVIDE(int_179 = DefaultHandler);
-PROVIDE(int_180 = DefaultHandler);
-PROVIDE(int_181 = DefaultHandler);
-PROVIDE(int_182 = DefaultHandler);
-PROVIDE(int_183 = DefaultHandler);
-PROVIDE(int_184 = DefaultHandler);
-PROVIDE(int_185 = DefaultHandler);
-PROVIDE(int_186 = DefaultHandler);
-PROVIDE(int_187 = DefaultHandler);
-PROVIDE(int_188 = DefaultHandler);
-PROVIDE(int_189 = DefaultHandler);
-PROVIDE(int_190 = DefaultHandler);
-PROVIDE(int_191 = DefaultHandler);
-PROVIDE(int_192 = DefaultHandler);
-PROVIDE(int_193 = DefaultHandler);
-PROVIDE(int_194 = DefaultHandler);
-PROVIDE(int_195 = DefaultHandler);
-PROVIDE(int_196 = DefaultHandler);
-PROVIDE(int_197 = DefaultHandler);
-PROVIDE(int_198 = DefaultHandler);
-PROVIDE(int_199 = DefaultHandler);
-PROVIDE(int_200 = DefaultHandler);
-PROVIDE(int_201 = DefaultHandler);
-PROVIDE(int_202 = DefaultHandler);
-PROVIDE(int_203 = DefaultHandler);
-PROVIDE(int_204 = DefaultHandler);
-PROVIDE(int_205 = DefaultHandler);
-PROVIDE(int_206 = DefaultHandler);
-PROVIDE(int_207 = DefaultHandler);
-PROVIDE(int_208 = DefaultHandler);
-PROVIDE(int_209 = DefaultHandler);
-PROVIDE(int_210 = DefaultHandler);
-PROVIDE(int_211 = DefaultHandler);
-PROVIDE(int_212 = DefaultHandler);
-PROVIDE(int_213 = DefaultHandler);
-PROVIDE(int_214 = DefaultHandler);
-PROVIDE(int_215 = DefaultHandler);
-PROVIDE(int_216 = DefaultHandler);
-PROVIDE(int_217 = DefaultHandler);
-PROVIDE(int_218 = DefaultHandler);
-PROVIDE(int_219 = DefaultHandler);
-PROVIDE(int_220 = DefaultHandler);
-PROVIDE(int_221 = DefaultHandler);
-PROVIDE(int_222 = DefaultHandler);
-PROVIDE(int_223 = DefaultHandler);
-PROVIDE(int_224 = DefaultHandler);
-PROVIDE(int_225 = DefaultHandler);
-PROVIDE(int_226 = DefaultHandler);
-PROVIDE(int_227 = DefaultHandler);
-PROVIDE(int_228 = DefaultHandler);
-PROVIDE(int_229 = DefaultHandler);
-PROVIDE(int_230 = DefaultHandler);
-PROVIDE(int_231 = DefaultHandler);
-PROVIDE(int_232 = DefaultHandler);
-PROVIDE(int_233 = DefaultHandler);
-PROVIDE(int_234 = DefaultHandler);
-PROVIDE(int_235 = DefaultHandler);
-PROVIDE(int_236 = DefaultHandler);
-PROVIDE(int_237 = DefaultHandler);
-PROVIDE(int_238 = DefaultHandler);
-PROVIDE(int_239 = DefaultHandler);
-PROVIDE(int_240 = DefaultHandler);
-PROVIDE(int_241 = DefaultHandler);
-PROVIDE(int_242 = DefaultHandler);
-PROVIDE(int_243 = DefaultHandler);
-PROVIDE(int_244 = DefaultHandler);
-PROVIDE(int_245 = DefaultHandler);
-PROVIDE(int_246 = DefaultHandler);
-PROVIDE(int_247 = DefaultHandler);
-PROVIDE(int_248 = DefaultHandler);
-PROVIDE(int_249 = DefaultHandler);
-PROVIDE(int_250 = DefaultHandler);
-PROVIDE(int_251 = DefaultHandler);
-PROVIDE(int_252 = DefaultHandler);
-PROVIDE(int_253 = DefaultHandler);
-PROVIDE(int_254 = DefaultHandler);
-PROVIDE(int_255 = DefaultHandler);
-PROVIDE(int_256 = DefaultHandler);
-PROVIDE(int_257 = DefaultHandler);
-PROVIDE(int_258 = DefaultHandler);
-PROVIDE(int_259 = DefaultHandler);
-PROVIDE(int_260 = DefaultHandler);
-PROVIDE(int_261 = DefaultHandler);
-PROVIDE(int_262 = DefaultHandler);
-PROVIDE(int_263 = DefaultHandler);
-PROVIDE(int_264 = DefaultHandler);
+/* default interrupt handlers of the CLIC vector table: the `int_N` not defined with
+   `#[interrupt_handler]` end up in `DefaultHandler`. In vectored mode the table jumps to them
+   directly, so they go through `_default_int_wrapper`, which saves the context and returns with
+   `mret`. With `nxti`, `_nxti_trap_handler` does that and calls them as functions. */
+#if defined(CLIC)
+#if defined(NXTI)
+PROVIDE(_default_int = DefaultHandler);
+#else
+PROVIDE(_default_int = _default_int_wrapper);
+#endif
+#else
+PROVIDE(_default_int = DefaultHandler);
+#endif
+PROVIDE(int_0 = _default_int);
+PROVIDE(int_1 = _default_int);
+PROVIDE(int_2 = _default_int);
+PROVIDE(int_3 = _default_int);
+PROVIDE(int_4 = _default_int);
+PROVIDE(int_5 = _default_int);
+PROVIDE(int_6 = _default_int);
+PROVIDE(int_7 = _default_int);
+PROVIDE(int_8 = _default_int);
+PROVIDE(int_9 = _default_int);
+PROVIDE(int_10 = _default_int);
+PROVIDE(int_11 = _default_int);
+PROVIDE(int_12 = _default_int);
+PROVIDE(int_13 = _default_int);
+PROVIDE(int_14 = _default_int);
+PROVIDE(int_15 = _default_int);
+PROVIDE(int_16 = _default_int);
+PROVIDE(int_17 = _default_int);
+PROVIDE(int_18 = _default_int);
+PROVIDE(int_19 = _default_int);
+PROVIDE(int_20 = _default_int);
+PROVIDE(int_21 = _default_int);
+PROVIDE(int_22 = _default_int);
+PROVIDE(int_23 = _default_int);
+PROVIDE(int_24 = _default_int);
+PROVIDE(int_25 = _default_int);
+PROVIDE(int_26 = _default_int);
+PROVIDE(int_27 = _default_int);
+PROVIDE(int_28 = _default_int);
+PROVIDE(int_29 = _default_int);
+PROVIDE(int_30 = _default_int);
+PROVIDE(int_31 = _default_int);
+PROVIDE(int_32 = _default_int);
+PROVIDE(int_33 = _default_int);
+PROVIDE(int_34 = _default_int);
+PROVIDE(int_35 = _default_int);
+PROVIDE(int_36 = _default_int);
+PROVIDE(int_37 = _default_int);
+PROVIDE(int_38 = _default_int);
+PROVIDE(int_39 = _default_int);
+PROVIDE(int_40 = _default_int);
+PROVIDE(int_41 = _default_int);
+PROVIDE(int_42 = _default_int);
+PROVIDE(int_43 = _default_int);
+PROVIDE(int_44 = _default_int);
+PROVIDE(int_45 = _default_int);
+PROVIDE(int_46 = _default_int);
+PROVIDE(int_47 = _default_int);
+PROVIDE(int_48 = _default_int);
+PROVIDE(int_49 = _default_int);
+PROVIDE(int_50 = _default_int);
+PROVIDE(int_51 = _default_int);
+PROVIDE(int_52 = _default_int);
+PROVIDE(int_53 = _default_int);
+PROVIDE(int_54 = _default_int);
+PROVIDE(int_55 = _default_int);
+PROVIDE(int_56 = _default_int);
+PROVIDE(int_57 = _default_int);
+PROVIDE(int_58 = _default_int);
+PROVIDE(int_59 = _default_int);
+PROVIDE(int_60 = _default_int);
+PROVIDE(int_61 = _default_int);
+PROVIDE(int_62 = _default_int);
+PROVIDE(int_63 = _default_int);
+PROVIDE(int_64 = _default_int);
+PROVIDE(int_65 = _default_int);
+PROVIDE(int_66 = _default_int);
+PROVIDE(int_67 = _default_int);
+PROVIDE(int_68 = _default_int);
+PROVIDE(int_69 = _default_int);
+PROVIDE(int_70 = _default_int);
+PROVIDE(int_71 = _default_int);
+PROVIDE(int_72 = _default_int);
+PROVIDE(int_73 = _default_int);
+PROVIDE(int_74 = _default_int);
+PROVIDE(int_75 = _default_int);
+PROVIDE(int_76 = _default_int);
+PROVIDE(int_77 = _default_int);
+PROVIDE(int_78 = _default_int);
+PROVIDE(int_79 = _default_int);
+PROVIDE(int_80 = _default_int);
+PROVIDE(int_81 = _default_int);
+PROVIDE(int_82 = _default_int);
+PROVIDE(int_83 = _default_int);
+PROVIDE(int_84 = _default_int);
+PROVIDE(int_85 = _default_int);
+PROVIDE(int_86 = _default_int);
+PROVIDE(int_87 = _default_int);
+PROVIDE(int_88 = _default_int);
+PROVIDE(int_89 = _default_int);
+PROVIDE(int_90 = _default_int);
+PROVIDE(int_91 = _default_int);
+PROVIDE(int_92 = _default_int);
+PROVIDE(int_93 = _default_int);
+PROVIDE(int_94 = _default_int);
+PROVIDE(int_95 = _default_int);
+PROVIDE(int_96 = _default_int);
+PROVIDE(int_97 = _default_int);
+PROVIDE(int_98 = _default_int);
+PROVIDE(int_99 = _default_int);
+PROVIDE(int_100 = _default_int);
+PROVIDE(int_101 = _default_int);
+PROVIDE(int_102 = _default_int);
+PROVIDE(int_103 = _default_int);
+PROVIDE(int_104 = _default_int);
+PROVIDE(int_105 = _default_int);
+PROVIDE(int_106 = _default_int);
+PROVIDE(int_107 = _default_int);
+PROVIDE(int_108 = _default_int);
+PROVIDE(int_109 = _default_int);
+PROVIDE(int_110 = _default_int);
+PROVIDE(int_111 = _default_int);
+PROVIDE(int_112 = _default_int);
+PROVIDE(int_113 = _default_int);
+PROVIDE(int_114 = _default_int);
+PROVIDE(int_115 = _default_int);
+PROVIDE(int_116 = _default_int);
+PROVIDE(int_117 = _default_int);
+PROVIDE(int_118 = _default_int);
+PROVIDE(int_119 = _default_int);
+PROVIDE(int_120 = _default_int);
+PROVIDE(int_121 = _default_int);
+PROVIDE(int_122 = _default_int);
+PROVIDE(int_123 = _default_int);
+PROVIDE(int_124 = _default_int);
+PROVIDE(int_125 = _default_int);
+PROVIDE(int_126 = _default_int);
+PROVIDE(int_127 = _default_int);
+PROVIDE(int_128 = _default_int);
+PROVIDE(int_129 = _default_int);
+PROVIDE(int_130 = _default_int);
+PROVIDE(int_131 = _default_int);
+PROVIDE(int_132 = _default_int);
+PROVIDE(int_133 = _default_int);
+PROVIDE(int_134 = _default_int);
+PROVIDE(int_135 = _default_int);
+PROVIDE(int_136 = _default_int);
+PROVIDE(int_137 = _default_int);
+PROVIDE(int_138 = _default_int);
+PROVIDE(int_139 = _default_int);
+PROVIDE(int_140 = _default_int);
+PROVIDE(int_141 = _default_int);
+PROVIDE(int_142 = _default_int);
+PROVIDE(int_143 = _default_int);
+PROVIDE(int_144 = _default_int);
+PROVIDE(int_145 = _default_int);
+PROVIDE(int_146 = _default_int);
+PROVIDE(int_147 = _default_int);
+PROVIDE(int_148 = _default_int);
+PROVIDE(int_149 = _default_int);
+PROVIDE(int_150 = _default_int);
+PROVIDE(int_151 = _default_int);
+PROVIDE(int_152 = _default_int);
+PROVIDE(int_153 = _default_int);
+PROVIDE(int_154 = _default_int);
+PROVIDE(int_155 = _default_int);
+PROVIDE(int_156 = _default_int);
+PROVIDE(int_157 = _default_int);
+PROVIDE(int_158 = _default_int);
+PROVIDE(int_159 = _default_int);
+PROVIDE(int_160 = _default_int);
+PROVIDE(int_161 = _default_int);
+PROVIDE(int_162 = _default_int);
+PROVIDE(int_163 = _default_int);
+PROVIDE(int_164 = _default_int);
+PROVIDE(int_165 = _default_int);
+PROVIDE(int_166 = _default_int);
+PROVIDE(int_167 = _default_int);
+PROVIDE(int_168 = _default_int);
+PROVIDE(int_169 = _default_int);
+PROVIDE(int_170 = _default_int);
+PROVIDE(int_171 = _default_int);
+PROVIDE(int_172 = _default_int);
+PROVIDE(int_173 = _default_int);
+PROVIDE(int_174 = _default_int);
+PROVIDE(int_175 = _default_int);
+PROVIDE(int_176 = _default_int);
+PROVIDE(int_177 = _default_int);
+PROVIDE(int_178 = _default_int);
+PROVIDE(int_179 = _default_int);
+PROVIDE(int_180 = _default_int);
+PROVIDE(int_181 = _default_int);
+PROVIDE(int_182 = _default_int);
+PROVIDE(int_183 = _default_int);
+PROVIDE(int_184 = _default_int);
+PROVIDE(int_185 = _default_int);
+PROVIDE(int_186 = _default_int);
+PROVIDE(int_187 = _default_int);
+PROVIDE(int_188 = _default_int);
+PROVIDE(int_189 = _default_int);
+PROVIDE(int_190 = _default_int);
+PROVIDE(int_191 = _default_int);
+PROVIDE(int_192 = _default_int);
+PROVIDE(int_193 = _default_int);
+PROVIDE(int_194 = _default_int);
+PROVIDE(int_195 = _default_int);
+PROVIDE(int_196 = _default_int);
+PROVIDE(int_197 = _default_int);
+PROVIDE(int_198 = _default_int);
+PROVIDE(int_199 = _default_int);
+PROVIDE(int_200 = _default_int);
+PROVIDE(int_201 = _default_int);
+PROVIDE(int_202 = _default_int);
+PROVIDE(int_203 = _default_int);
+PROVIDE(int_204 = _default_int);
+PROVIDE(int_205 = _default_int);
+PROVIDE(int_206 = _default_int);
+PROVIDE(int_207 = _default_int);
+PROVIDE(int_208 = _default_int);
+PROVIDE(int_209 = _default_int);
+PROVIDE(int_210 = _default_int);
+PROVIDE(int_211 = _default_int);
+PROVIDE(int_212 = _default_int);
+PROVIDE(int_213 = _default_int);
+PROVIDE(int_214 = _default_int);
+PROVIDE(int_215 = _default_int);
+PROVIDE(int_216 = _default_int);
+PROVIDE(int_217 = _default_int);
+PROVIDE(int_218 = _default_int);
+PROVIDE(int_219 = _default_int);
+PROVIDE(int_220 = _default_int);
+PROVIDE(int_221 = _default_int);
+PROVIDE(int_222 = _default_int);
+PROVIDE(int_223 = _default_int);
+PROVIDE(int_224 = _default_int);
+PROVIDE(int_225 = _default_int);
+PROVIDE(int_226 = _default_int);
+PROVIDE(int_227 = _default_int);
+PROVIDE(int_228 = _default_int);
+PROVIDE(int_229 = _default_int);
+PROVIDE(int_230 = _default_int);
+PROVIDE(int_231 = _default_int);
+PROVIDE(int_232 = _default_int);
+PROVIDE(int_233 = _default_int);
+PROVIDE(int_234 = _default_int);
+PROVIDE(int_235 = _default_int);
+PROVIDE(int_236 = _default_int);
+PROVIDE(int_237 = _default_int);
+PROVIDE(int_238 = _default_int);
+PROVIDE(int_239 = _default_int);
+PROVIDE(int_240 = _default_int);
+PROVIDE(int_241 = _default_int);
+PROVIDE(int_242 = _default_int);
+PROVIDE(int_243 = _default_int);
+PROVIDE(int_244 = _default_int);
+PROVIDE(int_245 = _default_int);
+PROVIDE(int_246 = _default_int);
+PROVIDE(int_247 = _default_int);
+PROVIDE(int_248 = _default_int);
+PROVIDE(int_249 = _default_int);
+PROVIDE(int_250 = _default_int);
+PROVIDE(int_251 = _default_int);
+PROVIDE(int_252 = _default_int);
+PROVIDE(int_253 = _default_int);
+PROVIDE(int_254 = _default_int);
+PROVIDE(int_255 = _default_int);
+PROVIDE(int_256 = _default_int);
+PROVIDE(int_257 = _default_int);
+PROVIDE(int_258 = _default_int);
+PROVIDE(int_259 = _default_int);
+PROVIDE(int_260 = _default_int);
+PROVIDE(int_261 = _default_int);
+PROVIDE(int_262 = _default_int);
+PROVIDE(int_263 = _default_int);
+PROVIDE(int_264 = _default_int);
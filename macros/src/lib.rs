@@ -10,8 +10,8 @@ extern crate syn;
 
 use proc_macro2::Span;
 use syn::{
-    parse, spanned::Spanned, AttributeArgs, FnArg, ItemFn, PathArguments, ReturnType,
-    Type, Visibility,
+    parse, spanned::Spanned, AttributeArgs, FnArg, GenericParam, ItemFn, PathArguments,
+    ReturnType, Type, Visibility,
 };
 
 use proc_macro::TokenStream;
@@ -26,7 +26,15 @@ use proc_macro::TokenStream;
 /// The specified function will be called by the reset handler *after* RAM has been initialized.
 /// If present, the FPU will also be enabled before the function is called.
 ///
-/// The type of the specified function must be `[unsafe] fn() -> !` (never ending function)
+/// The type of the specified function must be `[unsafe] fn() -> !` (never ending function). It may
+/// take up to three `usize` arguments, which receive the values of `a0` to `a2` at reset. The
+/// generated `main` always has the three arguments `start_rust` calls it with, and forwards the
+/// declared ones.
+///
+/// The function may declare a single `const N: usize` generic parameter, which is bound to the
+/// number of harts (`riscv_rt::MAX_HART_ID + 1`, configured with the `RISCV_RT_MAX_HART_ID`
+/// environment variable). This allows sizing per-hart data structures at compile time. As
+/// `_max_hart_id` is only known at link time, `link.x` checks that both values match.
 ///
 /// # Properties
 ///
@@ -47,6 +55,20 @@ use proc_macro::TokenStream;
 ///     }
 /// }
 /// ```
+///
+/// - Entry point sized by the number of harts
+///
+/// ``` no_run
+/// # #![no_main]
+/// # use riscv_rt_macros::entry;
+/// #[entry]
+/// fn main<const N: usize>(hartid: usize) -> ! {
+///     let _per_hart = [0u32; N];
+///     loop {
+///         /* .. */
+///     }
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn entry(args: TokenStream, input: TokenStream) -> TokenStream {
     let f = parse_macro_input!(input as ItemFn);
@@ -77,12 +99,40 @@ pub fn entry(args: TokenStream, input: TokenStream) -> TokenStream {
         }
     }
 
+    // the only generic parameter allowed is the number of harts: `<const N: usize>`
+    let hart_count = match f.sig.generics.params.len() {
+        0 => None,
+        1 => match f.sig.generics.params.first() {
+            Some(GenericParam::Const(c))
+                if is_simple_type(&c.ty, "usize") && c.default.is_none() =>
+            {
+                Some(c.ident.clone())
+            }
+            Some(p) => {
+                return parse::Error::new(
+                    p.span(),
+                    "`#[entry]` function only accepts a `const N: usize` generic parameter",
+                )
+                .to_compile_error()
+                .into();
+            }
+            None => unreachable!(),
+        },
+        _ => {
+            return parse::Error::new(
+                f.sig.generics.span(),
+                "`#[entry]` function only accepts a `const N: usize` generic parameter",
+            )
+            .to_compile_error()
+            .into();
+        }
+    };
+
     // check the function signature
     let valid_signature = f.sig.constness.is_none()
         && f.sig.asyncness.is_none()
         && f.vis == Visibility::Inherited
         && f.sig.abi.is_none()
-        && f.sig.generics.params.is_empty()
         && f.sig.generics.where_clause.is_none()
         && f.sig.variadic.is_none()
         && match f.sig.output {
@@ -93,7 +143,7 @@ pub fn entry(args: TokenStream, input: TokenStream) -> TokenStream {
     if !valid_signature {
         return parse::Error::new(
             f.span(),
-            "`#[entry]` function must have signature `[unsafe] fn([arg0: usize, ...]) -> !`",
+            "`#[entry]` function must have signature `[unsafe] fn[<const N: usize>]([arg0: usize, ...]) -> !`",
         )
         .to_compile_error()
         .into();
@@ -111,10 +161,42 @@ pub fn entry(args: TokenStream, input: TokenStream) -> TokenStream {
     let args = f.sig.inputs;
     let stmts = f.block.stmts;
 
+    // `main` always takes the three arguments `start_rust` passes, the user function is called
+    // with as many as it declares
+    let forwarded: Vec<_> = (0..args.len()).map(|i| format_ident!("a{}", i)).collect();
+
+    if let Some(hart_count) = hart_count {
+        // `N` is bound to the number of harts the crate was configured with
+        return quote!(
+            // makes `link.x` check `_max_hart_id` against `RISCV_RT_MAX_HART_ID`
+            core::arch::global_asm!(
+                ".globl __riscv_rt_const_hart_count",
+                ".set __riscv_rt_const_hart_count, 1",
+            );
+
+            #[export_name = "main"]
+            #[allow(unused_variables)]
+            pub #unsafety fn __risc_v_rt__main(a0: usize, a1: usize, a2: usize) -> ! {
+                __risc_v_rt__main_generic::<{ riscv_rt::MAX_HART_ID + 1 }>(#(#forwarded),*)
+            }
+
+            #(#attrs)*
+            #unsafety fn __risc_v_rt__main_generic<const #hart_count: usize>(#args) -> ! {
+                #(#stmts)*
+            }
+        )
+        .into();
+    }
+
     quote!(
         #[export_name = "main"]
+        #[allow(unused_variables)]
+        pub #unsafety fn __risc_v_rt__main(a0: usize, a1: usize, a2: usize) -> ! {
+            __risc_v_rt__main_user(#(#forwarded),*)
+        }
+
         #(#attrs)*
-        pub #unsafety fn __risc_v_rt__main(#args) -> ! {
+        #unsafety fn __risc_v_rt__main_user(#args) -> ! {
             #(#stmts)*
         }
     )
@@ -208,171 +290,1230 @@ pub fn pre_init(args: TokenStream, input: TokenStream) -> TokenStream {
     .into()
 }
 
-/// There are three ways to connect the handler function to the actual interrupt:
-/// 1. use no argument, provide a linker script entry with `PROVIDE(int_<your_interrupt_number> = <your_handler_name>)`
-/// 2. use a literal integer as argument. Handler is then mapped to this interrupt number.
-/// 3. use an interrupt enum from the PAC crate. Handler is mapped to this interrupt.
+/// Attribute to declare the multi-processing hook (`_mp_hook`)
+///
+/// **IMPORTANT**: This attribute can appear at most *once* in the dependency graph.
+///
+/// The function must have the signature of `[unsafe] fn(usize) -> bool`. It is called from all
+/// the harts with their hartid and must return true only for one hart, which will perform memory
+/// initialization. For other harts it must return false and implement wake-up in a
+/// platform-dependent way.
+///
+/// Alternatively, `#[mp_hook(boot_hart = N)]` generates the standard policy: hart `N` performs
+/// memory initialization and all other harts are parked on `wfi` forever. In this form the body
+/// of the function must be left empty.
+///
+/// # Examples
+///
+/// ``` no_run
+/// # use riscv_rt_macros::mp_hook;
+/// #[mp_hook]
+/// fn mp_hook(hartid: usize) -> bool {
+///     hartid == 0
+/// }
+/// ```
+///
+/// ``` no_run
+/// # use riscv_rt_macros::mp_hook;
+/// #[mp_hook(boot_hart = 1)]
+/// fn mp_hook(hartid: usize) -> bool {}
+/// ```
 #[proc_macro_attribute]
-pub fn interrupt_handler(args: TokenStream, input: TokenStream) -> TokenStream {
+pub fn mp_hook(args: TokenStream, input: TokenStream) -> TokenStream {
     let f = parse_macro_input!(input as ItemFn);
     let args = parse_macro_input!(args as AttributeArgs);
 
-    // at most one argument should be provided
-    if args.len() > 1 {
+    // check the function signature
+    let valid_signature = f.sig.constness.is_none()
+        && f.sig.asyncness.is_none()
+        && f.vis == Visibility::Inherited
+        && f.sig.abi.is_none()
+        && f.sig.inputs.len() == 1
+        && f.sig.generics.params.is_empty()
+        && f.sig.generics.where_clause.is_none()
+        && f.sig.variadic.is_none()
+        && match f.sig.inputs.first() {
+            Some(FnArg::Typed(t)) => is_simple_type(&t.ty, "usize"),
+            _ => false,
+        }
+        && match f.sig.output {
+            ReturnType::Default => false,
+            ReturnType::Type(_, ref ty) => is_simple_type(ty, "bool"),
+        };
+
+    if !valid_signature {
         return parse::Error::new(
             f.span(),
-            "Too many arguments: `#[interrupt(int_nr)]` attribute must have at max one argument",
+            "`#[mp_hook]` function must have signature `[unsafe] fn(hartid: usize) -> bool`",
         )
         .to_compile_error()
         .into();
     }
 
+    let boot_hart = match args.len() {
+        0 => None,
+        1 => match &args[0] {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident("boot_hart") => {
+                match &nv.lit {
+                    syn::Lit::Int(i) => Some(i.clone()),
+                    l => {
+                        return parse::Error::new(l.span(), "`boot_hart` must be an integer")
+                            .to_compile_error()
+                            .into()
+                    }
+                }
+            }
+            a => {
+                return parse::Error::new(
+                    a.span(),
+                    "Wrong argument: `#[mp_hook(..)]` only accepts `boot_hart = <hartid>`",
+                )
+                .to_compile_error()
+                .into()
+            }
+        },
+        _ => {
+            return parse::Error::new(
+                Span::call_site(),
+                "Too many arguments: `#[mp_hook(..)]` only accepts `boot_hart = <hartid>`",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+
+    // XXX should we blacklist other attributes?
     let attrs = f.attrs;
+    let unsafety = f.sig.unsafety;
     let ident = f.sig.ident;
+    let args = f.sig.inputs;
     let block = f.block;
-    let ident_string = ident.to_string();
 
-    let wrapper_ident_string = {
-        // check on number of arguments
-        let arg = args.get(0);
-        match arg {
-            // an argument exists
-            Some(a) =>
-            match a {
-                syn::NestedMeta::Lit(l) => match l {
-                    // option to supply an integer. It is treated the interrupt number, wrapper named after int_<number>
-                    syn::Lit::Int(i) => "int_".to_owned() + &i.to_string(),
-                    default => return parse::Error::new(
-                            default.span(),
-                            "Wrong type: `#[interrupt(int_nr)]` attribute must provide an integer as an argument",
-                        )
-                        .to_compile_error()
-                        .into(),
-                    },
-                syn::NestedMeta::Meta(m) => match m {
-                    // option to supply an identifier (e.g. an Enum name) wrapper is named after identifier
-                    syn::Meta::Path(p) => match p.get_ident() {
-                        Some(i) => i.to_string(),
-                        None => return parse::Error::new(
-                            p.span(),
-                            "Wrong type: `#[interrupt(identifier)]` attribute must provide a single enum value specifying an interrupt from the PAC crate",
-                        )
-                        .to_compile_error()
-                        .into(),
-                    },
-                    default => return parse::Error::new(
-                        default.span(),
-                        "Wrong type: `#[interrupt(..)]` attribute must have either no or one argument of type Int literal or Enum identifier",
-                    )
-                    .to_compile_error()
-                    .into(),
-                    }
+    let block = match boot_hart {
+        None => quote!(#block),
+        Some(boot_hart) => {
+            if !block.stmts.is_empty() {
+                return parse::Error::new(
+                    block.span(),
+                    "`#[mp_hook(boot_hart = ..)]` generates the function body, leave it empty",
+                )
+                .to_compile_error()
+                .into();
             }
-            // no argument exist -> wrapper is named after original function
-            None => ident_string.clone()
+            let hartid = match args.first() {
+                Some(FnArg::Typed(t)) => t.pat.clone(),
+                _ => unreachable!(),
+            };
+            quote!({
+                extern "Rust" {
+                    fn _watchdog_feed();
+                }
+
+                if #hartid == #boot_hart {
+                    true
+                } else {
+                    loop {
+                        unsafe {
+                            core::arch::asm!("wfi");
+                            _watchdog_feed();
+                        }
+                    }
+                }
+            })
         }
     };
 
-    // check that function has no arguments
-    if f.sig.inputs.len() != 0 {
+    quote!(
+        #[export_name = "_mp_hook"]
+        #(#attrs)*
+        pub #unsafety extern "Rust" fn #ident(#args) -> bool #block
+    )
+    .into()
+}
+
+/// Attribute to declare the exception handler (`ExceptionHandler`)
+///
+/// **IMPORTANT**: This attribute can appear at most *once* in the dependency graph.
+///
+/// The function must have the signature of `[unsafe] fn(&riscv_rt::TrapFrame) [-> !]`. The argument
+/// type is checked at compile time, so a mismatching override cannot be linked silently. If the
+/// function returns, the trapped code resumes at `mepc`/`sepc`, see `riscv_rt::skip_instruction`.
+///
+/// # Examples
+///
+/// ``` no_run
+/// # use riscv_rt_macros::exception_handler;
+/// #[exception_handler]
+/// fn exception_handler(trap_frame: &riscv_rt::TrapFrame) -> ! {
+///     loop {}
+/// }
+/// ```
+///
+/// ``` no_run
+/// # use riscv_rt_macros::exception_handler;
+/// #[exception_handler]
+/// fn exception_handler(trap_frame: &riscv_rt::TrapFrame) {
+///     // e.g. ignore environment calls
+///     unsafe { riscv_rt::skip_instruction() };
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn exception_handler(args: TokenStream, input: TokenStream) -> TokenStream {
+    let f = parse_macro_input!(input as ItemFn);
+
+    // check the function signature
+    if !is_trap_frame_handler(&f) {
         return parse::Error::new(
-            f.sig.inputs.last().unwrap().span(),
-            "`#[interrupt(..)]` handler function must not have any argument",
+            f.span(),
+            "`#[exception_handler]` function must have signature `[unsafe] fn(&riscv_rt::TrapFrame) [-> !]`",
         )
         .to_compile_error()
         .into();
     }
 
-    // check that function does not return anything. Not returning is also an option
-    let valid_ret_type = match f.sig.output {
-        ReturnType::Default => true,
-        ReturnType::Type(_, ref ty) => match **ty {
-            Type::Tuple(ref tuple) => tuple.elems.is_empty(),
-            Type::Never(_) => true,
+    if !args.is_empty() {
+        return parse::Error::new(Span::call_site(), "This attribute accepts no arguments")
+            .to_compile_error()
+            .into();
+    }
+
+    // XXX should we blacklist other attributes?
+    let attrs = f.attrs;
+    let unsafety = f.sig.unsafety;
+    let ident = f.sig.ident;
+    let args = f.sig.inputs;
+    let output = f.sig.output;
+    let block = f.block;
+
+    quote!(
+        #[export_name = "ExceptionHandler"]
+        #(#attrs)*
+        pub #unsafety extern "C" fn #ident(#args) #output #block
+
+        // the argument must match the declaration of the trap handler
+        const _: unsafe extern "C" fn(&riscv_rt::TrapFrame) #output = #ident;
+    )
+    .into()
+}
+
+/// Returns whether `f` has the signature `[unsafe] fn(&riscv_rt::TrapFrame) [-> !]` of the
+/// exception handlers. The argument type itself is checked by the generated code.
+fn is_trap_frame_handler(f: &ItemFn) -> bool {
+    f.sig.constness.is_none()
+        && f.sig.asyncness.is_none()
+        && f.vis == Visibility::Inherited
+        && f.sig.abi.is_none()
+        && f.sig.inputs.len() == 1
+        && f.sig.generics.params.is_empty()
+        && f.sig.generics.where_clause.is_none()
+        && f.sig.variadic.is_none()
+        && match f.sig.inputs.first() {
+            Some(FnArg::Typed(t)) => {
+                matches!(*t.ty, Type::Reference(ref r) if r.mutability.is_none())
+            }
             _ => false,
-        },
-    };
-    if !valid_ret_type {
+        }
+        && match f.sig.output {
+            ReturnType::Default => true,
+            ReturnType::Type(_, ref ty) => matches!(**ty, Type::Never(_)),
+        }
+}
+
+/// Standard exception codes and the names of their handlers, see `__EXCEPTIONS` in `riscv-rt`
+const EXCEPTIONS: &[(u8, &str)] = &[
+    (0, "InstructionMisaligned"),
+    (1, "InstructionFault"),
+    (2, "IllegalInstruction"),
+    (3, "Breakpoint"),
+    (4, "LoadMisaligned"),
+    (5, "LoadFault"),
+    (6, "StoreMisaligned"),
+    (7, "StoreFault"),
+    (8, "UserEnvCall"),
+    (9, "SupervisorEnvCall"),
+    (11, "MachineEnvCall"),
+    (12, "InstructionPageFault"),
+    (13, "LoadPageFault"),
+    (15, "StorePageFault"),
+];
+
+/// Attribute to declare an exception handler
+///
+/// Without argument, the function becomes `ExceptionHandler`, which handles all the exceptions.
+/// With an exception code (e.g. `2`) or the name of a standard exception (e.g.
+/// `IllegalInstruction`), it only handles the exceptions of that cause; the other ones still go
+/// to `ExceptionHandler`. Each exception can have at most *one* handler in the dependency graph.
+///
+/// The function must have the signature of `[unsafe] fn(&riscv_rt::TrapFrame) [-> !]`, which is
+/// checked at compile time. A handler that returns resumes the trapped code at `mepc`/`sepc`, so it
+/// must advance `mepc`/`sepc` past the faulting instruction unless the fault was fixed.
+///
+/// # Examples
+///
+/// ``` no_run
+/// # use riscv_rt_macros::exception;
+/// #[exception]
+/// fn exception_handler(trap_frame: &riscv_rt::TrapFrame) -> ! {
+///     loop {}
+/// }
+///
+/// #[exception(IllegalInstruction)]
+/// fn illegal_instruction(trap_frame: &riscv_rt::TrapFrame) {
+///     // emulate the instruction and advance `mepc`
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn exception(args: TokenStream, input: TokenStream) -> TokenStream {
+    let f = parse_macro_input!(input as ItemFn);
+    let args = parse_macro_input!(args as AttributeArgs);
+
+    // check the function signature
+    if !is_trap_frame_handler(&f) {
         return parse::Error::new(
-            f.sig.output.span(),
-            "`#[interrupt(..)]` handler function must not return anything",
+            f.sig.span(),
+            "`#[exception]` function must have signature `[unsafe] fn(&riscv_rt::TrapFrame) [-> !]`",
         )
         .to_compile_error()
         .into();
     }
 
-    let handler_ident = format_ident!("{}_handler", ident_string);
-    let wrapper_ident = format_ident!("{}", wrapper_ident_string);
-    let assembly_string = format!(
-        ".global {wrapper_ident_string}
-    {wrapper_ident_string}:
-    addi sp, sp, -(4 * 32)
-    sw ra, 0(sp)
-    sw t0, 4(sp)
-    sw t1, 8(sp)
-    sw t2, 12(sp)
-    sw a0, 16(sp)
-    sw a1, 20(sp)
-    sw a2, 24(sp)
-    sw a3, 28(sp)
-    sw a4, 32(sp)
-    sw a5, 36(sp)
-    sw a6, 40(sp)
-    sw a7, 44(sp)
-    sw t3, 48(sp)
-    sw t4, 52(sp)
-    sw t5, 56(sp)
-    sw t6, 60(sp)
-    csrr t0, mcause
-    csrr t1, mepc
-    sw t0, 64(sp)
-    sw t1, 68(sp)
-    csrsi mstatus, 8 /* enable global interrupts*/
+    let export_name = match args.as_slice() {
+        [] => "ExceptionHandler",
+        [syn::NestedMeta::Lit(syn::Lit::Int(i))] => {
+            let code = i.base10_parse::<u8>().ok();
+            match EXCEPTIONS.iter().find(|(c, _)| Some(*c) == code) {
+                Some((_, name)) => *name,
+                None => {
+                    return parse::Error::new(
+                        i.span(),
+                        "Unknown exception code: `#[exception(code)]` accepts the standard exception codes 0 to 15, except the reserved 10 and 14",
+                    )
+                    .to_compile_error()
+                    .into()
+                }
+            }
+        }
+        [syn::NestedMeta::Meta(syn::Meta::Path(p))] => {
+            match EXCEPTIONS.iter().find(|(_, name)| p.is_ident(name)) {
+                Some((_, name)) => *name,
+                None => {
+                    return parse::Error::new(
+                        p.span(),
+                        "Unknown exception: `#[exception(name)]` accepts the names of `riscv::register::mcause::Exception`",
+                    )
+                    .to_compile_error()
+                    .into()
+                }
+            }
+        }
+        _ => {
+            return parse::Error::new(
+                Span::call_site(),
+                "`#[exception]` accepts no argument, or one exception code or name",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
 
-    jal {handler_ident}
+    let attrs = f.attrs;
+    let unsafety = f.sig.unsafety;
+    let ident = f.sig.ident;
+    let inputs = f.sig.inputs;
+    let output = f.sig.output;
+    let block = f.block;
 
-    csrci mstatus, 8 /* disable global interrupts*/
-    lw t0, 64(sp)
-    lw t1, 68(sp)
-    csrw mcause, t0
-    csrw mepc, t1
-    lw ra, 0(sp)
-    lw t0, 4(sp)
-    lw t1, 8(sp)
-    lw t2, 12(sp)
-    lw a0, 16(sp)
-    lw a1, 20(sp)
-    lw a2, 24(sp)
-    lw a3, 28(sp)
-    lw a4, 32(sp)
-    lw a5, 36(sp)
-    lw a6, 40(sp)
-    lw a7, 44(sp)
-    lw t3, 48(sp)
-    lw t4, 52(sp)
-    lw t5, 56(sp)
-    lw t6, 60(sp)
-    addi sp, sp, (4 * 32)
-    mret
-    "
-    );
+    quote!(
+        #[export_name = #export_name]
+        #(#attrs)*
+        pub #unsafety extern "C" fn #ident(#inputs) #output #block
 
-    if cfg!(feature = "nxti") {
-        quote!(
-            #(#attrs)*
-            #[no_mangle]
-            pub unsafe fn #wrapper_ident() #block
-        )
-        .into()
-    } else {
-        quote!(
-            #(#attrs)*
-            #[no_mangle]
-            pub unsafe fn #handler_ident() #block
+        // the argument must match the declaration of the trap handler
+        const _: unsafe extern "C" fn(&riscv_rt::TrapFrame) #output = #ident;
+    )
+    .into()
+}
 
-            core::arch::global_asm!(#assembly_string);
+/// Attribute to declare the default interrupt handler (`DefaultHandler`)
+///
+/// **IMPORTANT**: This attribute can appear at most *once* in the dependency graph.
+///
+/// The function must have the signature of `[unsafe] fn() [-> !]`. It is called for every
+/// interrupt without a dedicated handler.
+///
+/// # Examples
+///
+/// ``` no_run
+/// # use riscv_rt_macros::default_handler;
+/// #[default_handler]
+/// fn default_handler() {
+///     // ...
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn default_handler(args: TokenStream, input: TokenStream) -> TokenStream {
+    let f = parse_macro_input!(input as ItemFn);
 
-        )
-        .into()
+    // check the function signature
+    let valid_signature = f.sig.constness.is_none()
+        && f.sig.asyncness.is_none()
+        && f.vis == Visibility::Inherited
+        && f.sig.abi.is_none()
+        && f.sig.inputs.is_empty()
+        && f.sig.generics.params.is_empty()
+        && f.sig.generics.where_clause.is_none()
+        && f.sig.variadic.is_none()
+        && match f.sig.output {
+            ReturnType::Default => true,
+            ReturnType::Type(_, ref ty) => match **ty {
+                Type::Tuple(ref tuple) => tuple.elems.is_empty(),
+                Type::Never(_) => true,
+                _ => false,
+            },
+        };
+
+    if !valid_signature {
+        return parse::Error::new(
+            f.span(),
+            "`#[default_handler]` function must have signature `[unsafe] fn() [-> !]`",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    if !args.is_empty() {
+        return parse::Error::new(Span::call_site(), "This attribute accepts no arguments")
+            .to_compile_error()
+            .into();
+    }
+
+    // XXX should we blacklist other attributes?
+    let attrs = f.attrs;
+    let unsafety = f.sig.unsafety;
+    let ident = f.sig.ident;
+    let output = f.sig.output;
+    let block = f.block;
+
+    quote!(
+        #[export_name = "DefaultHandler"]
+        #(#attrs)*
+        pub #unsafety extern "C" fn #ident() #output #block
+    )
+    .into()
+}
+
+/// Attribute to declare the handler of the interrupts without a dedicated handler
+/// (`DefaultHandler`)
+///
+/// **IMPORTANT**: This attribute can appear at most *once* in the dependency graph, and not
+/// together with `#[default_handler]`.
+///
+/// The function must have the signature of `[unsafe] fn([code: usize]) [-> !]`. With the argument,
+/// it receives the code of the interrupt read from `mcause`/`scause` (the exception code field
+/// only, with the `clic` feature).
+///
+/// # Examples
+///
+/// ``` no_run
+/// # use riscv_rt_macros::unhandled_interrupt;
+/// #[unhandled_interrupt]
+/// fn unhandled(code: usize) {
+///     // ...
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn unhandled_interrupt(args: TokenStream, input: TokenStream) -> TokenStream {
+    let f = parse_macro_input!(input as ItemFn);
+
+    // the optional argument must be a `usize`
+    let valid_args = match f.sig.inputs.len() {
+        0 => true,
+        1 => match f.sig.inputs.first() {
+            Some(FnArg::Typed(arg)) => match *arg.ty {
+                Type::Path(ref p) => p.qself.is_none() && p.path.is_ident("usize"),
+                _ => false,
+            },
+            _ => false,
+        },
+        _ => false,
+    };
+
+    // check the function signature
+    let valid_signature = f.sig.constness.is_none()
+        && f.sig.asyncness.is_none()
+        && f.vis == Visibility::Inherited
+        && f.sig.abi.is_none()
+        && valid_args
+        && f.sig.generics.params.is_empty()
+        && f.sig.generics.where_clause.is_none()
+        && f.sig.variadic.is_none()
+        && match f.sig.output {
+            ReturnType::Default => true,
+            ReturnType::Type(_, ref ty) => match **ty {
+                Type::Tuple(ref tuple) => tuple.elems.is_empty(),
+                Type::Never(_) => true,
+                _ => false,
+            },
+        };
+
+    if !valid_signature {
+        return parse::Error::new(
+            f.span(),
+            "`#[unhandled_interrupt]` function must have signature `[unsafe] fn([code: usize]) [-> !]`",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    if !args.is_empty() {
+        return parse::Error::new(Span::call_site(), "This attribute accepts no arguments")
+            .to_compile_error()
+            .into();
+    }
+
+    let attrs = f.attrs;
+    let unsafety = f.sig.unsafety;
+    let ident = f.sig.ident;
+    let inputs = f.sig.inputs;
+    let output = f.sig.output;
+    let block = f.block;
+
+    let call = if inputs.is_empty() {
+        quote!(#ident())
+    } else {
+        quote!(#ident(riscv_rt::__interrupt_code()))
+    };
+
+    quote!(
+        #[export_name = "DefaultHandler"]
+        pub unsafe extern "C" fn __riscv_rt_unhandled_interrupt() {
+            #call;
+        }
+
+        #(#attrs)*
+        #unsafety fn #ident(#inputs) #output #block
+    )
+    .into()
+}
+
+/// Attribute to run a function from RAM
+///
+/// The function is placed in a `.ramfunc` input section, which the linker script allocates in
+/// `REGION_DATA` with its load address in `REGION_RODATA`. It is therefore copied to RAM
+/// together with `.data` before `main` is called, and must not be called from `#[pre_init]`.
+/// The initializing hart executes a `fence.i` after the copy. The function is never inlined, so it
+/// always executes from its RAM address.
+///
+/// # Examples
+///
+/// ``` no_run
+/// # use riscv_rt_macros::ramfunc;
+/// #[ramfunc]
+/// fn hot_loop(x: u32) -> u32 {
+///     x.wrapping_mul(3)
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn ramfunc(args: TokenStream, input: TokenStream) -> TokenStream {
+    let f = parse_macro_input!(input as ItemFn);
+
+    if f.sig.constness.is_some() || f.sig.asyncness.is_some() {
+        return parse::Error::new(
+            f.sig.span(),
+            "`#[ramfunc]` function must not be `const` or `async`",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    if !args.is_empty() {
+        return parse::Error::new(Span::call_site(), "This attribute accepts no arguments")
+            .to_compile_error()
+            .into();
+    }
+
+    let section = format!(".ramfunc.{}", f.sig.ident);
+
+    quote!(
+        #[link_section = #section]
+        #[inline(never)]
+        #f
+    )
+    .into()
+}
+
+/// Modes of `#[setup_interrupts(mode = "..")]`, named after the functions of `riscv_rt::__setup`
+const SETUP_MODES: &[&str] = &[
+    "direct",
+    "vectored",
+    "clic",
+    "clic_nxti",
+    "clic_direct",
+    "clic_shv",
+];
+
+/// Attribute to declare the interrupt setup function (`_setup_interrupts`)
+///
+/// **IMPORTANT**: This attribute can appear at most *once* in the dependency graph.
+///
+/// The function must have the signature of `[unsafe] fn()`. It is called by every hart right
+/// before `main` and replaces the default setup of the trap vector.
+///
+/// Instead of writing the body, a standard configuration can be generated with
+/// `#[setup_interrupts(mode = "..")]`, leaving the body empty:
+///
+/// - `"direct"`: `mtvec` points to `_start_trap` in direct mode (default without `clic`)
+/// - `"vectored"`: `mtvec` points to a vector table in vectored mode, whose entries jump to
+///   `_start_trap`
+/// - `"clic"`: `mtvec` in CLIC mode and `mtvt` pointing to `interrupt_vector` (default with
+///   `clic`, requires the `clic` feature)
+/// - `"clic_nxti"`: same as `"clic"` with the `nxti` feature, interrupts dispatched by the NXTI
+///   handler (requires `nxti`)
+/// - `"clic_direct"`: CLIC mode, interrupts dispatched one per trap by `_start_trap_rust`
+///   (requires `nxti`)
+/// - `"clic_shv"`: CLIC mode with hardware vectoring selected for every interrupt (requires
+///   `clic` without `nxti`)
+///
+/// # Examples
+///
+/// ``` no_run
+/// # use riscv_rt_macros::setup_interrupts;
+/// #[setup_interrupts(mode = "vectored")]
+/// fn setup_interrupts() {}
+/// ```
+#[proc_macro_attribute]
+pub fn setup_interrupts(args: TokenStream, input: TokenStream) -> TokenStream {
+    let f = parse_macro_input!(input as ItemFn);
+    let args = parse_macro_input!(args as AttributeArgs);
+
+    // check the function signature
+    let valid_signature = f.sig.constness.is_none()
+        && f.sig.asyncness.is_none()
+        && f.vis == Visibility::Inherited
+        && f.sig.abi.is_none()
+        && f.sig.inputs.is_empty()
+        && f.sig.generics.params.is_empty()
+        && f.sig.generics.where_clause.is_none()
+        && f.sig.variadic.is_none()
+        && match f.sig.output {
+            ReturnType::Default => true,
+            ReturnType::Type(_, ref ty) => match **ty {
+                Type::Tuple(ref tuple) => tuple.elems.is_empty(),
+                _ => false,
+            },
+        };
+
+    if !valid_signature {
+        return parse::Error::new(
+            f.span(),
+            "`#[setup_interrupts]` function must have signature `[unsafe] fn()`",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let mode = match args.len() {
+        0 => None,
+        1 => match &args[0] {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident("mode") => {
+                match &nv.lit {
+                    syn::Lit::Str(s) if SETUP_MODES.contains(&&*s.value()) => {
+                        Some(format_ident!("{}", s.value()))
+                    }
+                    l => {
+                        return parse::Error::new(
+                            l.span(),
+                            "`mode` must be one of \"direct\", \"vectored\", \"clic\", \"clic_nxti\", \"clic_direct\" or \"clic_shv\"",
+                        )
+                        .to_compile_error()
+                        .into()
+                    }
+                }
+            }
+            a => {
+                return parse::Error::new(
+                    a.span(),
+                    "Wrong argument: `#[setup_interrupts(..)]` only accepts `mode = \"..\"`",
+                )
+                .to_compile_error()
+                .into()
+            }
+        },
+        _ => {
+            return parse::Error::new(
+                Span::call_site(),
+                "Too many arguments: `#[setup_interrupts(..)]` only accepts `mode = \"..\"`",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+
+    // XXX should we blacklist other attributes?
+    let attrs = f.attrs;
+    let unsafety = f.sig.unsafety;
+    let ident = f.sig.ident;
+    let block = f.block;
+
+    let block = match mode {
+        None => quote!(#block),
+        Some(mode) => {
+            if !block.stmts.is_empty() {
+                return parse::Error::new(
+                    block.span(),
+                    "`#[setup_interrupts(mode = ..)]` generates the function body, leave it empty",
+                )
+                .to_compile_error()
+                .into();
+            }
+            quote!({
+                unsafe { riscv_rt::__setup::#mode() }
+            })
+        }
+    };
+
+    quote!(
+        #[export_name = "_setup_interrupts"]
+        #(#attrs)*
+        pub #unsafety extern "Rust" fn #ident() #block
+    )
+    .into()
+}
+
+/// There are three ways to connect the handler function to the actual interrupt:
+/// 1. use no argument, provide a linker script entry with `PROVIDE(int_<your_interrupt_number> = <your_handler_name>)`
+/// 2. use a literal integer as argument. Handler is then mapped to this interrupt number.
+/// 3. use an interrupt enum from the PAC crate. Handler is mapped to this interrupt.
+///
+/// With integer arguments, the handler may take the number of the interrupt that fired as
+/// `line: usize`. Several integers share one handler between these interrupts, which requires this
+/// argument:
+///
+/// ``` ignore
+/// #[interrupt_handler(3, 4, 5)]
+/// fn gpio_bank(line: usize) {
+///     // `line` is 3, 4 or 5
+/// }
+/// ```
+///
+/// With the `reentrancy-check` feature enabled, debug builds wrap the handler body with a guard
+/// that panics if the handler is entered again on the same hart before the previous invocation
+/// returned.
+///
+/// The wrapper enables interrupts globally while the handler runs, so that other interrupts can
+/// preempt it. With the `no-nested-interrupts-default` feature enabled, the handlers run with
+/// interrupts disabled instead, unless they opt in with `#[interrupt_handler(int_nr, nested)]`.
+///
+/// With the `nesting-limit` feature enabled, the wrapper counts the nesting depth of the hart and
+/// runs the handler with interrupts disabled, after calling `_max_nesting_exceeded`, when the
+/// depth exceeds `_max_nesting_depth`.
+///
+/// `#[interrupt_handler(int_nr, clear_pending_first)]` clears the CLIC pending bit (`clicintip`)
+/// of the line before the body runs, the correct pattern for edge-triggered sources: an edge
+/// arriving while the body runs pends the interrupt again instead of being lost. It requires
+/// the `clic` feature and an integer interrupt number. Do not use it with level-triggered
+/// sources, their pending bit follows the input signal and must be cleared at the peripheral.
+///
+/// On targets with the C extension, the register saves and restores of the wrapper assemble to
+/// `c.swsp`/`c.lwsp` (`c.sdsp`/`c.ldsp` on RV64) and its stack pointer adjustments to
+/// `c.addi16sp`, as all offsets are within the range of the compressed forms. The CSR accesses,
+/// the call of the handler and `mret` remain 4 bytes long, as does the `li a0` of an interrupt
+/// number above 31.
+#[proc_macro_attribute]
+pub fn interrupt_handler(args: TokenStream, input: TokenStream) -> TokenStream {
+    let f = parse_macro_input!(input as ItemFn);
+    let mut args = parse_macro_input!(args as AttributeArgs);
+    // for the errors reported after the signature has been taken apart
+    let sig_span = f.sig.span();
+
+    // options, may follow the interrupt argument
+    let mut clear_pending_first = false;
+    let mut nested = !cfg!(feature = "no-nested-interrupts-default");
+    args.retain(|arg| match arg {
+        syn::NestedMeta::Meta(syn::Meta::Path(p)) if p.is_ident("clear_pending_first") => {
+            clear_pending_first = true;
+            false
+        }
+        syn::NestedMeta::Meta(syn::Meta::Path(p)) if p.is_ident("nested") => {
+            nested = true;
+            false
+        }
+        _ => true,
+    });
+
+    // several integer arguments share the handler between these interrupts
+    let lines: Vec<syn::LitInt> = args
+        .iter()
+        .filter_map(|arg| match arg {
+            syn::NestedMeta::Lit(syn::Lit::Int(i)) => Some(i.clone()),
+            _ => None,
+        })
+        .collect();
+    let integer_args = !args.is_empty() && lines.len() == args.len();
+
+    // at most one argument should be provided, unless they are all integers
+    if args.len() > 1 && !integer_args {
+        return parse::Error::new(
+            f.span(),
+            "Too many arguments: `#[interrupt_handler(int_nr)]` attribute must have at max one argument, or only interrupt numbers",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let attrs = f.attrs;
+    let ident = f.sig.ident;
+    let block = f.block;
+    let ident_string = ident.to_string();
+
+    let wrapper_ident_string = {
+        // check on number of arguments
+        let arg = args.get(0);
+        match arg {
+            // an argument exists
+            Some(a) =>
+            match a {
+                syn::NestedMeta::Lit(l) => match l {
+                    // option to supply an integer. It is treated the interrupt number, wrapper named after int_<number>
+                    syn::Lit::Int(i) => "int_".to_owned() + &i.to_string(),
+                    default => return parse::Error::new(
+                            default.span(),
+                            "Wrong type: `#[interrupt_handler(int_nr)]` attribute must provide an integer as an argument",
+                        )
+                        .to_compile_error()
+                        .into(),
+                    },
+                syn::NestedMeta::Meta(m) => match m {
+                    // option to supply an identifier (e.g. an Enum name) wrapper is named after identifier
+                    syn::Meta::Path(p) => match p.get_ident() {
+                        Some(i) => i.to_string(),
+                        None => return parse::Error::new(
+                            p.span(),
+                            "Wrong type: `#[interrupt_handler(identifier)]` attribute must provide a single enum value specifying an interrupt from the PAC crate",
+                        )
+                        .to_compile_error()
+                        .into(),
+                    },
+                    default => return parse::Error::new(
+                        default.span(),
+                        "Wrong type: `#[interrupt_handler(..)]` attribute must have either no or one argument of type Int literal or Enum identifier",
+                    )
+                    .to_compile_error()
+                    .into(),
+                    }
+            }
+            // no argument exist -> wrapper is named after original function
+            None => ident_string.clone()
+        }
+    };
+
+    let wrapper_ident_strings: Vec<String> = if lines.len() > 1 {
+        lines.iter().map(|i| format!("int_{}", i)).collect()
+    } else {
+        vec![wrapper_ident_string]
+    };
+
+    // check that function has no arguments, or only the interrupt number
+    let line_arg = match f.sig.inputs.first() {
+        None => None,
+        Some(FnArg::Typed(t))
+            if f.sig.inputs.len() == 1 && integer_args && is_simple_type(&t.ty, "usize") =>
+        {
+            Some(t.pat.clone())
+        }
+        Some(arg) => {
+            return parse::Error::new(
+                arg.span(),
+                "`#[interrupt_handler(..)]` handler function must not have any argument, except `line: usize` with integer interrupt numbers",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+    if lines.len() > 1 && line_arg.is_none() {
+        return parse::Error::new(
+            sig_span,
+            "`#[interrupt_handler(int_nr, int_nr, ..)]` handler function must take the interrupt number: `fn(line: usize)`",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let inputs = f.sig.inputs;
+
+    // check that function does not return anything. Not returning is also an option
+    let valid_ret_type = match f.sig.output {
+        ReturnType::Default => true,
+        ReturnType::Type(_, ref ty) => match **ty {
+            Type::Tuple(ref tuple) => tuple.elems.is_empty(),
+            Type::Never(_) => true,
+            _ => false,
+        },
+    };
+    if !valid_ret_type {
+        return parse::Error::new(
+            f.sig.output.span(),
+            "`#[interrupt_handler(..)]` handler function must not return anything",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let block = if cfg!(feature = "reentrancy-check") {
+        reentrancy_guard(
+            &ident_string,
+            &block,
+            matches!(f.sig.output, ReturnType::Type(_, ref ty) if matches!(**ty, Type::Never(_))),
+        )
+    } else {
+        quote!(#block)
+    };
+
+    // clear the pending bit of the line before the body runs, so that a new edge is not lost
+    let block = if clear_pending_first {
+        let id = match (&line_arg, args.first()) {
+            (Some(pat), _) => quote!(#pat),
+            (None, Some(syn::NestedMeta::Lit(syn::Lit::Int(i)))) => quote!(#i),
+            _ => {
+                return parse::Error::new(
+                    sig_span,
+                    "`clear_pending_first` requires the interrupt number: `#[interrupt_handler(int_nr, clear_pending_first)]`",
+                )
+                .to_compile_error()
+                .into()
+            }
+        };
+        quote!({
+            riscv_rt::clic::set_pending(#id, false);
+            #block
+        })
+    } else {
+        block
+    };
+
+    // with `nesting-limit`, interrupts stay disabled once the nesting depth exceeds the limit
+    let (enable_interrupts, disable_interrupts) = if !nested {
+        ("", "")
+    } else if cfg!(feature = "nesting-limit") {
+        (
+            "csrr t2, mhartid
+    slli t2, t2, 2
+    la t0, __riscv_rt_nesting_depth
+    add t0, t0, t2
+    lw t1, 0(t0)
+    addi t1, t1, 1
+    sw t1, 0(t0)
+    lui t2, %hi(_max_nesting_depth)
+    addi t2, t2, %lo(_max_nesting_depth)
+    bgtu t1, t2, 1f
+    csrsi mstatus, 8 /* enable global interrupts*/
+    j 2f
+1:
+    mv a0, t1
+    jal _max_nesting_exceeded
+2:",
+            "csrci mstatus, 8 /* disable global interrupts*/
+    csrr t2, mhartid
+    slli t2, t2, 2
+    la t0, __riscv_rt_nesting_depth
+    add t0, t0, t2
+    lw t1, 0(t0)
+    addi t1, t1, -1
+    sw t1, 0(t0)",
+        )
+    } else {
+        (
+            "csrsi mstatus, 8 /* enable global interrupts*/",
+            "csrci mstatus, 8 /* disable global interrupts*/",
+        )
+    };
+
+    let handler_ident = format_ident!("{}_handler", ident_string);
+    // the number is passed in `a0`, so the handler needs the C ABI
+    let abi = line_arg.as_ref().map(|_| quote!(extern "C"));
+
+    let mut wrappers = Vec::new();
+    for (n, wrapper_ident_string) in wrapper_ident_strings.iter().enumerate() {
+        let wrapper_ident = format_ident!("{}", wrapper_ident_string);
+        let line = lines.get(n);
+
+        // marker checked by `require_handlers!`
+        let marker = handled_marker(wrapper_ident_string);
+        wrappers.push(quote!(
+            const _: () = {
+                #[export_name = #marker]
+                #[used]
+                static MARKER: u8 = 0;
+            };
+        ));
+
+        // entry of the handler map, see `riscv_rt::handler_map`
+        let number = line.and_then(|l| l.base10_parse::<u32>().ok());
+        let record = handler_map_record(number, wrapper_ident_string, &ident_string);
+        let record_len = record.len();
+        wrappers.push(quote!(
+            const _: () = {
+                #[link_section = ".riscv_rt.handler_map"]
+                #[used]
+                static RECORD: [u8; #record_len] = [#(#record),*];
+            };
+        ));
+
+        if cfg!(feature = "nxti") {
+            if line_arg.is_some() {
+                wrappers.push(quote!(
+                    #[no_mangle]
+                    pub unsafe fn #wrapper_ident() {
+                        #handler_ident(#line)
+                    }
+                ));
+            }
+            continue;
+        }
+
+        let load_line = match (&line_arg, line) {
+            (Some(_), Some(line)) => format!("li a0, {}", line),
+            _ => String::new(),
+        };
+        // the frame layout is the same on RV32 and RV64, in `rb`-byte slots
+        for (arch, store, load, rb) in &[("riscv32", "sw", "lw", 4), ("riscv64", "sd", "ld", 8)] {
+            let assembly_string = format!(
+                ".global {wrapper_ident_string}
+    {wrapper_ident_string}:
+    addi sp, sp, -({rb} * 32)
+    {store} ra, 0*{rb}(sp)
+    {store} t0, 1*{rb}(sp)
+    {store} t1, 2*{rb}(sp)
+    {store} t2, 3*{rb}(sp)
+    {store} a0, 4*{rb}(sp)
+    {store} a1, 5*{rb}(sp)
+    {store} a2, 6*{rb}(sp)
+    {store} a3, 7*{rb}(sp)
+    {store} a4, 8*{rb}(sp)
+    {store} a5, 9*{rb}(sp)
+    {store} a6, 10*{rb}(sp)
+    {store} a7, 11*{rb}(sp)
+    {store} t3, 12*{rb}(sp)
+    {store} t4, 13*{rb}(sp)
+    {store} t5, 14*{rb}(sp)
+    {store} t6, 15*{rb}(sp)
+    csrr t0, mcause
+    csrr t1, mepc
+    {store} t0, 16*{rb}(sp)
+    {store} t1, 17*{rb}(sp)
+    {enable_interrupts}
+    {load_line}
+    jal {handler_ident}
+
+    {disable_interrupts}
+    {load} t0, 16*{rb}(sp)
+    {load} t1, 17*{rb}(sp)
+    csrw mcause, t0
+    csrw mepc, t1
+    {load} ra, 0*{rb}(sp)
+    {load} t0, 1*{rb}(sp)
+    {load} t1, 2*{rb}(sp)
+    {load} t2, 3*{rb}(sp)
+    {load} a0, 4*{rb}(sp)
+    {load} a1, 5*{rb}(sp)
+    {load} a2, 6*{rb}(sp)
+    {load} a3, 7*{rb}(sp)
+    {load} a4, 8*{rb}(sp)
+    {load} a5, 9*{rb}(sp)
+    {load} a6, 10*{rb}(sp)
+    {load} a7, 11*{rb}(sp)
+    {load} t3, 12*{rb}(sp)
+    {load} t4, 13*{rb}(sp)
+    {load} t5, 14*{rb}(sp)
+    {load} t6, 15*{rb}(sp)
+    addi sp, sp, ({rb} * 32)
+    mret
+    "
+            );
+
+            wrappers.push(quote!(
+                #[cfg(target_arch = #arch)]
+                core::arch::global_asm!(#assembly_string);
+            ));
+        }
+    }
+
+    if cfg!(feature = "nxti") && line_arg.is_none() {
+        let wrapper_ident = format_ident!("{}", wrapper_ident_strings[0]);
+        quote!(
+            #(#attrs)*
+            #[no_mangle]
+            pub unsafe fn #wrapper_ident() #block
+
+            #(#wrappers)*
+        )
+        .into()
+    } else {
+        quote!(
+            #(#attrs)*
+            #[no_mangle]
+            pub unsafe #abi fn #handler_ident(#inputs) #block
+
+            #(#wrappers)*
+        )
+        .into()
+    }
+}
+
+/// Encodes an entry of the `.riscv_rt.handler_map` section, in the format documented by
+/// `riscv_rt::handler_map`
+fn handler_map_record(number: Option<u32>, symbol: &str, handler: &str) -> Vec<u8> {
+    let mut record = Vec::new();
+    record.extend_from_slice(&number.unwrap_or(u32::MAX).to_le_bytes());
+    record.extend_from_slice(&(symbol.len() as u16).to_le_bytes());
+    record.extend_from_slice(&(handler.len() as u16).to_le_bytes());
+    record.extend_from_slice(symbol.as_bytes());
+    record.extend_from_slice(handler.as_bytes());
+    while record.len() % 4 != 0 {
+        record.push(0);
+    }
+    record
+}
+
+/// Name of the symbol emitted by `#[interrupt_handler]` for the interrupt `name`
+fn handled_marker(name: &str) -> String {
+    format!("__riscv_rt_handled_{}", name)
+}
+
+/// Fails the link if one of the given interrupts has no `#[interrupt_handler]`
+///
+/// The arguments use the same notation as `#[interrupt_handler(..)]`: an integer for `int_<number>`
+/// or an interrupt enum value from the PAC crate.
+///
+/// ``` ignore
+/// riscv_rt::require_handlers!(5, 7, UART0);
+/// ```
+///
+/// Every `#[interrupt_handler]` defines a marker symbol for its interrupt. This macro references
+/// the markers of the given interrupts from the `.riscv_rt.required_handlers` section, which the
+/// linker script keeps, so a missing handler is reported as an undefined symbol
+/// `__riscv_rt_handled_<interrupt>` instead of silently falling back to `DefaultHandler`.
+///
+/// Only handlers defined with `#[interrupt_handler]` are recognized.
+#[proc_macro]
+pub fn require_handlers(input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(
+        input with syn::punctuated::Punctuated::<syn::NestedMeta, Token![,]>::parse_terminated
+    );
+
+    let mut markers = Vec::new();
+    for arg in args.iter() {
+        let name = match arg {
+            syn::NestedMeta::Lit(syn::Lit::Int(i)) => "int_".to_owned() + &i.to_string(),
+            syn::NestedMeta::Meta(syn::Meta::Path(p)) if p.get_ident().is_some() => {
+                p.get_ident().unwrap().to_string()
+            }
+            default => {
+                return parse::Error::new(
+                    default.span(),
+                    "`require_handlers!` arguments must be integer literals or interrupt enum identifiers",
+                )
+                .to_compile_error()
+                .into()
+            }
+        };
+        markers.push(handled_marker(&name));
+    }
+
+    let idents: Vec<_> = (0..markers.len())
+        .map(|i| format_ident!("REQUIRED_{}", i))
+        .collect();
+    let count = markers.len();
+
+    quote!(
+        const _: () = {
+            extern "C" {
+                #(
+                    #[link_name = #markers]
+                    static #idents: u8;
+                )*
+            }
+
+            #[link_section = ".riscv_rt.required_handlers"]
+            #[used]
+            static REQUIRED: [&u8; #count] = unsafe { [#(&#idents),*] };
+        };
+    )
+    .into()
+}
+
+/// Wraps the body of an interrupt handler with a debug-build reentrancy guard.
+///
+/// The guard is a per-handler and per-hart flag that is set while the body runs. Entering the
+/// handler on a hart whose flag is set (e.g. by accidentally re-enabling the own interrupt inside
+/// the body) panics, other harts may run the handler concurrently. Handlers that never return only
+/// set the flag. The guard is compiled out without `debug_assertions`.
+fn reentrancy_guard(name: &str, block: &syn::Block, diverges: bool) -> proc_macro2::TokenStream {
+    let message = format!("interrupt handler `{}` has been re-entered", name);
+    let enter = quote!(
+        #[cfg(debug_assertions)]
+        #[allow(clippy::declare_interior_mutable_const)]
+        const __RISCV_RT_NOT_ENTERED: core::sync::atomic::AtomicBool =
+            core::sync::atomic::AtomicBool::new(false);
+        #[cfg(debug_assertions)]
+        static __RISCV_RT_REENTRANCY_GUARD: [core::sync::atomic::AtomicBool;
+            riscv_rt::MAX_HART_ID + 1] = [__RISCV_RT_NOT_ENTERED; riscv_rt::MAX_HART_ID + 1];
+        #[cfg(debug_assertions)]
+        let __riscv_rt_reentrancy_guard = &__RISCV_RT_REENTRANCY_GUARD[riscv_rt::hart_id()];
+        #[cfg(debug_assertions)]
+        {
+            if __riscv_rt_reentrancy_guard.swap(true, core::sync::atomic::Ordering::AcqRel) {
+                panic!(#message);
+            }
+        }
+    );
+
+    if diverges {
+        quote!({
+            #enter
+            #block
+        })
+    } else {
+        quote!({
+            #enter
+            #[allow(clippy::redundant_closure_call)]
+            let result = (|| #block)();
+            #[cfg(debug_assertions)]
+            __riscv_rt_reentrancy_guard.store(false, core::sync::atomic::Ordering::Release);
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::handler_map_record;
+
+    // the same records are decoded by the tests of `riscv_rt::handler_map`
+    #[test]
+    fn handler_map_records() {
+        assert_eq!(
+            handler_map_record(Some(7), "int_7", "uart"),
+            [
+                7, 0, 0, 0, 5, 0, 4, 0, b'i', b'n', b't', b'_', b'7', b'u', b'a', b'r', b't', 0, 0,
+                0
+            ]
+        );
+        assert_eq!(
+            handler_map_record(None, "UART0", "on_uart"),
+            [
+                0xff, 0xff, 0xff, 0xff, 5, 0, 7, 0, b'U', b'A', b'R', b'T', b'0', b'o', b'n', b'_',
+                b'u', b'a', b'r', b't'
+            ]
+        );
+    }
+
+    #[test]
+    fn handler_map_padding() {
+        for len in 0..8 {
+            let handler = "h".repeat(len);
+            let record = handler_map_record(Some(0), "int_0", &handler);
+            assert_eq!(record.len(), (8 + 5 + len + 3) / 4 * 4);
+            assert!(record[8 + 5 + len..].iter().all(|&b| b == 0));
+        }
     }
 }
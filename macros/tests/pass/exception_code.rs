@@ -0,0 +1,15 @@
+extern crate riscv_rt_macros;
+
+use riscv_rt_macros::exception;
+
+mod riscv_rt {
+    #[repr(C)]
+    pub struct TrapFrame;
+}
+
+#[exception(2)]
+unsafe fn illegal_instruction(_trap_frame: &riscv_rt::TrapFrame) -> ! {
+    loop {}
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+extern crate riscv_rt_macros;
+
+use riscv_rt_macros::exception;
+
+mod riscv_rt {
+    #[repr(C)]
+    pub struct TrapFrame;
+}
+
+#[exception(IllegalInstruction)]
+fn illegal_instruction(_trap_frame: &riscv_rt::TrapFrame) {}
+
+fn main() {}
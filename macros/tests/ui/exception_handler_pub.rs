@@ -0,0 +1,10 @@
+extern crate riscv_rt_macros;
+
+use riscv_rt_macros::exception_handler;
+
+#[exception_handler]
+pub fn handler(_trap_frame: &riscv_rt::TrapFrame) -> ! {
+    loop {}
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+extern crate riscv_rt_macros;
+
+use riscv_rt_macros::exception_handler;
+
+#[exception_handler]
+fn handler(_trap_frame: &riscv_rt::TrapFrame, _cause: usize) {}
+
+fn main() {}
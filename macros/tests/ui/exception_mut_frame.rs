@@ -0,0 +1,8 @@
+extern crate riscv_rt_macros;
+
+use riscv_rt_macros::exception;
+
+#[exception]
+fn handler(_trap_frame: &mut riscv_rt::TrapFrame) {}
+
+fn main() {}
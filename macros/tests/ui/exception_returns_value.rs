@@ -0,0 +1,10 @@
+extern crate riscv_rt_macros;
+
+use riscv_rt_macros::exception;
+
+#[exception(IllegalInstruction)]
+fn handler(_trap_frame: &riscv_rt::TrapFrame) -> usize {
+    0
+}
+
+fn main() {}
@@ -0,0 +1,93 @@
+#!/usr/bin/env python3
+
+# Stores the CRC-32 checked by `riscv_rt::verify_image` (`image-crc` feature) into a linked ELF.
+#
+# usage: ./patch-image-crc.py target/riscv32imac-unknown-none-elf/release/app
+#
+# Cargo build scripts run before the final link, so the CRC of the image cannot be computed by
+# `build.rs`. Run this script on the linked ELF, before converting it to a binary or flashing it.
+
+import struct
+import sys
+import zlib
+
+
+def main(path):
+    with open(path, 'r+b') as f:
+        elf = bytearray(f.read())
+
+        if elf[:4] != b'\x7fELF' or elf[5] != 1:
+            sys.exit('error: not a little-endian ELF file')
+        is64 = elf[4] == 2
+
+        if is64:
+            phoff, shoff = struct.unpack_from('<QQ', elf, 0x20)
+            phentsize, phnum, shentsize, shnum = struct.unpack_from('<HHHH', elf, 0x36)
+        else:
+            phoff, shoff = struct.unpack_from('<II', elf, 0x1c)
+            phentsize, phnum, shentsize, shnum = struct.unpack_from('<HHHH', elf, 0x2a)
+
+        # loadable segments: (vaddr, file offset, file size)
+        segments = []
+        for i in range(phnum):
+            off = phoff + i * phentsize
+            if is64:
+                ptype, _, poffset, vaddr, _, filesz = struct.unpack_from('<IIQQQQ', elf, off)
+            else:
+                ptype, poffset, vaddr, _, filesz = struct.unpack_from('<IIIII', elf, off)
+            if ptype == 1:
+                segments.append((vaddr, poffset, filesz))
+
+        def offset(addr, size):
+            for vaddr, poffset, filesz in segments:
+                if vaddr <= addr and addr + size <= vaddr + filesz:
+                    return poffset + addr - vaddr
+            sys.exit('error: 0x%x is not part of a loadable segment' % addr)
+
+        # symbol table, with the string table it links to
+        symbols = {}
+        for i in range(shnum):
+            off = shoff + i * shentsize
+            if is64:
+                _, stype, _, _, soffset, size, link, _, _, entsize = struct.unpack_from(
+                    '<IIQQQQIIQQ', elf, off)
+            else:
+                _, stype, _, _, soffset, size, link, _, _, entsize = struct.unpack_from(
+                    '<IIIIIIIIII', elf, off)
+            if stype != 2:
+                continue
+            stroff = shoff + link * shentsize
+            strtab = struct.unpack_from('<Q' if is64 else '<I', elf, stroff + (24 if is64 else 16))[0]
+            for sym in range(soffset, soffset + size, entsize):
+                if is64:
+                    name, _, _, _, value, _ = struct.unpack_from('<IBBHQQ', elf, sym)
+                else:
+                    name, value = struct.unpack_from('<II', elf, sym)
+                end = elf.index(b'\0', strtab + name)
+                symbols[elf[strtab + name:end].decode()] = value
+
+        required = ['_stext', '_text_size', '_image_crc_rodata_start', '_image_crc_rodata_end',
+                    '__riscv_rt_image_crc']
+        for name in required:
+            if name not in symbols:
+                sys.exit('error: symbol `%s` not found, is the `image-crc` feature enabled?' % name)
+
+        text = offset(symbols['_stext'], symbols['_text_size'])
+        crc = zlib.crc32(elf[text:text + symbols['_text_size']])
+        start = symbols['_image_crc_rodata_start']
+        size = symbols['_image_crc_rodata_end'] - start
+        rodata = offset(start, size)
+        crc = zlib.crc32(elf[rodata:rodata + size], crc)
+
+        field = offset(symbols['__riscv_rt_image_crc'], 4)
+        struct.pack_into('<I', elf, field, crc)
+        f.seek(0)
+        f.write(elf)
+
+    print('image CRC-32: 0x%08x' % crc)
+
+
+if __name__ == '__main__':
+    if len(sys.argv) != 2:
+        sys.exit('usage: %s <elf>' % sys.argv[0])
+    main(sys.argv[1])
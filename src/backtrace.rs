@@ -0,0 +1,95 @@
+//! Frame-pointer based backtraces from trap handlers
+
+use core::arch::asm;
+use core::mem::size_of;
+
+use crate::{current_stack_bounds, TrapFrame};
+
+/// Fills `out` with the return addresses of the code interrupted by the current trap, innermost
+/// first, and returns the number of addresses written.
+///
+/// `out[0]` is the address of the interrupted instruction (`mepc`/`sepc`), followed by the return
+/// addresses found by walking the frame pointer (`s0`) chain of the interrupted code. The walk
+/// starts from the frames of the trap handlers, which lead back to the interrupted code through
+/// the trap entry in `.trap` (`_strap..._etrap`). Handlers entered through the
+/// `#[interrupt_handler]` wrappers of the CLIC are not supported, only `out[0]` is written.
+///
+/// This requires that all the code, including `core` and this crate, keeps frame pointers:
+///
+/// ``` text
+/// RUSTFLAGS="-C force-frame-pointers=yes"
+/// ```
+///
+/// Without frame pointers, the result is truncated or meaningless, but the walk stays within the
+/// stack of the calling hart and always terminates: it stops at the first frame pointer that is
+/// misaligned, outside of the stack or not above the previous one, and at a null return address.
+///
+/// `frame` must be the [`TrapFrame`] of the current trap, as received by `ExceptionHandler`.
+/// It bounds the walk through the handler frames, which lie below it.
+pub fn backtrace(frame: &TrapFrame, out: &mut [usize]) -> usize {
+    extern "C" {
+        static _strap: u8;
+        static _etrap: u8;
+    }
+
+    if out.is_empty() {
+        return 0;
+    }
+
+    let trap = unsafe { &_strap as *const u8 as usize..&_etrap as *const u8 as usize };
+    let stack = current_stack_bounds();
+    let frame = frame as *const TrapFrame as usize;
+
+    let epc: usize;
+    #[cfg(not(feature = "s-mode"))]
+    unsafe {
+        asm!("csrr {0}, mepc", out(reg) epc)
+    };
+    #[cfg(feature = "s-mode")]
+    unsafe {
+        asm!("csrr {0}, sepc", out(reg) epc)
+    };
+    out[0] = epc;
+    let mut count = 1;
+
+    let mut fp: usize;
+    unsafe { asm!("mv {0}, s0", out(reg) fp) };
+
+    // handler frames, up to the one called by the trap entry
+    let mut interrupted = false;
+    while !interrupted {
+        match record(fp, stack.start..frame) {
+            Some((ra, prev)) => {
+                interrupted = trap.contains(&ra);
+                fp = prev;
+            }
+            None => return count,
+        }
+    }
+
+    // interrupted code, above the trap frame
+    let mut bottom = frame + size_of::<TrapFrame>();
+    while count < out.len() {
+        match record(fp, bottom..stack.end) {
+            Some((ra, prev)) if ra != 0 => {
+                out[count] = ra;
+                count += 1;
+                bottom = fp + 1;
+                fp = prev;
+            }
+            _ => break,
+        }
+    }
+    count
+}
+
+/// Returns the return address and the previous frame pointer of the frame record of `fp`, i.e.
+/// `fp[-1]` and `fp[-2]`, if `fp` is aligned and the record lies within `bounds`.
+fn record(fp: usize, bounds: core::ops::Range<usize>) -> Option<(usize, usize)> {
+    let record = fp.checked_sub(2 * size_of::<usize>())?;
+    if fp % size_of::<usize>() != 0 || record < bounds.start || fp > bounds.end {
+        return None;
+    }
+    let record = record as *const usize;
+    unsafe { Some((record.add(1).read_volatile(), record.read_volatile())) }
+}
@@ -0,0 +1,88 @@
+//! Staged boot helpers
+
+use core::arch::asm;
+
+/// Jumps to the image located at `addr`, passing `a0` and `a1` to it.
+///
+/// This is meant for a minimal first stage that initializes the platform (e.g. the clocks) and
+/// then starts the application. Interrupts are disabled globally (`mstatus.MIE`, or
+/// `sstatus.SIE` with the `s-mode` feature) and the instruction cache is synchronized
+/// (`fence.i`), in case the image has just been written. All the other general-purpose
+/// registers, except `sp` and `gp`, are cleared, and the jump does not leave a return address.
+///
+/// The target is expected to start like a `riscv-rt` application, i.e. with its own `_start`
+/// that sets up `sp` and `gp` and re-initializes its memory. `mtvec`, `mie` and the other CSRs
+/// keep the values configured by this stage until the target overwrites them.
+///
+/// # Safety
+///
+/// `addr` must be the entry point of a valid image for this hart. Nothing of the current program
+/// (in particular its stack and statics) must be needed anymore.
+pub unsafe fn jump_to(addr: usize, a0: usize, a1: usize) -> ! {
+    #[cfg(not(feature = "s-mode"))]
+    asm!("csrci mstatus, 8");
+    #[cfg(feature = "s-mode")]
+    asm!("csrci sstatus, 2");
+
+    asm!(
+        "fence.i",
+        "li ra, 0",
+        "li tp, 0",
+        "li t0, 0",
+        "li t1, 0",
+        "li t2, 0",
+        "li s0, 0",
+        "li s1, 0",
+        "li a2, 0",
+        "li a3, 0",
+        "li a4, 0",
+        "li a5, 0",
+        "li a6, 0",
+        "li a7, 0",
+        "li s2, 0",
+        "li s3, 0",
+        "li s4, 0",
+        "li s5, 0",
+        "li s6, 0",
+        "li s7, 0",
+        "li s8, 0",
+        "li s9, 0",
+        "li s10, 0",
+        "li s11, 0",
+        "li t3, 0",
+        "li t4, 0",
+        "li t5, 0",
+        "jr t6",
+        in("t6") addr,
+        in("a0") a0,
+        in("a1") a1,
+        options(noreturn),
+    );
+}
+
+/// Restarts the program from `_start`, as after a reset, without resetting the hardware.
+///
+/// `sp` and `gp` are set up again, `.bss` is zeroed, `.data` is re-initialized and `main` is
+/// called again, after `#[pre_init]` and `_setup_interrupts`. With the [`ram`](crate#ram)
+/// feature `.data` is loaded in place, so it is not re-initialized and keeps the values written
+/// before the restart. Interrupts are disabled before the jump, like with [`jump_to`]. `main`
+/// receives the id of the hart as first argument and 0 as the other ones: the arguments of the
+/// original boot (e.g. the device tree passed by the SBI implementation) are not preserved.
+///
+/// Only the core state initialized by `_start` is reset. Peripherals, PMP entries, the CSRs not
+/// written by the startup code and the interrupt controller keep their state, and DMA transfers
+/// in flight keep writing to memory, possibly into the re-initialized statics. They must be
+/// stopped before the restart, or reset by `#[pre_init]` or `main`.
+///
+/// # Safety
+///
+/// Must be called on the hart that initializes the memory (see `_mp_hook`), while the other harts
+/// are stopped: they would observe their statics being re-initialized, and keep using their
+/// stacks. No other code may hold references to statics.
+pub unsafe fn soft_restart() -> ! {
+    extern "C" {
+        fn _start();
+    }
+
+    jump_to(_start as usize, crate::hart_id(), 0)
+}
@@ -0,0 +1,104 @@
+//! Cache-block management operations (Zicbom, Zicboz)
+//!
+//! Each function applies one `cbo.*` instruction to every cache block overlapping the address
+//! range, stepping by the `_cache_block_size` symbol (64 bytes by default), and then executes a
+//! `fence` so that the operations are ordered before subsequent memory and I/O accesses (e.g.
+//! starting a DMA transfer).
+//!
+//! | function       | instruction | feature  |
+//! |----------------|-------------|----------|
+//! | [`clean`]      | `cbo.clean` | `zicbom` |
+//! | [`invalidate`] | `cbo.inval` | `zicbom` |
+//! | [`flush`]      | `cbo.flush` | `zicbom` |
+//! | [`zero`]       | `cbo.zero`  | `zicboz` |
+//!
+//! Below machine mode, the instructions must be enabled in `menvcfg` (and `senvcfg` for user
+//! mode) by the more privileged software, otherwise they raise an illegal instruction exception.
+//! Machine-mode firmware built with this crate can do so with the `_envcfg_init_mask` and
+//! `_envcfg_init_value` symbols. With `CBIE` = `0b01`, `cbo.inval` performs a flush instead.
+
+use core::arch::asm;
+
+extern "C" {
+    static _cache_block_size: u8;
+}
+
+/// Returns the size of a cache block, from the `_cache_block_size` symbol.
+#[inline]
+pub fn block_size() -> usize {
+    unsafe { &_cache_block_size as *const u8 as usize }
+}
+
+/// Calls `op` with the address of every cache block overlapping `addr..addr + len`. A range that
+/// extends past the end of the address space stops at its last block.
+#[inline(always)]
+fn for_each_block(addr: usize, len: usize, op: impl Fn(usize)) {
+    if len == 0 {
+        return;
+    }
+    let size = block_size();
+    let end = addr.saturating_add(len);
+    let mut block = addr & !(size - 1);
+    while block < end {
+        op(block);
+        block = match block.checked_add(size) {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    unsafe { asm!("fence iorw, iorw") };
+}
+
+/// Writes the dirty cache blocks of the range back to memory, keeping them in the cache.
+#[cfg(feature = "zicbom")]
+#[inline]
+pub fn clean(addr: usize, len: usize) {
+    // cbo.clean
+    for_each_block(addr, len, |block| unsafe {
+        asm!(".insn i 0x0f, 2, x0, 1({0})", in(reg) block)
+    });
+}
+
+/// Discards the cache blocks of the range without writing them back.
+///
+/// # Safety
+///
+/// The blocks at the edges of the range are invalidated as a whole, including the bytes outside
+/// `addr..addr + len`: writes to them that were not written back yet are lost.
+#[cfg(feature = "zicbom")]
+#[inline]
+pub unsafe fn invalidate(addr: usize, len: usize) {
+    // cbo.inval
+    for_each_block(
+        addr,
+        len,
+        |block| asm!(".insn i 0x0f, 2, x0, 0({0})", in(reg) block),
+    );
+}
+
+/// Writes the dirty cache blocks of the range back to memory and discards them from the cache.
+#[cfg(feature = "zicbom")]
+#[inline]
+pub fn flush(addr: usize, len: usize) {
+    // cbo.flush
+    for_each_block(addr, len, |block| unsafe {
+        asm!(".insn i 0x0f, 2, x0, 2({0})", in(reg) block)
+    });
+}
+
+/// Zeroes the cache blocks of the range.
+///
+/// # Safety
+///
+/// The blocks at the edges of the range are zeroed as a whole, including the bytes outside
+/// `addr..addr + len`. The range must be valid for writes.
+#[cfg(feature = "zicboz")]
+#[inline]
+pub unsafe fn zero(addr: usize, len: usize) {
+    // cbo.zero
+    for_each_block(
+        addr,
+        len,
+        |block| asm!(".insn i 0x0f, 2, x0, 4({0})", in(reg) block),
+    );
+}
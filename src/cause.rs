@@ -0,0 +1,56 @@
+//! Human-readable names of trap causes
+
+/// Returns a description of the interrupt `code` of `mcause`/`scause` (without the interrupt
+/// bit), for logging unhandled interrupts.
+///
+/// The standard software, timer and external interrupts of every privilege level are named, as
+/// well as the codes reserved by the privileged specification. With the `clic` feature, code 12
+/// is the CLIC software interrupt and codes from 16 upwards are local interrupts. Unknown codes
+/// are described as `"Other"`, so the code itself should be logged along with the description:
+///
+/// ``` ignore
+/// #[riscv_rt::unhandled_interrupt]
+/// fn unhandled(code: usize) {
+///     log::warn!("unhandled interrupt {}: {}", code, riscv_rt::interrupt_description(code));
+/// }
+/// ```
+pub fn interrupt_description(code: usize) -> &'static str {
+    match code {
+        0 => "User software interrupt",
+        1 => "Supervisor software interrupt",
+        3 => "Machine software interrupt",
+        4 => "User timer interrupt",
+        5 => "Supervisor timer interrupt",
+        7 => "Machine timer interrupt",
+        8 => "User external interrupt",
+        9 => "Supervisor external interrupt",
+        11 => "Machine external interrupt",
+        2 | 6 | 10 => "Reserved interrupt",
+        12 if cfg!(feature = "clic") => "CLIC software interrupt",
+        13 if !cfg!(feature = "clic") => "Counter overflow interrupt",
+        16..=4095 if cfg!(feature = "clic") => "Local interrupt",
+        _ => "Other",
+    }
+}
+
+/// Returns a description of the exception `code` of `mcause`/`scause` (without the interrupt
+/// bit), for logging faults. Reserved and custom codes are described as `"Other"`.
+pub fn exception_description(code: usize) -> &'static str {
+    match code {
+        0 => "Instruction address misaligned",
+        1 => "Instruction access fault",
+        2 => "Illegal instruction",
+        3 => "Breakpoint",
+        4 => "Load address misaligned",
+        5 => "Load access fault",
+        6 => "Store/AMO address misaligned",
+        7 => "Store/AMO access fault",
+        8 => "Environment call from U-mode",
+        9 => "Environment call from S-mode",
+        11 => "Environment call from M-mode",
+        12 => "Instruction page fault",
+        13 => "Load page fault",
+        15 => "Store/AMO page fault",
+        _ => "Other",
+    }
+}
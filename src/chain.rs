@@ -0,0 +1,150 @@
+//! Chaining to the trap handler installed before `_setup_interrupts`
+
+use core::arch::asm;
+use core::ptr;
+
+use crate::{xcause, xtvec, TrapFrame};
+
+/// `mtvec`/`stvec` found by [`save_previous_handler`], 0 if none
+static mut PREVIOUS: usize = 0;
+
+/// Stores the current value of `mtvec` (`stvec` with the `s-mode` feature) for
+/// [`chain_to_previous_handler`].
+///
+/// The setups of this crate (the default `_setup_interrupts` and the ones generated by
+/// `#[setup_interrupts(mode = "..")]`) call this before installing their trap vector. A custom
+/// `_setup_interrupts` must call it itself, before writing the trap vector.
+#[inline]
+pub fn save_previous_handler() {
+    let bits = xtvec::read().bits();
+    unsafe { ptr::write_volatile(ptr::addr_of_mut!(PREVIOUS), bits) };
+}
+
+/// Returns the raw `mtvec` (`stvec`) value saved by [`save_previous_handler`], if any.
+#[inline]
+pub fn previous_handler() -> Option<usize> {
+    match unsafe { ptr::read_volatile(ptr::addr_of!(PREVIOUS)) } {
+        0 => None,
+        bits => Some(bits),
+    }
+}
+
+/// Enters the trap handler that was installed before this crate's trap vector, as if the current
+/// trap had been taken by it.
+///
+/// `frame` must be the [`TrapFrame`] of the current trap, as received by `ExceptionHandler`.
+/// The stack pointer and the registers saved in `frame` are restored, and the previous handler is
+/// entered at its base address, or at its vector entry for an interrupt if the previous handler
+/// used the vectored mode. The trap CSRs (`mepc`, `mcause`, `mtval`, `mstatus`) are untouched.
+///
+/// The stack pointer is found from the size of the frame saved by the trap entry of this crate
+/// selected by the features: `default_start_trap` (also behind `stack-overflow-hook`, except for
+/// the overflows reported to `_stack_overflow`), or the entries of `fp-trap-save`,
+/// `full-trap-frame` and `interrupt-stack`. A custom `_start_trap` must save the same frame as
+/// `default_start_trap`.
+/// With `interrupt-stack`, the interrupt stack is handed back to `mscratch` (`sscratch`). With
+/// `fp-trap-save`, the floating-point registers are not restored.
+///
+/// # Constraints on the previous handler
+///
+/// Chaining is one-way: the previous handler is entered with `t6` holding its own address and
+/// with the callee-saved registers (`s0`-`s11`) of the calling handler, as both values of the
+/// interrupted code are lost at this point. It must therefore not return to the interrupted code
+/// with `mret`/`sret`. This suits handlers of a previous boot stage that report the trap and
+/// stop or reset (e.g. a boot ROM's fatal error handler or a debug monitor).
+///
+/// The previous handler runs on the stack of the interrupted code, from the value of `sp` at trap
+/// entry.
+///
+/// # Safety
+///
+/// `frame` must be the frame of the current trap, and the previous handler must still be valid.
+///
+/// # Panics
+///
+/// Panics if no previous handler has been saved.
+pub unsafe fn chain_to_previous_handler(frame: &TrapFrame) -> ! {
+    let bits = previous_handler().expect("no previous trap handler");
+    let base = bits & !3;
+    let cause = xcause::read();
+    let target = if bits & 3 == 1 && cause.is_interrupt() {
+        base + 4 * cause.code()
+    } else {
+        base
+    };
+
+    restore_and_jump(frame, trapped_sp(frame), target)
+}
+
+/// Returns the value of `sp` at trap entry, above the frame saved by the active `_start_trap`.
+///
+/// With the `interrupt-stack` feature, this also gives the interrupt stack back to the scratch
+/// register if the trap was taken on the thread stack, as the trap exit would.
+#[inline(always)]
+unsafe fn trapped_sp(frame: &TrapFrame) -> usize {
+    // in `usize` words
+    #[cfg(all(feature = "fp-trap-save", target_feature = "f"))]
+    let size = crate::fptrap::FRAME_SIZE / core::mem::size_of::<usize>();
+    #[cfg(any(feature = "full-trap-frame", feature = "interrupt-stack"))]
+    let size = 20;
+    #[cfg(not(any(
+        all(feature = "fp-trap-save", target_feature = "f"),
+        feature = "full-trap-frame",
+        feature = "interrupt-stack"
+    )))]
+    let size = 16;
+
+    let top = (frame as *const TrapFrame as *const usize).add(size) as usize;
+
+    #[cfg(feature = "interrupt-stack")]
+    {
+        // interrupted sp of the thread, 0 if the trap nested into a trap handler
+        let thread_sp = ptr::read((frame as *const TrapFrame).add(1) as *const usize);
+        if thread_sp != 0 {
+            #[cfg(not(feature = "s-mode"))]
+            asm!("csrw mscratch, {0}", in(reg) top);
+            #[cfg(feature = "s-mode")]
+            asm!("csrw sscratch, {0}", in(reg) top);
+            return thread_sp;
+        }
+    }
+
+    top
+}
+
+macro_rules! restore_and_jump {
+    ($load:literal, $rb:literal) => {
+        /// Restores `frame` and `sp` to the state at trap entry, then jumps to `target`.
+        #[inline(always)]
+        unsafe fn restore_and_jump(frame: &TrapFrame, sp: usize, target: usize) -> ! {
+            asm!(
+                "mv sp, t4",
+                concat!($load, " ra, 0*", $rb, "(t5)"),
+                concat!($load, " t0, 1*", $rb, "(t5)"),
+                concat!($load, " t1, 2*", $rb, "(t5)"),
+                concat!($load, " t2, 3*", $rb, "(t5)"),
+                concat!($load, " t3, 4*", $rb, "(t5)"),
+                concat!($load, " t4, 5*", $rb, "(t5)"),
+                concat!($load, " a0, 8*", $rb, "(t5)"),
+                concat!($load, " a1, 9*", $rb, "(t5)"),
+                concat!($load, " a2, 10*", $rb, "(t5)"),
+                concat!($load, " a3, 11*", $rb, "(t5)"),
+                concat!($load, " a4, 12*", $rb, "(t5)"),
+                concat!($load, " a5, 13*", $rb, "(t5)"),
+                concat!($load, " a6, 14*", $rb, "(t5)"),
+                concat!($load, " a7, 15*", $rb, "(t5)"),
+                concat!($load, " t5, 6*", $rb, "(t5)"),
+                "jr t6",
+                in("t4") sp,
+                in("t5") frame as *const TrapFrame,
+                in("t6") target,
+                options(noreturn),
+            );
+        }
+    };
+}
+
+#[cfg(target_arch = "riscv32")]
+restore_and_jump!("lw", "4");
+#[cfg(target_arch = "riscv64")]
+restore_and_jump!("ld", "8");
@@ -0,0 +1,555 @@
+//! Helpers for the Core Local Interrupt Controller (CLIC)
+//!
+//! The memory-mapped registers are accessed relative to the `_clic_base` symbol, which has
+//! no default and must be provided by `memory.x` or the platform support crate when these
+//! helpers are used:
+//!
+//! ``` text
+//! _clic_base = 0x1A200000;
+//! ```
+
+use core::arch::asm;
+
+use crate::mmio::MemoryMapper;
+
+/// Register offsets of the CLIC memory map, relative to `_clic_base`
+pub mod addr {
+    /// CLIC configuration register (`cliccfg`)
+    pub const CLICCFG: usize = 0x0;
+    /// CLIC information register (`clicinfo`)
+    pub const CLICINFO: usize = 0x4;
+    /// Start of the per-interrupt registers, 4 bytes per interrupt
+    pub const CLICINT: usize = 0x1000;
+
+    /// Offset of the interrupt pending byte (`clicintip`) within the per-interrupt registers
+    pub const CLICINTIP: usize = 0x0;
+    /// Offset of the interrupt enable byte (`clicintie`) within the per-interrupt registers
+    pub const CLICINTIE: usize = 0x1;
+    /// Offset of the interrupt attribute byte (`clicintattr`) within the per-interrupt registers
+    pub const CLICINTATTR: usize = 0x2;
+    /// Offset of the interrupt control byte (`clicintctl`) within the per-interrupt registers
+    pub const CLICINTCTL: usize = 0x3;
+
+    /// `clicintattr`: selective hardware vectoring
+    pub const CLICINTATTR_SHV: u8 = 1 << 0;
+    /// `clicintattr`: mask of the trigger field
+    pub const CLICINTATTR_TRIG_MASK: u8 = 0b11 << 1;
+    /// `clicintattr` trigger field: level-triggered
+    pub const TRIG_LEVEL: u8 = 0 << 1;
+    /// `clicintattr` trigger field: edge-triggered
+    pub const TRIG_EDGE: u8 = 1 << 1;
+    /// `clicintattr` trigger field: active high, or on the rising edge
+    pub const TRIG_POSITIVE: u8 = 0 << 2;
+    /// `clicintattr` trigger field: active low, or on the falling edge
+    pub const TRIG_NEGATIVE: u8 = 1 << 2;
+
+    /// Returns the offset of the per-interrupt registers of interrupt `id`.
+    #[inline]
+    pub const fn clicint(id: usize) -> usize {
+        CLICINT + 4 * id
+    }
+
+    /// Configuration register of the low 32-bit timer, relative to the timer base
+    pub const TIMER_CFG_LO: usize = 0x00;
+    /// Configuration register of the high 32-bit timer
+    pub const TIMER_CFG_HI: usize = 0x04;
+    /// Counter of the low timer
+    pub const TIMER_CNT_LO: usize = 0x08;
+    /// Counter of the high timer
+    pub const TIMER_CNT_HI: usize = 0x0C;
+    /// Compare value of the low timer
+    pub const TIMER_CMP_LO: usize = 0x10;
+    /// Compare value of the high timer
+    pub const TIMER_CMP_HI: usize = 0x14;
+    /// Writing 1 starts the low timer
+    pub const TIMER_START_LO: usize = 0x18;
+    /// Writing 1 starts the high timer
+    pub const TIMER_START_HI: usize = 0x1C;
+    /// Writing 1 resets the counter of the low timer
+    pub const TIMER_RESET_LO: usize = 0x20;
+    /// Writing 1 resets the counter of the high timer
+    pub const TIMER_RESET_HI: usize = 0x24;
+
+    /// Timer configuration: counting enabled
+    pub const TIMER_CFG_ENABLE: u32 = 1 << 0;
+    /// Timer configuration: reset the counter
+    pub const TIMER_CFG_RESET: u32 = 1 << 1;
+    /// Timer configuration: raise an interrupt when the counter reaches the compare value
+    pub const TIMER_CFG_IRQ_ENABLE: u32 = 1 << 2;
+    /// Timer configuration: clear the counter when it reaches the compare value
+    pub const TIMER_CFG_CMP_CLR: u32 = 1 << 4;
+    /// Timer configuration: stop the timer when it reaches the compare value
+    pub const TIMER_CFG_ONE_SHOT: u32 = 1 << 5;
+    /// Timer configuration: prescaler enabled
+    pub const TIMER_CFG_PRESCALER_ENABLE: u32 = 1 << 6;
+    /// Timer configuration: position of the 8-bit prescaler value
+    pub const TIMER_CFG_PRESCALER_SHIFT: u32 = 8;
+    /// Timer configuration: mask of the prescaler value
+    pub const TIMER_CFG_PRESCALER_MASK: u32 = 0xff << TIMER_CFG_PRESCALER_SHIFT;
+    /// Timer configuration (low timer only): cascade both timers into a 64-bit timer
+    pub const TIMER_CFG_CASCADE: u32 = 1 << 31;
+}
+
+extern "C" {
+    static _clic_base: u8;
+}
+
+/// Returns the base address of the CLIC (`_clic_base`).
+#[inline]
+pub fn base() -> usize {
+    unsafe { &_clic_base as *const u8 as usize }
+}
+
+#[inline]
+fn mapper() -> MemoryMapper {
+    MemoryMapper::new(base())
+}
+
+/// Returns the number of interrupts implemented by the CLIC (`num_interrupt` field of `clicinfo`).
+#[inline]
+pub fn interrupt_count() -> usize {
+    unsafe { mapper().read::<u32>(addr::CLICINFO) as usize & 0x1fff }
+}
+
+/// Sets or clears the pending bit (`clicintip`) of interrupt `id`.
+///
+/// Setting the pending bit of an enabled interrupt triggers its handler through the normal
+/// dispatch path, which allows testing handlers without the actual peripheral. Depending on
+/// the trigger configuration of the line, the hardware may ignore software writes (e.g. for
+/// level-triggered lines whose pending bit follows the input signal).
+#[inline]
+pub fn set_pending(id: usize, pending: bool) {
+    unsafe { mapper().write::<u8>(addr::clicint(id) + addr::CLICINTIP, pending as u8) };
+}
+
+/// Returns whether interrupt `id` is pending.
+#[inline]
+pub fn is_pending(id: usize) -> bool {
+    unsafe { mapper().read::<u8>(addr::clicint(id) + addr::CLICINTIP) & 1 != 0 }
+}
+
+/// Trigger type of a CLIC interrupt line, the `trig` field of `clicintattr`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// Pending while the input is high
+    PositiveLevel,
+    /// Pending while the input is low
+    NegativeLevel,
+    /// Pending on a rising edge of the input
+    PositiveEdge,
+    /// Pending on a falling edge of the input
+    NegativeEdge,
+}
+
+impl Trigger {
+    /// Returns the value of the trigger field, see [`addr::CLICINTATTR_TRIG_MASK`].
+    #[inline]
+    pub const fn bits(self) -> u8 {
+        match self {
+            Trigger::PositiveLevel => addr::TRIG_LEVEL | addr::TRIG_POSITIVE,
+            Trigger::NegativeLevel => addr::TRIG_LEVEL | addr::TRIG_NEGATIVE,
+            Trigger::PositiveEdge => addr::TRIG_EDGE | addr::TRIG_POSITIVE,
+            Trigger::NegativeEdge => addr::TRIG_EDGE | addr::TRIG_NEGATIVE,
+        }
+    }
+}
+
+/// Per-line interrupt control of the CLIC
+///
+/// Each line has its own `clicintie` byte, so enabling or disabling a line is a single byte store
+/// that does not touch the pending bit or any other line. These operations are therefore atomic
+/// with respect to the hardware, which may clear pending bits concurrently, and to handlers
+/// changing other lines, and only need a shared reference.
+///
+/// The configuration of a line (`clicintattr`, `clicintctl`) should be set while it is disabled.
+/// [`set_trigger`](Clic::set_trigger) and [`set_shv`](Clic::set_shv) read, modify and write back
+/// `clicintattr`, which is not atomic: they take `&mut self` so that the same driver cannot
+/// interleave them, but other writers of the same line must be excluded by the caller.
+///
+/// ``` no_run
+/// use riscv_rt::clic::{self, Clic, Trigger};
+///
+/// let mut clic = unsafe { Clic::new(clic::base()) };
+/// clic.set_trigger(11, Trigger::PositiveEdge);
+/// unsafe { clic.enable(11) };
+/// ```
+pub struct Clic {
+    mapper: MemoryMapper,
+}
+
+impl Clic {
+    /// Creates a driver for the CLIC located at `base`, usually [`base()`].
+    ///
+    /// # Safety
+    ///
+    /// `base` must be the base address of the CLIC, and the lines configured through this driver
+    /// must not be configured concurrently through another one.
+    #[inline]
+    pub const unsafe fn new(base: usize) -> Self {
+        Self {
+            mapper: MemoryMapper::new(base),
+        }
+    }
+
+    /// Enables interrupt `id`.
+    ///
+    /// # Safety
+    ///
+    /// Enabling an interrupt may break critical sections relying on it being disabled.
+    #[inline]
+    pub unsafe fn enable(&self, id: usize) {
+        self.mapper
+            .write::<u8>(addr::clicint(id) + addr::CLICINTIE, 1);
+    }
+
+    /// Disables interrupt `id`.
+    #[inline]
+    pub fn disable(&self, id: usize) {
+        unsafe {
+            self.mapper
+                .write::<u8>(addr::clicint(id) + addr::CLICINTIE, 0)
+        };
+    }
+
+    /// Returns whether interrupt `id` is enabled.
+    #[inline]
+    pub fn is_enabled(&self, id: usize) -> bool {
+        unsafe { self.mapper.read::<u8>(addr::clicint(id) + addr::CLICINTIE) & 1 != 0 }
+    }
+
+    /// Runs `f` with interrupt `id` disabled, then restores its previous enable state.
+    ///
+    /// The handler of `id` cannot run while `f` is executed, which allows `f` to access data
+    /// shared with it. Other interrupts are not affected.
+    #[inline]
+    pub fn with_disabled<R>(&self, id: usize, f: impl FnOnce() -> R) -> R {
+        let enabled = self.is_enabled(id);
+        self.disable(id);
+        let r = f();
+        if enabled {
+            unsafe { self.enable(id) };
+        }
+        r
+    }
+
+    /// Sets the control byte (`clicintctl`) of interrupt `id`, which holds its level and priority.
+    ///
+    /// The level occupies the `cliccfg.nlbits` most significant bits, the priority the next
+    /// ones. The bits that the CLIC does not implement read as 1.
+    ///
+    /// # Safety
+    ///
+    /// Raising the level of an interrupt may let it preempt critical sections relying on the
+    /// interrupt level (see [`set_threshold`]).
+    #[inline]
+    pub unsafe fn set_priority(&mut self, id: usize, level: u8) {
+        self.mapper
+            .write::<u8>(addr::clicint(id) + addr::CLICINTCTL, level);
+    }
+
+    /// Returns the control byte (`clicintctl`) of interrupt `id`.
+    #[inline]
+    pub fn priority(&self, id: usize) -> u8 {
+        unsafe { self.mapper.read::<u8>(addr::clicint(id) + addr::CLICINTCTL) }
+    }
+
+    /// Sets the trigger type of interrupt `id`, keeping the other fields of `clicintattr`.
+    ///
+    /// The CLIC may hardwire the trigger type of some lines, in which case the write is ignored.
+    #[inline]
+    pub fn set_trigger(&mut self, id: usize, trigger: Trigger) {
+        unsafe {
+            self.mapper
+                .modify::<u8, _>(addr::clicint(id) + addr::CLICINTATTR, |attr| {
+                    attr & !addr::CLICINTATTR_TRIG_MASK | trigger.bits()
+                })
+        };
+    }
+
+    /// Selects whether interrupt `id` is dispatched through the vector table (`mtvt`), or through
+    /// the common trap handler (`_start_trap`, or the NXTI handler with the `nxti` feature),
+    /// keeping the other fields of `clicintattr`.
+    #[inline]
+    pub fn set_shv(&mut self, id: usize, vectored: bool) {
+        unsafe {
+            self.mapper
+                .modify::<u8, _>(addr::clicint(id) + addr::CLICINTATTR, |attr| {
+                    if vectored {
+                        attr | addr::CLICINTATTR_SHV
+                    } else {
+                        attr & !addr::CLICINTATTR_SHV
+                    }
+                })
+        };
+    }
+}
+
+/// Interrupt level threshold that masks all interrupts
+pub const THRESHOLD_MASK_ALL: u8 = 0xff;
+
+/// Sets the interrupt level threshold (`mintthresh`).
+///
+/// Only interrupts with a level strictly greater than `level` are taken. Unless the
+/// `clic-unmasked-boot` feature is enabled, the runtime sets the threshold to
+/// [`THRESHOLD_MASK_ALL`] at boot, so the application has to lower it (e.g. to 0) once
+/// all the handlers it needs are configured.
+#[inline]
+pub fn set_threshold(level: u8) {
+    unsafe { asm!("csrw 0x347, {0}", in(reg) level as usize) };
+}
+
+/// Returns the current interrupt level threshold (`mintthresh`).
+#[inline]
+pub fn threshold() -> u8 {
+    let level: usize;
+    unsafe { asm!("csrr {0}, 0x347", out(reg) level) };
+    level as u8
+}
+
+/// Claims the next pending interrupt through the `mnxti` CSR.
+///
+/// This is the primitive the NXTI trap handler (`nxti` feature) is built on. A single
+/// `csrrsi mnxti, MIE` instruction atomically:
+///
+/// * sets `mstatus.MIE`, i.e. **enables interrupts globally** as a side effect,
+/// * selects the highest-level pending interrupt above the current threshold and, for
+///   edge-triggered lines, clears its pending bit,
+/// * returns the address of its entry in the interrupt vector table (`mtvt`).
+///
+/// Calling the returned entry jumps to the handler of the claimed interrupt. `None` is
+/// returned if no suitable interrupt is pending.
+///
+/// # Safety
+///
+/// Must only be called from a trap handler running in CLIC mode with the trap context saved,
+/// and interrupts will be enabled when it returns. The handlers reached through the vector
+/// table must be plain functions, as generated by `#[interrupt_handler]` with the `nxti`
+/// feature.
+#[inline]
+pub unsafe fn next_interrupt() -> Option<unsafe extern "C" fn()> {
+    let entry: usize;
+    asm!("csrrsi {0}, 0x345, 8", out(reg) entry);
+    if entry == 0 {
+        None
+    } else {
+        Some(core::mem::transmute::<usize, unsafe extern "C" fn()>(entry))
+    }
+}
+
+/// Driver for the timer unit next to the CLIC
+///
+/// The unit consists of two 32-bit timers, which can be cascaded into a single 64-bit timer. The
+/// methods configure the low timer, which holds the configuration of the cascaded timer. The
+/// register offsets are defined in [`addr`] and are relative to the base address of the timer.
+pub struct Timer {
+    mapper: MemoryMapper,
+}
+
+impl Timer {
+    /// Creates a driver for the timer located at `base`.
+    ///
+    /// # Safety
+    ///
+    /// `base` must be the base address of the timer unit, and no other driver may configure it
+    /// concurrently.
+    #[inline]
+    pub const unsafe fn new(base: usize) -> Self {
+        Self {
+            mapper: MemoryMapper::new(base),
+        }
+    }
+
+    #[inline]
+    fn set_cfg(&mut self, mask: u32, set: bool) {
+        unsafe {
+            if set {
+                self.mapper.set_bits(addr::TIMER_CFG_LO, mask);
+            } else {
+                self.mapper.clear_bits(addr::TIMER_CFG_LO, mask);
+            }
+        }
+    }
+
+    #[inline]
+    fn is_cascaded(&self) -> bool {
+        unsafe { self.mapper.read::<u32>(addr::TIMER_CFG_LO) & addr::TIMER_CFG_CASCADE != 0 }
+    }
+
+    /// Starts counting.
+    #[inline]
+    pub fn start(&mut self) {
+        unsafe { self.mapper.write::<u32>(addr::TIMER_START_LO, 1) };
+    }
+
+    /// Stops counting, the counter keeps its value.
+    #[inline]
+    pub fn stop(&mut self) {
+        self.set_cfg(addr::TIMER_CFG_ENABLE, false);
+    }
+
+    /// Resets the counter to 0 (both halves when cascaded).
+    #[inline]
+    pub fn reset(&mut self) {
+        unsafe {
+            self.mapper.write::<u32>(addr::TIMER_RESET_LO, 1);
+            if self.is_cascaded() {
+                self.mapper.write::<u32>(addr::TIMER_RESET_HI, 1);
+            }
+        }
+    }
+
+    /// Divides the input clock by `value + 1`, or disables the prescaler if `value` is `None`.
+    #[inline]
+    pub fn set_prescaler(&mut self, value: Option<u8>) {
+        unsafe {
+            self.mapper.modify::<u32, _>(addr::TIMER_CFG_LO, |cfg| {
+                let cfg =
+                    cfg & !(addr::TIMER_CFG_PRESCALER_MASK | addr::TIMER_CFG_PRESCALER_ENABLE);
+                match value {
+                    Some(value) => {
+                        cfg | addr::TIMER_CFG_PRESCALER_ENABLE
+                            | (value as u32) << addr::TIMER_CFG_PRESCALER_SHIFT
+                    }
+                    None => cfg,
+                }
+            })
+        };
+    }
+
+    /// Sets the compare value. Only the low 32 bits are used unless the timer is cascaded.
+    #[inline]
+    pub fn set_compare(&mut self, value: u64) {
+        unsafe {
+            self.mapper.write::<u32>(addr::TIMER_CMP_LO, value as u32);
+            if self.is_cascaded() {
+                self.mapper
+                    .write::<u32>(addr::TIMER_CMP_HI, (value >> 32) as u32);
+            }
+        }
+    }
+
+    /// Returns the counter value.
+    ///
+    /// When cascaded, the high half is re-read until it is stable, so that a carry from the low
+    /// half between the reads cannot produce a wrong value.
+    #[inline]
+    pub fn counter(&self) -> u64 {
+        unsafe {
+            if !self.is_cascaded() {
+                return self.mapper.read::<u32>(addr::TIMER_CNT_LO) as u64;
+            }
+            loop {
+                let hi = self.mapper.read::<u32>(addr::TIMER_CNT_HI);
+                let lo = self.mapper.read::<u32>(addr::TIMER_CNT_LO);
+                if hi == self.mapper.read::<u32>(addr::TIMER_CNT_HI) {
+                    return (hi as u64) << 32 | lo as u64;
+                }
+            }
+        }
+    }
+
+    /// Raises an interrupt when the counter reaches the compare value.
+    #[inline]
+    pub fn enable_irq(&mut self, enable: bool) {
+        self.set_cfg(addr::TIMER_CFG_IRQ_ENABLE, enable);
+    }
+
+    /// Stops the timer when the counter reaches the compare value, instead of clearing the
+    /// counter and continuing.
+    #[inline]
+    pub fn one_shot(&mut self, one_shot: bool) {
+        self.set_cfg(addr::TIMER_CFG_ONE_SHOT, one_shot);
+        self.set_cfg(addr::TIMER_CFG_CMP_CLR, !one_shot);
+    }
+
+    /// Cascades both 32-bit timers into a single 64-bit timer.
+    #[inline]
+    pub fn cascade(&mut self, cascade: bool) {
+        self.set_cfg(addr::TIMER_CFG_CASCADE, cascade);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Registers of interrupts 0 to 3, starting at `CLICINT`
+    struct Registers([u8; 16]);
+
+    impl Registers {
+        fn clic(&mut self) -> Clic {
+            unsafe { Clic::new(self.0.as_mut_ptr() as usize - addr::CLICINT) }
+        }
+
+        fn byte(&self, id: usize, offset: usize) -> u8 {
+            self.0[addr::clicint(id) - addr::CLICINT + offset]
+        }
+    }
+
+    #[test]
+    fn enable() {
+        let mut registers = Registers([0xa5; 16]);
+        let clic = registers.clic();
+        unsafe { clic.enable(2) };
+        assert!(clic.is_enabled(2));
+        clic.disable(1);
+        assert!(!clic.is_enabled(1));
+        for id in 0..4 {
+            let ie = match id {
+                1 => 0,
+                2 => 1,
+                _ => 0xa5,
+            };
+            assert_eq!(registers.byte(id, addr::CLICINTIP), 0xa5);
+            assert_eq!(registers.byte(id, addr::CLICINTIE), ie);
+            assert_eq!(registers.byte(id, addr::CLICINTATTR), 0xa5);
+            assert_eq!(registers.byte(id, addr::CLICINTCTL), 0xa5);
+        }
+    }
+
+    #[test]
+    fn with_disabled() {
+        let mut registers = Registers([0; 16]);
+        let clic = registers.clic();
+        unsafe { clic.enable(3) };
+        let inside = clic.with_disabled(3, || clic.is_enabled(3));
+        assert!(!inside);
+        assert!(clic.is_enabled(3));
+        clic.with_disabled(0, || ());
+        assert!(!clic.is_enabled(0));
+    }
+
+    #[test]
+    fn priority() {
+        let mut registers = Registers([0; 16]);
+        let mut clic = registers.clic();
+        unsafe { clic.set_priority(1, 0xc0) };
+        assert_eq!(clic.priority(1), 0xc0);
+        assert_eq!(registers.byte(1, addr::CLICINTCTL), 0xc0);
+        assert_eq!(registers.byte(0, addr::CLICINTCTL), 0);
+        assert_eq!(registers.byte(2, addr::CLICINTCTL), 0);
+    }
+
+    #[test]
+    fn attributes() {
+        let mut registers = Registers([0; 16]);
+        let mut clic = registers.clic();
+        clic.set_shv(2, true);
+        clic.set_trigger(2, Trigger::NegativeEdge);
+        assert_eq!(
+            registers.byte(2, addr::CLICINTATTR),
+            addr::CLICINTATTR_SHV | addr::TRIG_EDGE | addr::TRIG_NEGATIVE
+        );
+
+        let mut clic = registers.clic();
+        clic.set_trigger(2, Trigger::PositiveLevel);
+        assert_eq!(registers.byte(2, addr::CLICINTATTR), addr::CLICINTATTR_SHV);
+
+        let mut clic = registers.clic();
+        clic.set_shv(2, false);
+        assert_eq!(registers.byte(2, addr::CLICINTATTR), 0);
+        for id in [0, 1, 3] {
+            assert_eq!(registers.byte(id, addr::CLICINTATTR), 0);
+        }
+    }
+}
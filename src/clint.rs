@@ -0,0 +1,129 @@
+//! Helpers for the Core Local Interruptor (CLINT)
+//!
+//! The memory-mapped registers are accessed relative to the `_clint_base` symbol, which defaults
+//! to `0x2000000` (QEMU `virt` and most SiFive cores) and can be overridden in `memory.x`, or at
+//! run time with [`set_base`] (e.g. from the device tree, see [`crate::fdt`]).
+//!
+//! Each hart has its own `msip` and `mtimecmp` register, `mtime` is shared:
+//!
+//! | register        | offset                    |
+//! |-----------------|---------------------------|
+//! | `msip[hart]`    | `0x0000 + 4 * hart`       |
+//! | `mtimecmp[hart]`| `0x4000 + 8 * hart`       |
+//! | `mtime`         | `0xbff8`                  |
+
+use core::ptr;
+
+use crate::mmio::MemoryMapper;
+
+/// Register offsets of the CLINT memory map, relative to `_clint_base`
+pub mod addr {
+    /// Start of the `msip` registers, 4 bytes per hart
+    pub const MSIP: usize = 0x0;
+    /// Start of the `mtimecmp` registers, 8 bytes per hart
+    pub const MTIMECMP: usize = 0x4000;
+    /// The `mtime` register
+    pub const MTIME: usize = 0xbff8;
+
+    /// Returns the offset of the `msip` register of hart `hartid`.
+    #[inline]
+    pub const fn msip(hartid: usize) -> usize {
+        MSIP + 4 * hartid
+    }
+
+    /// Returns the offset of the `mtimecmp` register of hart `hartid`.
+    #[inline]
+    pub const fn mtimecmp(hartid: usize) -> usize {
+        MTIMECMP + 8 * hartid
+    }
+}
+
+extern "C" {
+    static _clint_base: u8;
+}
+
+/// Base address set at run time, 0 if `_clint_base` is used
+static mut BASE: usize = 0;
+
+/// Returns the base address of the CLINT: the one set with [`set_base`], or `_clint_base`.
+#[inline]
+pub fn base() -> usize {
+    match unsafe { ptr::read_volatile(ptr::addr_of!(BASE)) } {
+        0 => unsafe { &_clint_base as *const u8 as usize },
+        base => base,
+    }
+}
+
+/// Overrides `_clint_base` at run time, e.g. with the address found by [`crate::fdt::from_ptr`].
+///
+/// # Safety
+///
+/// `base` must be the base address of the CLINT. The override lives in `.bss`, so this must not
+/// be called before memory initialization is completed, and must not race with the other
+/// helpers of this module.
+#[inline]
+pub unsafe fn set_base(base: usize) {
+    ptr::write_volatile(ptr::addr_of_mut!(BASE), base);
+}
+
+#[inline]
+fn mapper() -> MemoryMapper {
+    MemoryMapper::new(base())
+}
+
+/// Reads the 64-bit value of the register at `offset`.
+///
+/// On RV32 the high word is read before and after the low word, and the read is repeated if
+/// the low word wrapped around in between.
+#[inline]
+fn read64(offset: usize) -> u64 {
+    let clint = mapper();
+    #[cfg(target_arch = "riscv32")]
+    loop {
+        let hi = unsafe { clint.read::<u32>(offset + 4) };
+        let lo = unsafe { clint.read::<u32>(offset) };
+        if hi == unsafe { clint.read::<u32>(offset + 4) } {
+            return (u64::from(hi) << 32) | u64::from(lo);
+        }
+    }
+    #[cfg(not(target_arch = "riscv32"))]
+    unsafe {
+        clint.read::<u64>(offset)
+    }
+}
+
+/// Returns the current value of the `mtime` register.
+#[inline]
+pub fn mtime() -> u64 {
+    read64(addr::MTIME)
+}
+
+/// Returns the `mtimecmp` register of hart `hartid`.
+#[inline]
+pub fn mtimecmp(hartid: usize) -> u64 {
+    read64(addr::mtimecmp(hartid))
+}
+
+/// Writes `value` to the `mtimecmp` register of hart `hartid`.
+///
+/// The machine timer interrupt of the hart is pending while `mtime >= mtimecmp`, writing a value
+/// in the future therefore also clears a pending timer interrupt. On RV32 the low word is first
+/// set to its maximum, so that no intermediate value below both the old and the new compare
+/// value triggers a spurious interrupt.
+///
+/// # Safety
+///
+/// `hartid` must be a hart of the CLINT at `_clint_base`.
+#[inline]
+pub unsafe fn set_mtimecmp(hartid: usize, value: u64) {
+    let clint = mapper();
+    let offset = addr::mtimecmp(hartid);
+    #[cfg(target_arch = "riscv32")]
+    {
+        clint.write::<u32>(offset, u32::MAX);
+        clint.write::<u32>(offset + 4, (value >> 32) as u32);
+        clint.write::<u32>(offset, value as u32);
+    }
+    #[cfg(not(target_arch = "riscv32"))]
+    clint.write::<u64>(offset, value);
+}
@@ -0,0 +1,82 @@
+//! Machine identity registers
+
+use core::arch::asm;
+
+macro_rules! read_csr {
+    ($csr:literal) => {{
+        let bits: usize;
+        unsafe { asm!(concat!("csrr {0}, ", $csr), out(reg) bits) };
+        bits
+    }};
+}
+
+/// Identity of the hart, read from the machine information registers
+///
+/// Any of these registers may be hardwired to zero, meaning that the value is not implemented.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CpuId {
+    vendor: usize,
+    arch: usize,
+    imp: usize,
+    hart: usize,
+}
+
+impl CpuId {
+    /// JEDEC manufacturer ID of the provider of the core (`mvendorid`), if implemented.
+    #[inline]
+    pub fn vendor_id(&self) -> Option<usize> {
+        non_zero(self.vendor)
+    }
+
+    /// Base microarchitecture of the hart (`marchid`), if implemented.
+    #[inline]
+    pub fn arch_id(&self) -> Option<usize> {
+        non_zero(self.arch)
+    }
+
+    /// Version of the processor implementation (`mimpid`), if implemented.
+    #[inline]
+    pub fn imp_id(&self) -> Option<usize> {
+        non_zero(self.imp)
+    }
+
+    /// Hart id of the hart that read the registers (`mhartid`).
+    #[inline]
+    pub fn hart_id(&self) -> usize {
+        self.hart
+    }
+}
+
+#[inline]
+fn non_zero(bits: usize) -> Option<usize> {
+    if bits == 0 {
+        None
+    } else {
+        Some(bits)
+    }
+}
+
+/// Reads the identity registers of the calling hart.
+///
+/// These registers are only accessible in machine mode, this function is therefore not
+/// available with the `s-mode` feature.
+#[inline]
+pub fn cpu_id() -> CpuId {
+    CpuId {
+        vendor: read_csr!("0xF11"),
+        arch: read_csr!("0xF12"),
+        imp: read_csr!("0xF13"),
+        hart: read_csr!("0xF14"),
+    }
+}
+
+/// Reads the address of the configuration data structure (`mconfigptr`), if implemented.
+///
+/// # Safety
+///
+/// `mconfigptr` was introduced with version 1.12 of the privileged specification. Reading it
+/// on cores implementing an older version raises an illegal instruction exception.
+#[inline]
+pub unsafe fn mconfigptr() -> Option<usize> {
+    non_zero(read_csr!("0xF15"))
+}
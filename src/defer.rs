@@ -0,0 +1,48 @@
+//! Deferred initialization, see the `defer` feature
+
+use core::mem::MaybeUninit;
+
+/// Maximum number of functions that can be queued with [`defer`]
+pub const DEFER_CAPACITY: usize = 8;
+
+// Both live in `.uninit`, so that the queue survives the initialization of `.bss`. `LEN` is
+// reset by `start_rust` before `__pre_init` is called.
+#[link_section = ".uninit.riscv_rt.defer"]
+static mut QUEUE: [MaybeUninit<fn()>; DEFER_CAPACITY] = [MaybeUninit::uninit(); DEFER_CAPACITY];
+#[link_section = ".uninit.riscv_rt.defer"]
+static mut LEN: usize = 0;
+
+/// Queues `f` to be run once, right before `main` is called.
+///
+/// The queued functions are run in order by the initializing hart (the one whose `_mp_hook`
+/// returned `true`), after `_setup_interrupts`. A function may queue further functions, which
+/// are run in the same pass. If [`DEFER_CAPACITY`] functions are already queued, `f` is
+/// returned as the error.
+///
+/// # Safety
+///
+/// Must only be called by the initializing hart before `main`, i.e. from `#[pre_init]`, from
+/// `_setup_interrupts` or from a deferred function.
+pub unsafe fn defer(f: fn()) -> Result<(), fn()> {
+    if LEN == DEFER_CAPACITY {
+        return Err(f);
+    }
+    QUEUE[LEN] = MaybeUninit::new(f);
+    LEN += 1;
+    Ok(())
+}
+
+/// Empties the queue, the contents of `.uninit` are undefined at boot.
+#[inline]
+pub(crate) unsafe fn reset() {
+    LEN = 0;
+}
+
+/// Runs the queued functions in order.
+pub(crate) unsafe fn run() {
+    let mut i = 0;
+    while i < LEN {
+        QUEUE[i].assume_init()();
+        i += 1;
+    }
+}
@@ -0,0 +1,93 @@
+//! Temporary exception handlers, see [`with_exception_handler`], and resumption of the trapped code
+
+use core::arch::asm;
+use core::ptr::{addr_of, addr_of_mut};
+use core::sync::atomic::{compiler_fence, Ordering};
+
+use crate::TrapFrame;
+
+/// Handler installed by [`with_exception_handler`], `None` outside of it
+static mut HANDLER: Option<fn(&TrapFrame)> = None;
+
+/// Runs `f` with `handler` handling all the exceptions, instead of the exception handlers
+/// (`ExceptionHandler`, `IllegalInstruction`, ...), and returns its result.
+///
+/// This allows code that expects a fault, e.g. probing whether an address is backed by memory.
+/// When `handler` returns, the trapped code resumes at `mepc`/`sepc`, so it must advance
+/// `mepc`/`sepc` past the faulting instruction, e.g. with [`skip_instruction`]:
+///
+/// ``` ignore
+/// static mut FAULTED: bool = false;
+///
+/// fn skip(_: &riscv_rt::TrapFrame) {
+///     unsafe {
+///         FAULTED = true;
+///         riscv_rt::skip_instruction();
+///     }
+/// }
+///
+/// fn is_mapped(addr: usize) -> bool {
+///     unsafe {
+///         FAULTED = false;
+///         riscv_rt::with_exception_handler(skip, || {
+///             core::arch::asm!("lw zero, 0({0})", in(reg) addr)
+///         });
+///         !FAULTED
+///     }
+/// }
+/// ```
+///
+/// Nested calls are supported, the previous handler is restored when `f` returns.
+///
+/// # Safety
+///
+/// The handler is global: while `f` runs, it also receives the exceptions of the other harts and
+/// of the interrupt handlers. Concurrent calls on several harts restore each other's handlers
+/// in the wrong order. If `f` unwinds, the previous handler is not restored.
+///
+/// The handler is stored in `.bss`, so this must not be called before memory initialization is
+/// completed.
+pub unsafe fn with_exception_handler<R>(handler: fn(&TrapFrame), f: impl FnOnce() -> R) -> R {
+    let previous = core::ptr::read_volatile(addr_of!(HANDLER));
+    core::ptr::write_volatile(addr_of_mut!(HANDLER), Some(handler));
+    // keep the accesses of `f` within the scope of the handler
+    compiler_fence(Ordering::SeqCst);
+    let result = f();
+    compiler_fence(Ordering::SeqCst);
+    core::ptr::write_volatile(addr_of_mut!(HANDLER), previous);
+    result
+}
+
+/// Returns the handler installed by [`with_exception_handler`], if any.
+#[inline]
+pub(crate) fn handler() -> Option<fn(&TrapFrame)> {
+    unsafe { core::ptr::read_volatile(addr_of!(HANDLER)) }
+}
+
+/// Advances `mepc` (`sepc` with the `s-mode` feature) past the instruction it points to, by 2 bytes
+/// if it is compressed and 4 otherwise, so that an exception handler that returns resumes the
+/// trapped code after the instruction that raised the exception.
+///
+/// # Safety
+///
+/// Must be called at most once from an exception handler, before it returns, and the faulting
+/// instruction must be readable: not after an instruction access or page fault, whose `mepc` may
+/// not point to memory.
+#[inline]
+pub unsafe fn skip_instruction() {
+    let epc: usize;
+    #[cfg(not(feature = "s-mode"))]
+    asm!("csrr {0}, mepc", out(reg) epc);
+    #[cfg(feature = "s-mode")]
+    asm!("csrr {0}, sepc", out(reg) epc);
+    // the two lowest bits of a 32-bit instruction are set, the parcel is 16-bit aligned
+    let len = if *(epc as *const u16) & 0b11 == 0b11 {
+        4
+    } else {
+        2
+    };
+    #[cfg(not(feature = "s-mode"))]
+    asm!("csrw mepc, {0}", in(reg) epc + len);
+    #[cfg(feature = "s-mode")]
+    asm!("csrw sepc, {0}", in(reg) epc + len);
+}
@@ -0,0 +1,271 @@
+//! Minimal flattened device tree (FDT) parser
+//!
+//! Boot loaders and SBI implementations pass the address of a device tree blob in `a1`, which
+//! `start_rust` forwards to the `#[entry]` function:
+//!
+//! ``` ignore
+//! #[riscv_rt::entry]
+//! fn main(hartid: usize, fdt: usize, _: usize) -> ! {
+//!     if let Some(platform) = unsafe { riscv_rt::fdt::from_ptr(fdt) } {
+//!         if let Some(base) = platform.clint_base {
+//!             unsafe { riscv_rt::clint::set_base(base) };
+//!         }
+//!     }
+//!     // ...
+//! }
+//! ```
+//!
+//! Only the nodes needed to configure the helpers of this crate are considered: the CLINT
+//! (`riscv,clint0` or `sifive,clint0`), the PLIC (`riscv,plic0` or `sifive,plic-1.0.0`) and the
+//! harts (`device_type = "cpu"`). The parser does not allocate and never panics on malformed
+//! input: [`parse`] returns `None` for a blob it cannot read, in which case the link-time
+//! symbols (e.g. `_clint_base`) remain in use.
+
+use core::convert::TryInto;
+
+const MAGIC: u32 = 0xd00d_feed;
+
+const FDT_BEGIN_NODE: u32 = 1;
+const FDT_END_NODE: u32 = 2;
+const FDT_PROP: u32 = 3;
+const FDT_NOP: u32 = 4;
+const FDT_END: u32 = 9;
+
+/// Deepest node nesting the parser keeps track of
+const MAX_DEPTH: usize = 16;
+
+const CLINT_COMPATIBLE: &[&[u8]] = &[b"riscv,clint0", b"sifive,clint0"];
+const PLIC_COMPATIBLE: &[&[u8]] = &[b"riscv,plic0", b"sifive,plic-1.0.0"];
+
+/// Platform information extracted from a device tree
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Platform {
+    /// Base address of the first CLINT node
+    pub clint_base: Option<usize>,
+    /// Base address of the first PLIC node
+    pub plic_base: Option<usize>,
+    /// Number of nodes with `device_type = "cpu"`
+    pub hart_count: usize,
+}
+
+/// Parses the device tree blob at `addr`.
+///
+/// Returns `None` if `addr` does not point to a valid device tree.
+///
+/// # Safety
+///
+/// If `addr` points to a device tree header, the whole blob (`totalsize` bytes) must be readable.
+/// The 40 bytes of the header must be readable in any case.
+pub unsafe fn from_ptr(addr: usize) -> Option<Platform> {
+    if addr == 0 || addr % 4 != 0 {
+        return None;
+    }
+    let header = core::slice::from_raw_parts(addr as *const u8, 8);
+    if be32(header, 0)? != MAGIC {
+        return None;
+    }
+    let size = be32(header, 4)? as usize;
+    parse(core::slice::from_raw_parts(addr as *const u8, size))
+}
+
+/// Parses the device tree `blob`.
+///
+/// Returns `None` if the blob is not a valid device tree.
+pub fn parse(blob: &[u8]) -> Option<Platform> {
+    if be32(blob, 0)? != MAGIC {
+        return None;
+    }
+    let structs = be32(blob, 8)? as usize;
+    let strings = be32(blob, 12)? as usize;
+
+    let mut platform = Platform::default();
+    // `#address-cells` of the nodes on the current path, index 0 is the root
+    let mut address_cells = [2u32; MAX_DEPTH];
+    let mut depth = 0;
+    // properties of the current node
+    let mut compatible: &[u8] = &[];
+    let mut reg: &[u8] = &[];
+
+    let mut offset = structs;
+    loop {
+        let token = be32(blob, offset)?;
+        offset = offset.checked_add(4)?;
+        match token {
+            FDT_BEGIN_NODE => {
+                let name_len = blob.get(offset..)?.iter().position(|&b| b == 0)?;
+                offset = align4(offset.checked_add(name_len)?.checked_add(1)?)?;
+                depth += 1;
+                if depth >= MAX_DEPTH {
+                    return None;
+                }
+                // default of the specification, until the node says otherwise
+                address_cells[depth] = 2;
+                compatible = &[];
+                reg = &[];
+            }
+            FDT_END_NODE => {
+                if depth == 0 {
+                    return None;
+                }
+                // `reg` is encoded with the `#address-cells` of the parent
+                let cells = address_cells[depth - 1];
+                if platform.clint_base.is_none() && is_compatible(compatible, CLINT_COMPATIBLE) {
+                    platform.clint_base = address(reg, cells);
+                }
+                if platform.plic_base.is_none() && is_compatible(compatible, PLIC_COMPATIBLE) {
+                    platform.plic_base = address(reg, cells);
+                }
+                depth -= 1;
+                compatible = &[];
+                reg = &[];
+            }
+            FDT_PROP => {
+                let len = be32(blob, offset)? as usize;
+                let name_offset = be32(blob, offset.checked_add(4)?)? as usize;
+                let name = string(blob, strings.checked_add(name_offset)?)?;
+                let start = offset.checked_add(8)?;
+                let end = start.checked_add(len)?;
+                let value = blob.get(start..end)?;
+                offset = align4(end)?;
+                match name {
+                    b"#address-cells" => address_cells[depth] = be32(value, 0)?,
+                    b"compatible" => compatible = value,
+                    b"reg" => reg = value,
+                    b"device_type" if value == b"cpu\0" => platform.hart_count += 1,
+                    _ => {}
+                }
+            }
+            FDT_NOP => {}
+            FDT_END => return Some(platform),
+            _ => return None,
+        }
+    }
+}
+
+/// Reads the big-endian 32-bit value at `offset` of `bytes`.
+fn be32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+/// Returns the null-terminated string at `offset` of `blob`, without the terminator.
+fn string(blob: &[u8], offset: usize) -> Option<&[u8]> {
+    let bytes = blob.get(offset..)?;
+    let len = bytes.iter().position(|&b| b == 0)?;
+    Some(&bytes[..len])
+}
+
+#[inline]
+fn align4(offset: usize) -> Option<usize> {
+    Some(offset.checked_add(3)? & !3)
+}
+
+/// Returns whether the string list `compatible` contains one of `candidates`.
+fn is_compatible(compatible: &[u8], candidates: &[&[u8]]) -> bool {
+    compatible
+        .split(|&b| b == 0)
+        .any(|entry| candidates.contains(&entry))
+}
+
+/// Returns the first address of `reg`, made of `cells` 32-bit cells.
+fn address(reg: &[u8], cells: u32) -> Option<usize> {
+    let mut address: u64 = 0;
+    for cell in 0..cells as usize {
+        address = (address << 32) | u64::from(be32(reg, cell.checked_mul(4)?)?);
+    }
+    address.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a blob with a CLINT node at `0x200_0000` and one hart.
+    fn blob() -> Vec<u8> {
+        let strings = b"compatible\0reg\0device_type\0";
+        let mut structs = Vec::new();
+        token(&mut structs, FDT_BEGIN_NODE);
+        structs.extend_from_slice(&[0; 4]);
+        token(&mut structs, FDT_BEGIN_NODE);
+        structs.extend_from_slice(b"clint\0\0\0");
+        prop(&mut structs, 0, b"riscv,clint0\0");
+        prop(&mut structs, 11, &[0, 0, 0, 0, 0x02, 0, 0, 0]);
+        token(&mut structs, FDT_END_NODE);
+        token(&mut structs, FDT_BEGIN_NODE);
+        structs.extend_from_slice(b"cpu\0");
+        prop(&mut structs, 15, b"cpu\0");
+        token(&mut structs, FDT_END_NODE);
+        token(&mut structs, FDT_END_NODE);
+        token(&mut structs, FDT_END);
+
+        let structs_offset = 40u32;
+        let strings_offset = structs_offset + structs.len() as u32;
+        let size = strings_offset + strings.len() as u32;
+        let mut blob = Vec::new();
+        for word in [MAGIC, size, structs_offset, strings_offset] {
+            blob.extend_from_slice(&word.to_be_bytes());
+        }
+        blob.resize(40, 0);
+        blob.extend_from_slice(&structs);
+        blob.extend_from_slice(strings);
+        blob
+    }
+
+    fn token(structs: &mut Vec<u8>, token: u32) {
+        structs.extend_from_slice(&token.to_be_bytes());
+    }
+
+    fn prop(structs: &mut Vec<u8>, name: u32, value: &[u8]) {
+        token(structs, FDT_PROP);
+        structs.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        structs.extend_from_slice(&name.to_be_bytes());
+        structs.extend_from_slice(value);
+        structs.resize((structs.len() + 3) & !3, 0);
+    }
+
+    fn set_be32(blob: &mut [u8], offset: usize, value: u32) {
+        blob[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+    }
+
+    #[test]
+    fn valid() {
+        let platform = parse(&blob()).unwrap();
+        assert_eq!(platform.clint_base, Some(0x200_0000));
+        assert_eq!(platform.plic_base, None);
+        assert_eq!(platform.hart_count, 1);
+    }
+
+    #[test]
+    fn truncated() {
+        let blob = blob();
+        for len in 0..blob.len() {
+            assert_eq!(parse(&blob[..len]), None, "truncated to {} bytes", len);
+        }
+    }
+
+    #[test]
+    fn overflowing_offsets() {
+        let valid = blob();
+        for &value in &[u32::MAX, u32::MAX - 3, 0x8000_0000] {
+            for &offset in &[8, 12] {
+                let mut blob = valid.clone();
+                set_be32(&mut blob, offset, value);
+                assert_eq!(parse(&blob), None);
+            }
+        }
+    }
+
+    #[test]
+    fn overflowing_property() {
+        let valid = blob();
+        // `len` and `nameoff` of the `compatible` property of the CLINT node
+        let prop = 40 + 8 + 12 + 4;
+        for &value in &[u32::MAX, u32::MAX - 3, 0x8000_0000] {
+            for &offset in &[prop, prop + 4] {
+                let mut blob = valid.clone();
+                set_be32(&mut blob, offset, value);
+                assert_eq!(parse(&blob), None);
+            }
+        }
+    }
+}
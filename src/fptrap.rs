@@ -0,0 +1,133 @@
+//! Trap entry saving the floating-point state, see the `fp-trap-save` feature
+//!
+//! `fp_save_start_trap` replaces the default `_start_trap`. It saves the same integer registers as
+//! `default_start_trap`, so that `_start_trap_rust` receives the usual [`TrapFrame`], followed by
+//! the caller-saved floating-point registers (`ft0`-`ft11`, `fa0`-`fa7`) and `fcsr`. The callee-saved
+//! `fs0`-`fs11` are preserved by the trap handlers themselves, as for any other function.
+//!
+//! The floating-point registers are only saved if `FS` is Dirty when the trap is taken, i.e. if
+//! the interrupted code modified them since `FS` was last set to Clean or Initial. Otherwise, they
+//! are not touched on entry nor on exit, and the trap costs the same as without the feature.
+//!
+//! [`TrapFrame`]: crate::TrapFrame
+
+use core::arch::global_asm;
+
+/// Size in bytes of the frame saved by `fp_save_start_trap`, the `$frame` argument below
+#[cfg(target_arch = "riscv32")]
+pub(crate) const FRAME_SIZE: usize = 240;
+#[cfg(target_arch = "riscv64")]
+pub(crate) const FRAME_SIZE: usize = 304;
+
+// Frame layout, in `REGBYTES` (`$rb`) slots: 16 integer registers (`TrapFrame`), the flag telling
+// whether the floating-point state has been saved, `fcsr`, then 20 8-byte floating-point slots.
+macro_rules! fp_save_start_trap {
+    ($store:literal, $load:literal, $fstore:literal, $fload:literal, $status:literal, $ret:literal,
+     $rb:literal, $frame:literal) => {
+        global_asm!(
+            ".section .trap, \"ax\"",
+            // `mtvec` of the CLIC holds the mode in bits 5..0
+            ".balign 64",
+            ".global fp_save_start_trap",
+            "fp_save_start_trap:",
+            concat!("addi sp, sp, -", $frame),
+            concat!($store, " ra, 0*", $rb, "(sp)"),
+            concat!($store, " t0, 1*", $rb, "(sp)"),
+            concat!($store, " t1, 2*", $rb, "(sp)"),
+            concat!($store, " t2, 3*", $rb, "(sp)"),
+            concat!($store, " t3, 4*", $rb, "(sp)"),
+            concat!($store, " t4, 5*", $rb, "(sp)"),
+            concat!($store, " t5, 6*", $rb, "(sp)"),
+            concat!($store, " t6, 7*", $rb, "(sp)"),
+            concat!($store, " a0, 8*", $rb, "(sp)"),
+            concat!($store, " a1, 9*", $rb, "(sp)"),
+            concat!($store, " a2, 10*", $rb, "(sp)"),
+            concat!($store, " a3, 11*", $rb, "(sp)"),
+            concat!($store, " a4, 12*", $rb, "(sp)"),
+            concat!($store, " a5, 13*", $rb, "(sp)"),
+            concat!($store, " a6, 14*", $rb, "(sp)"),
+            concat!($store, " a7, 15*", $rb, "(sp)"),
+            // FS (bits 13-14) == Dirty
+            concat!("csrr t0, ", $status),
+            "srli t0, t0, 13",
+            "andi t0, t0, 3",
+            "addi t0, t0, -3",
+            "seqz t0, t0",
+            concat!($store, " t0, 16*", $rb, "(sp)"),
+            "beqz t0, 1f",
+            "frcsr t1",
+            concat!($store, " t1, 17*", $rb, "(sp)"),
+            ".irp r, 0,1,2,3,4,5,6,7",
+            concat!($fstore, " f\\r, 18*", $rb, " + 8*\\r(sp)"),
+            ".endr",
+            ".irp r, 10,11,12,13,14,15,16,17",
+            concat!($fstore, " f\\r, 18*", $rb, " + 8*(\\r - 2)(sp)"),
+            ".endr",
+            ".irp r, 28,29,30,31",
+            concat!($fstore, " f\\r, 18*", $rb, " + 8*(\\r - 12)(sp)"),
+            ".endr",
+            "1:",
+            "mv a0, sp",
+            "call _start_trap_rust",
+            concat!($load, " t0, 16*", $rb, "(sp)"),
+            "beqz t0, 2f",
+            concat!($load, " t1, 17*", $rb, "(sp)"),
+            "fscsr t1",
+            ".irp r, 0,1,2,3,4,5,6,7",
+            concat!($fload, " f\\r, 18*", $rb, " + 8*\\r(sp)"),
+            ".endr",
+            ".irp r, 10,11,12,13,14,15,16,17",
+            concat!($fload, " f\\r, 18*", $rb, " + 8*(\\r - 2)(sp)"),
+            ".endr",
+            ".irp r, 28,29,30,31",
+            concat!($fload, " f\\r, 18*", $rb, " + 8*(\\r - 12)(sp)"),
+            ".endr",
+            "2:",
+            concat!($load, " ra, 0*", $rb, "(sp)"),
+            concat!($load, " t0, 1*", $rb, "(sp)"),
+            concat!($load, " t1, 2*", $rb, "(sp)"),
+            concat!($load, " t2, 3*", $rb, "(sp)"),
+            concat!($load, " t3, 4*", $rb, "(sp)"),
+            concat!($load, " t4, 5*", $rb, "(sp)"),
+            concat!($load, " t5, 6*", $rb, "(sp)"),
+            concat!($load, " t6, 7*", $rb, "(sp)"),
+            concat!($load, " a0, 8*", $rb, "(sp)"),
+            concat!($load, " a1, 9*", $rb, "(sp)"),
+            concat!($load, " a2, 10*", $rb, "(sp)"),
+            concat!($load, " a3, 11*", $rb, "(sp)"),
+            concat!($load, " a4, 12*", $rb, "(sp)"),
+            concat!($load, " a5, 13*", $rb, "(sp)"),
+            concat!($load, " a6, 14*", $rb, "(sp)"),
+            concat!($load, " a7, 15*", $rb, "(sp)"),
+            concat!("addi sp, sp, ", $frame),
+            $ret,
+        );
+    };
+}
+
+// 18 * 4 + 20 * 8 = 232 and 18 * 8 + 20 * 8 = 304 bytes, rounded up to keep `sp` 16-byte aligned
+
+#[cfg(all(target_arch = "riscv32", target_feature = "d", not(feature = "s-mode")))]
+fp_save_start_trap!("sw", "lw", "fsd", "fld", "mstatus", "mret", "4", "240");
+#[cfg(all(target_arch = "riscv32", target_feature = "d", feature = "s-mode"))]
+fp_save_start_trap!("sw", "lw", "fsd", "fld", "sstatus", "sret", "4", "240");
+#[cfg(all(
+    target_arch = "riscv32",
+    not(target_feature = "d"),
+    not(feature = "s-mode")
+))]
+fp_save_start_trap!("sw", "lw", "fsw", "flw", "mstatus", "mret", "4", "240");
+#[cfg(all(target_arch = "riscv32", not(target_feature = "d"), feature = "s-mode"))]
+fp_save_start_trap!("sw", "lw", "fsw", "flw", "sstatus", "sret", "4", "240");
+#[cfg(all(target_arch = "riscv64", target_feature = "d", not(feature = "s-mode")))]
+fp_save_start_trap!("sd", "ld", "fsd", "fld", "mstatus", "mret", "8", "304");
+#[cfg(all(target_arch = "riscv64", target_feature = "d", feature = "s-mode"))]
+fp_save_start_trap!("sd", "ld", "fsd", "fld", "sstatus", "sret", "8", "304");
+#[cfg(all(
+    target_arch = "riscv64",
+    not(target_feature = "d"),
+    not(feature = "s-mode")
+))]
+fp_save_start_trap!("sd", "ld", "fsw", "flw", "mstatus", "mret", "8", "304");
+#[cfg(all(target_arch = "riscv64", not(target_feature = "d"), feature = "s-mode"))]
+fp_save_start_trap!("sd", "ld", "fsw", "flw", "sstatus", "sret", "8", "304");
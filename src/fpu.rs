@@ -0,0 +1,37 @@
+//! Floating-point unit initialization
+
+use core::arch::asm;
+
+/// Enables the FPU (`FS` = Initial) and clears `fcsr`.
+///
+/// `FS` is Off at reset, in which state every floating-point instruction raises an illegal
+/// instruction exception.
+#[inline(always)]
+pub(crate) unsafe fn enable() {
+    #[cfg(not(feature = "s-mode"))]
+    asm!("csrs mstatus, {0}", in(reg) 1 << 13);
+    #[cfg(feature = "s-mode")]
+    asm!("csrs sstatus, {0}", in(reg) 1 << 13);
+    asm!("csrw fcsr, zero");
+}
+
+/// Clears the floating-point register file. The FPU must be [enabled](enable).
+///
+/// With the D extension, the registers are cleared with `fcvt.d.w`, which writes a 64-bit
+/// `+0.0`. With only the F extension, `fmv.w.x` writes the 32-bit `+0.0`.
+#[cfg(feature = "zero-fpu")]
+#[inline(always)]
+pub(crate) unsafe fn zero_registers() {
+    #[cfg(target_feature = "d")]
+    asm!(
+        ".irp r, 0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31",
+        "fcvt.d.w f\\r, zero",
+        ".endr",
+    );
+    #[cfg(not(target_feature = "d"))]
+    asm!(
+        ".irp r, 0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31",
+        "fmv.w.x f\\r, zero",
+        ".endr",
+    );
+}
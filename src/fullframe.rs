@@ -0,0 +1,159 @@
+//! Trap entry saving the fault context, see the `full-trap-frame` feature
+//!
+//! `full_frame_start_trap` replaces the default `_start_trap`. It saves the same registers as
+//! `default_start_trap`, so that `_start_trap_rust` receives the usual [`TrapFrame`], followed by
+//! the interrupted `sp` and the values of `mepc`, `mcause` and `mtval` (`sepc`, `scause`, `stval`
+//! with the `s-mode` feature) at the time of the trap. [`TrapFrame::full`] gives access to them.
+//! Only the 16 registers of the `TrapFrame` are restored on exit.
+
+use core::arch::global_asm;
+use core::fmt;
+
+use crate::TrapFrame;
+
+/// Registers saved in trap handler by the trap entry of the `full-trap-frame` feature
+///
+/// The layout is `#[repr(C)]`: the [`TrapFrame`], then `sp`, `epc`, `cause` and `tval`, i.e. 20
+/// XLEN-bit words, which keeps the stack 16-byte aligned on RV32 and RV64.
+#[repr(C)]
+pub struct FullTrapFrame {
+    /// Caller-saved registers
+    pub frame: TrapFrame,
+    /// Stack pointer of the interrupted code
+    pub sp: usize,
+    /// `mepc`/`sepc`: address of the interrupted or faulting instruction
+    pub epc: usize,
+    /// `mcause`/`scause`: cause of the trap
+    pub cause: usize,
+    /// `mtval`/`stval`: faulting address or instruction, depending on the cause
+    pub tval: usize,
+}
+
+impl TrapFrame {
+    /// Returns the fault context saved along with this frame by the `full-trap-frame` trap entry.
+    ///
+    /// # Safety
+    ///
+    /// `self` must be the frame of a trap, as received by a trap handler, saved by the trap entry
+    /// of this feature. A custom `_start_trap` must save a [`FullTrapFrame`] as well.
+    #[inline]
+    pub unsafe fn full(&self) -> &FullTrapFrame {
+        &*(self as *const TrapFrame as *const FullTrapFrame)
+    }
+}
+
+/// Decoded `cause`, see [`FullTrapFrame`]'s `Debug` implementation
+struct Cause(usize);
+
+impl fmt::Debug for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let interrupt = self.0 >> (usize::BITS - 1) != 0;
+        // with the CLIC, `mcause` holds further fields above the 12-bit code
+        let code = if cfg!(feature = "clic") {
+            self.0 & 0xfff
+        } else {
+            self.0 & !(1 << (usize::BITS - 1))
+        };
+        let (kind, description) = if interrupt {
+            ("Interrupt", crate::interrupt_description(code))
+        } else {
+            ("Exception", crate::exception_description(code))
+        };
+        write!(f, "{}({}, {:?})", kind, code, description)
+    }
+}
+
+impl fmt::Debug for FullTrapFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let t = &self.frame;
+        f.debug_struct("FullTrapFrame")
+            .field("cause", &Cause(self.cause))
+            .field("epc", &format_args!("{:#x}", self.epc))
+            .field("tval", &format_args!("{:#x}", self.tval))
+            .field("sp", &format_args!("{:#x}", self.sp))
+            .field("ra", &format_args!("{:#x}", t.ra))
+            .field("t0", &format_args!("{:#x}", t.t0))
+            .field("t1", &format_args!("{:#x}", t.t1))
+            .field("t2", &format_args!("{:#x}", t.t2))
+            .field("t3", &format_args!("{:#x}", t.t3))
+            .field("t4", &format_args!("{:#x}", t.t4))
+            .field("t5", &format_args!("{:#x}", t.t5))
+            .field("t6", &format_args!("{:#x}", t.t6))
+            .field("a0", &format_args!("{:#x}", t.a0))
+            .field("a1", &format_args!("{:#x}", t.a1))
+            .field("a2", &format_args!("{:#x}", t.a2))
+            .field("a3", &format_args!("{:#x}", t.a3))
+            .field("a4", &format_args!("{:#x}", t.a4))
+            .field("a5", &format_args!("{:#x}", t.a5))
+            .field("a6", &format_args!("{:#x}", t.a6))
+            .field("a7", &format_args!("{:#x}", t.a7))
+            .finish()
+    }
+}
+
+macro_rules! full_frame_start_trap {
+    ($store:literal, $load:literal, $rb:literal, $epc:literal, $cause:literal, $tval:literal,
+     $ret:literal) => {
+        global_asm!(
+            ".section .trap, \"ax\"",
+            // `mtvec` of the CLIC holds the mode in bits 5..0
+            ".balign 64",
+            ".global full_frame_start_trap",
+            "full_frame_start_trap:",
+            concat!("addi sp, sp, -(", $rb, " * 20)"),
+            concat!($store, " ra, 0*", $rb, "(sp)"),
+            concat!($store, " t0, 1*", $rb, "(sp)"),
+            concat!($store, " t1, 2*", $rb, "(sp)"),
+            concat!($store, " t2, 3*", $rb, "(sp)"),
+            concat!($store, " t3, 4*", $rb, "(sp)"),
+            concat!($store, " t4, 5*", $rb, "(sp)"),
+            concat!($store, " t5, 6*", $rb, "(sp)"),
+            concat!($store, " t6, 7*", $rb, "(sp)"),
+            concat!($store, " a0, 8*", $rb, "(sp)"),
+            concat!($store, " a1, 9*", $rb, "(sp)"),
+            concat!($store, " a2, 10*", $rb, "(sp)"),
+            concat!($store, " a3, 11*", $rb, "(sp)"),
+            concat!($store, " a4, 12*", $rb, "(sp)"),
+            concat!($store, " a5, 13*", $rb, "(sp)"),
+            concat!($store, " a6, 14*", $rb, "(sp)"),
+            concat!($store, " a7, 15*", $rb, "(sp)"),
+            concat!("addi t0, sp, (", $rb, " * 20)"),
+            concat!($store, " t0, 16*", $rb, "(sp)"),
+            concat!("csrr t0, ", $epc),
+            concat!($store, " t0, 17*", $rb, "(sp)"),
+            concat!("csrr t0, ", $cause),
+            concat!($store, " t0, 18*", $rb, "(sp)"),
+            concat!("csrr t0, ", $tval),
+            concat!($store, " t0, 19*", $rb, "(sp)"),
+            "add a0, sp, zero",
+            "jal ra, _start_trap_rust",
+            concat!($load, " ra, 0*", $rb, "(sp)"),
+            concat!($load, " t0, 1*", $rb, "(sp)"),
+            concat!($load, " t1, 2*", $rb, "(sp)"),
+            concat!($load, " t2, 3*", $rb, "(sp)"),
+            concat!($load, " t3, 4*", $rb, "(sp)"),
+            concat!($load, " t4, 5*", $rb, "(sp)"),
+            concat!($load, " t5, 6*", $rb, "(sp)"),
+            concat!($load, " t6, 7*", $rb, "(sp)"),
+            concat!($load, " a0, 8*", $rb, "(sp)"),
+            concat!($load, " a1, 9*", $rb, "(sp)"),
+            concat!($load, " a2, 10*", $rb, "(sp)"),
+            concat!($load, " a3, 11*", $rb, "(sp)"),
+            concat!($load, " a4, 12*", $rb, "(sp)"),
+            concat!($load, " a5, 13*", $rb, "(sp)"),
+            concat!($load, " a6, 14*", $rb, "(sp)"),
+            concat!($load, " a7, 15*", $rb, "(sp)"),
+            concat!("addi sp, sp, (", $rb, " * 20)"),
+            $ret,
+        );
+    };
+}
+
+#[cfg(all(target_arch = "riscv32", not(feature = "s-mode")))]
+full_frame_start_trap!("sw", "lw", "4", "mepc", "mcause", "mtval", "mret");
+#[cfg(all(target_arch = "riscv32", feature = "s-mode"))]
+full_frame_start_trap!("sw", "lw", "4", "sepc", "scause", "stval", "sret");
+#[cfg(all(target_arch = "riscv64", not(feature = "s-mode")))]
+full_frame_start_trap!("sd", "ld", "8", "mepc", "mcause", "mtval", "mret");
+#[cfg(all(target_arch = "riscv64", feature = "s-mode"))]
+full_frame_start_trap!("sd", "ld", "8", "sepc", "scause", "stval", "sret");
@@ -0,0 +1,54 @@
+//! Stack overflow detection, see the `stack-overflow-hook` feature
+//!
+//! `stack_guard_start_trap` replaces the default `_start_trap`. It switches to the emergency
+//! stack of the hart (`mscratch`), so that the trap can be inspected even if `sp` ran out of the
+//! stack region. A load/store access or page fault taken while the interrupted `sp` is below
+//! `_estack` is reported to `_stack_overflow`, every other trap goes back to the interrupted
+//! stack and continues in `default_start_trap`.
+
+use core::arch::global_asm;
+
+macro_rules! stack_guard_start_trap {
+    ($store:literal, $load:literal, $scratch:literal, $cause:literal, $tval:literal) => {
+        global_asm!(
+            ".section .trap, \"ax\"",
+            // `mtvec` of the CLIC holds the mode in bits 5..0
+            ".balign 64",
+            ".global stack_guard_start_trap",
+            "stack_guard_start_trap:",
+            // switch to the emergency stack, the interrupted sp is kept in the scratch register
+            concat!("csrrw sp, ", $scratch, ", sp"),
+            concat!($store, " t0, -16(sp)"),
+            concat!($store, " t1, -8(sp)"),
+            // access faults (5, 7) and page faults (13, 15) of loads and stores
+            concat!("csrr t0, ", $cause),
+            "li t1, 16",
+            "bgeu t0, t1, 1f",
+            "andi t0, t0, 5",
+            "li t1, 5",
+            "bne t0, t1, 1f",
+            // taken while the interrupted sp is below the stack region
+            concat!("csrr t0, ", $scratch),
+            "la t1, _estack",
+            "bgeu t0, t1, 1f",
+            "mv a0, t0",
+            concat!("csrr a1, ", $tval),
+            "addi sp, sp, -16",
+            "call _stack_overflow",
+            "1:",
+            concat!($load, " t0, -16(sp)"),
+            concat!($load, " t1, -8(sp)"),
+            concat!("csrrw sp, ", $scratch, ", sp"),
+            "j default_start_trap",
+        );
+    };
+}
+
+#[cfg(all(target_arch = "riscv32", not(feature = "s-mode")))]
+stack_guard_start_trap!("sw", "lw", "mscratch", "mcause", "mtval");
+#[cfg(all(target_arch = "riscv32", feature = "s-mode"))]
+stack_guard_start_trap!("sw", "lw", "sscratch", "scause", "stval");
+#[cfg(all(target_arch = "riscv64", not(feature = "s-mode")))]
+stack_guard_start_trap!("sd", "ld", "mscratch", "mcause", "mtval");
+#[cfg(all(target_arch = "riscv64", feature = "s-mode"))]
+stack_guard_start_trap!("sd", "ld", "sscratch", "scause", "stval");
@@ -0,0 +1,153 @@
+//! Map of the interrupt handlers defined with `#[interrupt_handler]`, for offline analysis
+//!
+//! Each `#[interrupt_handler]` emits one record per interrupt it handles into the
+//! `.riscv_rt.handler_map` input section, which the linker script keeps in the non-allocated
+//! `.handler_map` output section of the ELF file: it takes no space in the image. A host tool
+//! (e.g. a CI check that all the expected interrupts are wired) extracts the section, e.g. with
+//! `llvm-objcopy --dump-section .handler_map=handlers.bin app.elf`, and decodes it with
+//! [`records`].
+//!
+//! # Format
+//!
+//! The section is a sequence of records, in link order, each aligned to 4 bytes:
+//!
+//! | offset | size       | content                                                            |
+//! |--------|------------|--------------------------------------------------------------------|
+//! | 0      | 4          | interrupt number (little-endian), `0xffff_ffff` if not a number    |
+//! | 4      | 2          | length `s` of the symbol name (little-endian)                      |
+//! | 6      | 2          | length `h` of the handler name (little-endian)                     |
+//! | 8      | `s`        | symbol name: `int_<number>`, or the PAC interrupt or function name |
+//! | 8 + s  | `h`        | name of the function the attribute is applied to                   |
+//! |        | 0 to 3     | zero padding to a multiple of 4                                    |
+//!
+//! The names are UTF-8, without terminator.
+
+use core::convert::TryInto;
+
+/// Record of the handler map
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HandlerRecord<'a> {
+    /// Interrupt number, `None` for a handler bound by name (PAC interrupt or linker script)
+    pub number: Option<u32>,
+    /// Symbol the handler is linked as, e.g. `int_7`
+    pub symbol: &'a str,
+    /// Name of the function the attribute is applied to
+    pub handler: &'a str,
+}
+
+/// Iterator over the records of a handler map, see [`records`]
+#[derive(Clone, Debug)]
+pub struct Records<'a> {
+    section: &'a [u8],
+}
+
+/// Returns an iterator over the records of the content of the `.handler_map` section.
+///
+/// The iteration stops at the first malformed record.
+pub fn records(section: &[u8]) -> Records<'_> {
+    Records { section }
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = HandlerRecord<'a>;
+
+    fn next(&mut self) -> Option<HandlerRecord<'a>> {
+        let section = self.section;
+        // stop for good on a malformed record
+        self.section = &[];
+
+        let number = u32::from_le_bytes(section.get(0..4)?.try_into().ok()?);
+        let symbol_len = u16::from_le_bytes(section.get(4..6)?.try_into().ok()?) as usize;
+        let handler_len = u16::from_le_bytes(section.get(6..8)?.try_into().ok()?) as usize;
+        let symbol = section.get(8..8 + symbol_len)?;
+        let handler = section.get(8 + symbol_len..8 + symbol_len + handler_len)?;
+        let len = (8 + symbol_len + handler_len + 3) & !3;
+
+        let record = HandlerRecord {
+            number: Some(number).filter(|&n| n != u32::MAX),
+            symbol: core::str::from_utf8(symbol).ok()?,
+            handler: core::str::from_utf8(handler).ok()?,
+        };
+        self.section = section.get(len..).unwrap_or(&[]);
+        Some(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Record of `#[interrupt_handler(7)] fn uart()`, with 3 bytes of padding. The macros crate
+    /// checks that its encoder emits the same bytes.
+    const INT_7: &[u8] = &[
+        7, 0, 0, 0, 5, 0, 4, 0, b'i', b'n', b't', b'_', b'7', b'u', b'a', b'r', b't', 0, 0, 0,
+    ];
+    /// Record of `#[interrupt_handler(UART0)] fn on_uart()`, bound by name, without padding
+    const UART0: &[u8] = &[
+        0xff, 0xff, 0xff, 0xff, 5, 0, 7, 0, b'U', b'A', b'R', b'T', b'0', b'o', b'n', b'_', b'u',
+        b'a', b'r', b't',
+    ];
+
+    fn decode(section: &[u8]) -> Vec<HandlerRecord<'_>> {
+        records(section).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let section = [INT_7, UART0].concat();
+        assert_eq!(
+            decode(&section),
+            [
+                HandlerRecord {
+                    number: Some(7),
+                    symbol: "int_7",
+                    handler: "uart",
+                },
+                HandlerRecord {
+                    number: None,
+                    symbol: "UART0",
+                    handler: "on_uart",
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn padding() {
+        // the padding of a record is skipped, also when the record is the last one
+        let section = [INT_7, INT_7].concat();
+        assert_eq!(decode(&section).len(), 2);
+        assert_eq!(decode(&INT_7[..17]).len(), 1);
+        assert!(decode(&[]).is_empty());
+    }
+
+    #[test]
+    fn unknown_number() {
+        let mut section = INT_7.to_vec();
+        section[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(decode(&section)[0].number, None);
+        section[..4].copy_from_slice(&(u32::MAX - 1).to_le_bytes());
+        assert_eq!(decode(&section)[0].number, Some(u32::MAX - 1));
+    }
+
+    #[test]
+    fn truncated() {
+        for len in [3, 7, 12, 16] {
+            assert!(decode(&INT_7[..len]).is_empty(), "{} bytes", len);
+        }
+        // the records before the truncated one are returned
+        let section = [INT_7, &UART0[..10]].concat();
+        assert_eq!(decode(&section).len(), 1);
+    }
+
+    #[test]
+    fn not_utf8() {
+        let mut section = [INT_7, UART0].concat();
+        section[9] = 0xff;
+        assert!(decode(&section).is_empty());
+
+        let mut section = [UART0, INT_7].concat();
+        section[UART0.len() + 14] = 0xff;
+        assert_eq!(decode(&section).len(), 1);
+    }
+}
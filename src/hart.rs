@@ -0,0 +1,38 @@
+//! Multi-hart bring-up helpers
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{_watchdog_feed, max_hart_id};
+
+/// Bitmask of the harts that reported to be online, bit `N` corresponds to hart `N`
+static HARTS_ONLINE: AtomicUsize = AtomicUsize::new(0);
+
+/// Marks the hart `hartid` as online.
+///
+/// Every hart is expected to call this once it reached `main`. The bitmask lives in `.bss`, so
+/// this must not be called before memory initialization is completed (i.e. not from `_mp_hook`
+/// or `#[pre_init]`).
+///
+/// # Panics
+///
+/// Panics if `hartid` is greater than `_max_hart_id` or does not fit into the bitmask.
+#[inline]
+pub fn hart_online(hartid: usize) {
+    assert!(hartid <= max_hart_id() && hartid < usize::BITS as usize);
+    HARTS_ONLINE.fetch_or(1 << hartid, Ordering::Release);
+}
+
+/// Returns the bitmask of the harts that called [`hart_online`].
+#[inline]
+pub fn harts_online() -> usize {
+    HARTS_ONLINE.load(Ordering::Acquire)
+}
+
+/// Spins until all the harts in `mask` called [`hart_online`].
+///
+/// Bit `N` of `mask` corresponds to hart `N`. `_watchdog_feed` is called while waiting.
+pub fn wait_for_harts(mask: usize) {
+    while harts_online() & mask != mask {
+        unsafe { _watchdog_feed() };
+    }
+}
@@ -0,0 +1,61 @@
+//! Boot-time integrity check of the loaded image, see the `image-crc` feature
+
+use core::ptr;
+
+/// Placeholder of the CRC field, replaced after linking by `patch-image-crc.py`
+const UNPATCHED: u32 = 0xffff_ffff;
+
+/// Expected CRC-32 of the image, outside of the covered range
+#[export_name = "__riscv_rt_image_crc"]
+#[link_section = ".image_crc"]
+#[used]
+static IMAGE_CRC: u32 = UNPATCHED;
+
+/// Nibble lookup table of CRC-32 (IEEE 802.3, reflected polynomial `0xedb88320`)
+#[rustfmt::skip]
+const TABLE: [u32; 16] = [
+    0x0000_0000, 0x1db7_1064, 0x3b6e_20c8, 0x26d9_30ac, 0x76dc_4190, 0x6b6b_51f4, 0x4db2_6158,
+    0x5005_713c, 0xedb8_8320, 0xf00f_9344, 0xd6d6_a3e8, 0xcb61_b38c, 0x9b64_c2b0, 0x86d3_d2d4,
+    0xa00a_e278, 0xbdbd_f21c,
+];
+
+/// Updates the (non-inverted) CRC-32 `crc` with the bytes in `start..end`.
+unsafe fn update(mut crc: u32, start: *const u8, end: *const u8) -> u32 {
+    let mut p = start;
+    while p < end {
+        crc ^= u32::from(ptr::read_volatile(p));
+        crc = (crc >> 4) ^ TABLE[(crc & 0xf) as usize];
+        crc = (crc >> 4) ^ TABLE[(crc & 0xf) as usize];
+        p = p.add(1);
+    }
+    crc
+}
+
+/// Recomputes the CRC-32 of `.text` and `.rodata` and compares it with the value stored in the
+/// image by `patch-image-crc.py`.
+///
+/// Returns `false` if the image is corrupted or the CRC field has not been patched.
+///
+/// The range covers `.text` and `.rodata` up to the CRC field, which is placed at the end of
+/// `.rodata`. The field cannot be part of the range it protects, as storing the CRC would change
+/// the CRC. Read-only data placed after it (none by default) and the initial values of `.data`
+/// are not covered.
+pub fn verify_image() -> bool {
+    extern "C" {
+        static _stext: u8;
+        static _text_size: u8;
+        static _image_crc_rodata_start: u8;
+        static _image_crc_rodata_end: u8;
+    }
+
+    unsafe {
+        let text = &_stext as *const u8;
+        let text_end = text.add(&_text_size as *const u8 as usize);
+        let crc = update(!0, text, text_end);
+        let crc = update(crc, &_image_crc_rodata_start, &_image_crc_rodata_end);
+
+        // volatile, the compiler would otherwise compare against the placeholder
+        let expected = ptr::read_volatile(&IMAGE_CRC);
+        expected != UNPATCHED && !crc == expected
+    }
+}
@@ -0,0 +1,86 @@
+//! Memory initialization routines used by `start_rust`
+
+use core::mem::size_of;
+use core::ptr;
+
+// All the accesses are volatile, so that the compiler cannot turn the loops into `memset` or
+// `memcpy` calls: freestanding targets may not provide them, and they must not run before
+// `.data` is initialized anyway.
+
+/// Zeroes the memory in `start..end`, one 32-bit word at a time.
+///
+/// Both bounds must be 4-byte aligned.
+#[inline(always)]
+pub(crate) unsafe fn zero_bss(start: *mut u32, end: *mut u32) {
+    let mut word = start;
+    while word < end {
+        ptr::write_volatile(word, 0);
+        word = word.add(1);
+    }
+}
+
+/// Copies the initial values at `sidata` to `sdata..edata`, one 32-bit word at a time.
+///
+/// All pointers must be 4-byte aligned.
+#[inline(always)]
+pub(crate) unsafe fn init_data(sdata: *mut u32, edata: *mut u32, sidata: *const u32) {
+    let mut word = sdata;
+    let mut value = sidata;
+    while word < edata {
+        ptr::write_volatile(word, ptr::read_volatile(value));
+        word = word.add(1);
+        value = value.add(1);
+    }
+}
+
+/// Zeroes the memory in `start..end`, clearing four `usize` words per iteration.
+///
+/// Both bounds must be 4-byte aligned. On RV64, a leading and a trailing 32-bit store cover
+/// the parts that are not 8-byte aligned.
+#[cfg(feature = "fast-bss")]
+#[inline(always)]
+pub(crate) unsafe fn zero_bss_fast(start: *mut u32, end: *mut u32) {
+    let mut start = start as usize;
+    let end = end as usize;
+
+    if size_of::<usize>() > size_of::<u32>() && start % size_of::<usize>() != 0 && start < end {
+        ptr::write_volatile(start as *mut u32, 0);
+        start += size_of::<u32>();
+    }
+
+    let mut word = start as *mut usize;
+    let words_end = (end - (end - start) % size_of::<usize>()) as *mut usize;
+
+    while (word as usize) + 4 * size_of::<usize>() <= words_end as usize {
+        ptr::write_volatile(word, 0);
+        ptr::write_volatile(word.add(1), 0);
+        ptr::write_volatile(word.add(2), 0);
+        ptr::write_volatile(word.add(3), 0);
+        word = word.add(4);
+    }
+    while word < words_end {
+        ptr::write_volatile(word, 0);
+        word = word.add(1);
+    }
+
+    if (words_end as usize) < end {
+        ptr::write_volatile(words_end as *mut u32, 0);
+    }
+}
+
+/// Calls the C/C++ static constructors of `.preinit_array` and `.init_array`, in order.
+#[cfg(feature = "run-init-array")]
+pub(crate) unsafe fn run_init_array() {
+    extern "C" {
+        static __preinit_array_start: extern "C" fn();
+        static __init_array_end: extern "C" fn();
+    }
+
+    // `.preinit_array` is placed right before `.init_array`, see link.x
+    let mut f = ptr::addr_of!(__preinit_array_start);
+    let end = ptr::addr_of!(__init_array_end);
+    while f < end {
+        (*f)();
+        f = f.add(1);
+    }
+}
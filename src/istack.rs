@@ -0,0 +1,84 @@
+//! Dedicated interrupt stack, see the `interrupt-stack` feature
+//!
+//! `interrupt_stack_start_trap` replaces the default `_start_trap`. While a hart runs on its thread
+//! stack, `mscratch` holds the top of its interrupt stack; while it runs on the interrupt stack,
+//! `mscratch` is 0. On entry, `sp` and `mscratch` are swapped: a non-zero `sp` is the interrupt
+//! stack top, a zero `sp` means that the trap nested into a trap handler, which is already on the
+//! interrupt stack. The frame saves the registers in the [`TrapFrame`](crate::TrapFrame) layout
+//! followed by the interrupted `sp` of the thread (0 if nested), which selects the way back.
+
+use core::arch::global_asm;
+
+macro_rules! interrupt_stack_start_trap {
+    ($store:literal, $load:literal, $rb:literal, $scratch:literal, $ret:literal) => {
+        global_asm!(
+            ".section .trap, \"ax\"",
+            ".global interrupt_stack_start_trap",
+            "interrupt_stack_start_trap:",
+            concat!("csrrw sp, ", $scratch, ", sp"),
+            "bnez sp, 1f",
+            // nested trap: go back to the interrupted sp on the interrupt stack, scratch stays 0
+            concat!("csrrw sp, ", $scratch, ", zero"),
+            "1:",
+            concat!("addi sp, sp, -(", $rb, " * 20)"),
+            concat!($store, " ra, 0*", $rb, "(sp)"),
+            concat!($store, " t0, 1*", $rb, "(sp)"),
+            concat!($store, " t1, 2*", $rb, "(sp)"),
+            concat!($store, " t2, 3*", $rb, "(sp)"),
+            concat!($store, " t3, 4*", $rb, "(sp)"),
+            concat!($store, " t4, 5*", $rb, "(sp)"),
+            concat!($store, " t5, 6*", $rb, "(sp)"),
+            concat!($store, " t6, 7*", $rb, "(sp)"),
+            concat!($store, " a0, 8*", $rb, "(sp)"),
+            concat!($store, " a1, 9*", $rb, "(sp)"),
+            concat!($store, " a2, 10*", $rb, "(sp)"),
+            concat!($store, " a3, 11*", $rb, "(sp)"),
+            concat!($store, " a4, 12*", $rb, "(sp)"),
+            concat!($store, " a5, 13*", $rb, "(sp)"),
+            concat!($store, " a6, 14*", $rb, "(sp)"),
+            concat!($store, " a7, 15*", $rb, "(sp)"),
+            // interrupted sp of the thread, or 0 if nested; scratch is 0 while on the stack
+            concat!("csrrw t0, ", $scratch, ", zero"),
+            concat!($store, " t0, 16*", $rb, "(sp)"),
+            "add a0, sp, zero",
+            "jal ra, _start_trap_rust",
+            concat!($load, " ra, 0*", $rb, "(sp)"),
+            concat!($load, " t1, 2*", $rb, "(sp)"),
+            concat!($load, " t2, 3*", $rb, "(sp)"),
+            concat!($load, " t3, 4*", $rb, "(sp)"),
+            concat!($load, " t4, 5*", $rb, "(sp)"),
+            concat!($load, " t5, 6*", $rb, "(sp)"),
+            concat!($load, " t6, 7*", $rb, "(sp)"),
+            concat!($load, " a0, 8*", $rb, "(sp)"),
+            concat!($load, " a1, 9*", $rb, "(sp)"),
+            concat!($load, " a2, 10*", $rb, "(sp)"),
+            concat!($load, " a3, 11*", $rb, "(sp)"),
+            concat!($load, " a4, 12*", $rb, "(sp)"),
+            concat!($load, " a5, 13*", $rb, "(sp)"),
+            concat!($load, " a6, 14*", $rb, "(sp)"),
+            concat!($load, " a7, 15*", $rb, "(sp)"),
+            concat!($load, " t0, 16*", $rb, "(sp)"),
+            "beqz t0, 2f",
+            // back to the thread stack, the interrupt stack top goes back to scratch
+            concat!("csrw ", $scratch, ", t0"),
+            concat!($load, " t0, 1*", $rb, "(sp)"),
+            concat!("addi sp, sp, (", $rb, " * 20)"),
+            concat!("csrrw sp, ", $scratch, ", sp"),
+            $ret,
+            // back to the interrupted trap handler
+            "2:",
+            concat!($load, " t0, 1*", $rb, "(sp)"),
+            concat!("addi sp, sp, (", $rb, " * 20)"),
+            $ret,
+        );
+    };
+}
+
+#[cfg(all(target_arch = "riscv32", not(feature = "s-mode")))]
+interrupt_stack_start_trap!("sw", "lw", "4", "mscratch", "mret");
+#[cfg(all(target_arch = "riscv32", feature = "s-mode"))]
+interrupt_stack_start_trap!("sw", "lw", "4", "sscratch", "sret");
+#[cfg(all(target_arch = "riscv64", not(feature = "s-mode")))]
+interrupt_stack_start_trap!("sd", "ld", "8", "mscratch", "mret");
+#[cfg(all(target_arch = "riscv64", feature = "s-mode"))]
+interrupt_stack_start_trap!("sd", "ld", "8", "sscratch", "sret");
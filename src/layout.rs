@@ -0,0 +1,162 @@
+//! Structure of the linker script, for host tooling
+//!
+//! This module only contains data describing `link.x`, so that tools generating or validating
+//! `memory.x` files do not need to parse the linker script. Host tools depend on this crate with
+//! the `std` feature, which only builds the parts of the crate that do not require a RISC-V
+//! target.
+
+/// The linker script shipped with this crate (`link.x`), before the build script resolves its
+/// `#if` blocks and `$` placeholders
+pub const LINK_X: &str = include_str!("../link.x");
+
+/// Region aliases that `memory.x` must always define
+pub const REQUIRED_REGIONS: &[&str] = &[
+    "REGION_TEXT",
+    "REGION_RODATA",
+    "REGION_DATA",
+    "REGION_BSS",
+    "REGION_HEAP",
+    "REGION_STACK",
+];
+
+/// Region aliases that `memory.x` must define when a feature is enabled, as `(feature, region)`
+pub const FEATURE_REGIONS: &[(&str, &str)] = &[
+    ("payload", "REGION_PAYLOAD"),
+    ("vectors-region", "REGION_VECTORS"),
+];
+
+/// Symbol that `memory.x` may define to configure the layout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    /// Name of the symbol
+    pub name: &'static str,
+    /// Default value provided by `link.x`, `None` if the symbol must be defined when used
+    pub default: Option<&'static str>,
+}
+
+/// Symbols that `memory.x` may define, see the crate documentation for their meaning
+#[rustfmt::skip]
+pub const SYMBOLS: &[Symbol] = &[
+    Symbol { name: "_stext", default: Some("ORIGIN(REGION_TEXT)") },
+    Symbol { name: "_stack_start", default: Some("ORIGIN(REGION_STACK) + LENGTH(REGION_STACK)") },
+    Symbol { name: "_max_hart_id", default: Some("$RISCV_RT_MAX_HART_ID, or 0") },
+    Symbol { name: "_hart_stack_size", default: Some("$RISCV_RT_HART_STACK_SIZE, or 2K") },
+    Symbol { name: "_heap_size", default: Some("$RISCV_RT_HEAP_SIZE, or 0") },
+    Symbol { name: "_emergency_stack_size", default: Some("256") },
+    Symbol { name: "_interrupt_stack_size", default: Some("1K") },
+    Symbol { name: "_flash_block_size", default: Some("4") },
+    Symbol { name: "_hart_local_size", default: Some("0") },
+    Symbol { name: "_boot_hartid", default: Some("0") },
+    Symbol { name: "_clint_base", default: Some("0x2000000") },
+    Symbol { name: "_stack_init_pattern", default: Some("0") },
+    Symbol { name: "_cache_block_size", default: Some("64") },
+    Symbol { name: "_clic_base", default: None },
+    Symbol { name: "_mstatus_init_mask", default: Some("0") },
+    Symbol { name: "_mstatus_init_value", default: Some("0") },
+    Symbol { name: "_envcfg_init_mask", default: Some("0") },
+    Symbol { name: "_envcfg_init_value", default: Some("0") },
+    Symbol { name: "_envcfgh_init_mask", default: Some("0") },
+    Symbol { name: "_envcfgh_init_value", default: Some("0") },
+    Symbol { name: "_mie_init", default: Some("0") },
+    Symbol { name: "_ram_test_background", default: Some("0") },
+    Symbol { name: "_max_nesting_depth", default: Some("4") },
+];
+
+/// Output section of `link.x`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Section {
+    /// Name of the output section
+    pub name: &'static str,
+    /// Region alias the section is placed in
+    pub region: &'static str,
+    /// Feature that adds the section, `None` if it is always present
+    pub feature: Option<&'static str>,
+}
+
+/// Output sections of `link.x`, in order
+///
+/// With the `single-load-segment` feature, `.rodata` is placed in `REGION_TEXT` instead.
+#[rustfmt::skip]
+pub const SECTIONS: &[Section] = &[
+    Section { name: ".text", region: "REGION_TEXT", feature: None },
+    Section { name: ".rodata", region: "REGION_RODATA", feature: None },
+    Section { name: ".data", region: "REGION_DATA", feature: None },
+    Section { name: ".bss", region: "REGION_BSS", feature: None },
+    Section { name: ".uninit", region: "REGION_BSS", feature: None },
+    Section { name: ".emergency_stack", region: "REGION_BSS", feature: Some("emergency-stack") },
+    Section { name: ".interrupt_stack", region: "REGION_BSS", feature: Some("interrupt-stack") },
+    Section { name: ".payload", region: "REGION_PAYLOAD", feature: Some("payload") },
+    Section { name: ".heap", region: "REGION_HEAP", feature: None },
+    Section { name: ".stack", region: "REGION_STACK", feature: None },
+    Section { name: ".vectors", region: "REGION_VECTORS", feature: Some("vectors-region") },
+];
+
+/// Returns the region aliases required with `features` that are not defined by `memory_x`.
+#[cfg(feature = "std")]
+pub fn missing_regions(memory_x: &str, features: &[&str]) -> Vec<&'static str> {
+    let feature_regions = FEATURE_REGIONS
+        .iter()
+        .filter(|(feature, _)| features.contains(feature))
+        .map(|&(_, region)| region);
+
+    REQUIRED_REGIONS
+        .iter()
+        .copied()
+        .chain(feature_regions)
+        .filter(|region| {
+            !memory_x
+                .lines()
+                .filter(|line| line.trim_start().starts_with("REGION_ALIAS"))
+                .any(|line| line.contains(&format!("\"{}\"", region)))
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    const MEMORY_X: &str = r#"
+MEMORY
+{
+  RAM : ORIGIN = 0x80000000, LENGTH = 16K
+  FLASH : ORIGIN = 0x20000000, LENGTH = 4M
+}
+
+REGION_ALIAS("REGION_TEXT", FLASH);
+REGION_ALIAS("REGION_RODATA", FLASH);
+REGION_ALIAS("REGION_DATA", RAM);
+REGION_ALIAS("REGION_BSS", RAM);
+REGION_ALIAS("REGION_HEAP", RAM);
+REGION_ALIAS("REGION_STACK", RAM);
+"#;
+
+    #[test]
+    fn complete() {
+        assert!(missing_regions(MEMORY_X, &[]).is_empty());
+        assert!(missing_regions(MEMORY_X, &["s-mode", "clic"]).is_empty());
+    }
+
+    #[test]
+    fn missing_required() {
+        let memory_x = MEMORY_X.replace("REGION_ALIAS(\"REGION_HEAP\", RAM);", "");
+        assert_eq!(missing_regions(&memory_x, &[]), ["REGION_HEAP"]);
+
+        // only the aliases count, not other mentions of the region
+        let memory_x =
+            MEMORY_X.replace("REGION_ALIAS(\"REGION_STACK\", RAM);", "/* REGION_STACK */");
+        assert_eq!(missing_regions(&memory_x, &[]), ["REGION_STACK"]);
+    }
+
+    #[test]
+    fn feature_regions() {
+        assert_eq!(missing_regions(MEMORY_X, &["payload"]), ["REGION_PAYLOAD"]);
+        assert_eq!(
+            missing_regions(MEMORY_X, &["payload", "vectors-region"]),
+            ["REGION_PAYLOAD", "REGION_VECTORS"]
+        );
+
+        let memory_x = format!("{}REGION_ALIAS(\"REGION_PAYLOAD\", FLASH);\n", MEMORY_X);
+        assert!(missing_regions(&memory_x, &["payload"]).is_empty());
+    }
+}
@@ -13,6 +13,9 @@
 //!
 //! - `#[entry]` to declare the entry point of the program
 //! - `#[pre_init]` to run code *before* `static` variables are initialized
+//! - `#[mp_hook]` to declare the multi-processing hook
+//! - `#[ramfunc]` to execute a function from RAM
+//! - `#[setup_interrupts]` to declare the interrupt setup function
 //!
 //! - A linker script that encodes the memory layout of a generic RISC-V
 //!   microcontroller. This linker script is missing some information that must
@@ -20,8 +23,27 @@
 //!   must be supplied using rustflags and listed *before* `link.x`. Arbitrary
 //!   filename can be use instead of `memory.x`.
 //!
+//!   The build script generates `link.x` in `OUT_DIR`, which it adds to the linker search path,
+//!   from the template of the same name at the root of this crate: the sections and symbols of
+//!   the enabled Cargo features are selected with `#if defined(FEATURE)` blocks, and the build
+//!   time settings (`RISCV_RT_MAX_HART_ID`, `RISCV_RT_HART_STACK_SIZE`, `RISCV_RT_HEAP_SIZE`,
+//!   `RISCV_RT_VECTOR_ALIGN`) replace `$` placeholders. [`layout::LINK_X`] holds the template.
+//!
+//!   The symbols documented below keep their names and defaults in every configuration, except
+//!   for the ones tied to a feature: the hooks of `boot-selftest` (`_selftest_failed`), `ram-test`
+//!   (`_ram_test_failed`, `_ram_test_background`), `nesting-limit` (`_max_nesting_depth`,
+//!   `_max_nesting_exceeded`) and `stack-overflow-hook` (`_stack_overflow`) are only provided
+//!   with their feature, `no-default-abort` omits `abort`, and the default of `_start_trap`
+//!   depends on the trap entry features. `check-link-x.sh` checks the generated script.
+//!
 //! - A `_sheap` symbol at whose address you can locate a heap.
 //!
+//! - [`memory_map`] to inspect the linked memory layout at runtime for diagnostics, and
+//!   [`current_stack_bounds`] returning the stack slice of the calling hart, and [`sizes`]
+//!   returning the sizes of the output sections.
+//!
+//! - [`hart_online`] and [`wait_for_harts`] for staged multi-hart bring-up (targets with the A extension).
+//!
 //! - Support for a runtime in supervisor mode, that can be bootstrapped via [Supervisor Binary Interface (SBI)](https://github.com/riscv-non-isa/riscv-sbi-doc)
 //!
 //! ``` text
@@ -106,9 +128,11 @@
 //! Disassembly of section .text:
 //!
 //! 20000000 <_start>:
-//! 20000000:	800011b7          	lui	gp,0x80001
-//! 20000004:	80018193          	addi	gp,gp,-2048 # 80000800 <_stack_start+0xffffc800>
-//! 20000008:	80004137          	lui	sp,0x80004
+//! 20000000:	200000b7          	lui	ra,0x20000
+//! 20000004:	00808067          	jr	8(ra) # 20000008 <_abs_start>
+//!
+//! 20000008 <_abs_start>:
+//! 20000008:	30401073          	csrw	mie,0
 //! ```
 //!
 //! # Symbol interfaces
@@ -117,6 +141,11 @@
 //! provide most of its functionality. Below are described the main symbol
 //! interfaces.
 //!
+//! The layout symbols below, as well as the section boundaries (`_sdata`, `_edata`, `_sbss`,
+//! `_ebss`, `_sheap`, `_estack`, `_sstack`, ...), are kept in the symbol table of the final ELF,
+//! so a debugger can resolve them, e.g. `print &_stack_start` in GDB. For sizes and ids such
+//! as `_hart_stack_size`, the *address* of the symbol is the value.
+//!
 //! ## `memory.x`
 //!
 //! This file supplies the information about the device to the linker.
@@ -140,6 +169,23 @@
 //!
 //! Specific aliases for these regions must be defined in `memory.x` file (see example below).
 //!
+//! For simple targets, `memory.x` can be omitted: if the `RISCV_RT_RAM_ORIGIN` and
+//! `RISCV_RT_RAM_LENGTH` environment variables are set at build time, the build script generates
+//! it with a `RAM` region and, if `RISCV_RT_FLASH_ORIGIN` and `RISCV_RT_FLASH_LENGTH` are set too,
+//! a `FLASH` region. `REGION_TEXT` and `REGION_RODATA` are placed in `FLASH` if present, all other
+//! regions in `RAM`. Values are linker script expressions, e.g. `0x80000000` or `16K`. The
+//! generated regions are included by `link.x`, so only `-Tlink.x` is passed to the linker:
+//!
+//! ``` text
+//! $ cat .cargo/config
+//! [target.riscv32imac-unknown-none-elf]
+//! rustflags = ["-C", "link-arg=-Tlink.x"]
+//! $ RISCV_RT_RAM_ORIGIN=0x80000000 RISCV_RT_RAM_LENGTH=16K cargo build
+//! ```
+//!
+//! An explicit `memory.x` takes precedence: the variables are ignored as soon as the rustflags
+//! pass a linker script other than `link.x`.
+//!
 //! ### `_stext`
 //!
 //! This symbol provides the loading address of `.text` section. This value can be changed
@@ -181,26 +227,186 @@
 //! _stack_start = ORIGIN(L2_LIM) + LENGTH(L2_LIM);
 //! ```
 //!
+//! ### `_estack` and `_sstack`
+//!
+//! The `.stack` output section covers the memory reserved for the stacks of all the harts,
+//! from `_estack` (lowest address) up to `_sstack` (equal to `_stack_start`). It is a `NOLOAD`
+//! section, so debuggers and stack analyzers can find its bounds in the ELF without it taking
+//! any space in the image. The linker checks that it can hold `(_max_hart_id + 1) * _hart_stack_size`
+//! bytes. With the `paint-stack` feature, the section is filled with a pattern (see below).
+//!
+//! With the `stack-sections` feature, the stack of hart `N` is a separate `.stack.hartN` section
+//! bounded by `_estack_hartN` and `_sstack_hartN`, and `.stack` only covers the unused memory
+//! below the lowest one.
+//!
 //! ### `_max_hart_id`
 //!
 //! This symbol defines the maximum hart id supported. All harts with id
-//! greater than `_max_hart_id` will be redirected to `abort()`.
+//! greater than `_max_hart_id` will be redirected to `_excess_hart`.
 //!
 //! This symbol is supposed to be redefined in platform support crates for
 //! multi-core targets.
 //!
-//! If omitted this symbol value will default to 0 (single core).
+//! If omitted this symbol value will default to the value of the `RISCV_RT_MAX_HART_ID`
+//! environment variable at build time, or 0 (single core) if it is not set. The same value
+//! is available at compile time as [`MAX_HART_ID`], e.g. for `#[entry] fn main<const N: usize>()`.
+//! With such an entry, the link fails if `_max_hart_id` is redefined to another value.
 //!
 //! ### `_hart_stack_size`
 //!
 //! This symbol defines stack area size for *one* hart.
 //!
-//! If omitted this symbol value will default to 2K.
+//! A definition in `memory.x` takes precedence. If omitted this symbol value will default to
+//! the value of the `RISCV_RT_HART_STACK_SIZE` environment variable at build time (a linker
+//! script expression such as `4K` or `0x1000`), or 2K if it is not set.
+//!
+//! ### `_clint_base`
+//!
+//! This symbol provides the base address of the Core Local Interruptor (CLINT), used by the
+//! CLINT helpers of this crate (e.g. [`trigger_interrupt`] and [`clint::set_mtimecmp`]).
+//!
+//! If omitted this symbol value will default to `0x2000000` (QEMU `virt` and most SiFive cores).
+//! The address can also be set at run time with [`clint::set_base`], e.g. from the device tree.
+//!
+//! ### `_cache_block_size`
+//!
+//! This symbol provides the size of a cache block, by which the functions of the `cache` module
+//! (features `zicbom` and `zicboz`) step through an address range. It must be a power of 2.
+//!
+//! If omitted this symbol value will default to 64, the most common block size.
+//!
+//! ### `_mstatus_init_mask` and `_mstatus_init_value`
+//!
+//! These symbols configure the initial value of `mstatus` on exotic cores (machine mode only).
+//! Before `_setup_interrupts` is called, each hart replaces the bits of `mstatus` selected
+//! by `_mstatus_init_mask` with the corresponding bits of `_mstatus_init_value`.
+//!
+//! Fields that are safe to preset are `MPP`, `MPRV`, `SUM`, `MXR`, `TVM`, `TW`, `TSR`
+//! and, on RV64, `UBE`/`SBE`/`MBE` (on RV32 these are located in `mstatush`, which is not
+//! covered). `MIE`/`SIE` must be left to the interrupt setup, and `FS`/`XS` to the FPU setup.
+//!
+//! If omitted, the mask defaults to 0 and `mstatus` keeps its reset value.
+//!
+//! #### Example
+//!
+//! Setting `MPP` to supervisor mode:
+//!
+//! ``` text
+//! _mstatus_init_mask = 0x1800;
+//! _mstatus_init_value = 0x0800;
+//! ```
+//!
+//! ### `_envcfg_init_mask` and `_envcfg_init_value`
+//!
+//! These symbols configure the environment configuration register, which enables extensions
+//! for the less privileged modes: `menvcfg`, or `senvcfg` with the `s-mode` feature. Before
+//! `_setup_interrupts` is called, each hart replaces the bits selected by `_envcfg_init_mask`
+//! with the corresponding bits of `_envcfg_init_value`. On RV32 in machine mode, the upper half
+//! (`menvcfgh`) is configured with `_envcfgh_init_mask` and `_envcfgh_init_value`.
+//!
+//! Commonly needed bits:
+//!
+//! | Bit | Field   | Register             | Enables                                          |
+//! |-----|---------|----------------------|--------------------------------------------------|
+//! | 0   | `FIOM`  | `menvcfg`, `senvcfg` | Fence of I/O implies memory                      |
+//! | 5:4 | `CBIE`  | `menvcfg`, `senvcfg` | `cbo.inval` (Zicbom): `0b01` flush, `0b11` inval |
+//! | 6   | `CBCFE` | `menvcfg`, `senvcfg` | `cbo.clean` and `cbo.flush` (Zicbom)             |
+//! | 7   | `CBZE`  | `menvcfg`, `senvcfg` | `cbo.zero` (Zicboz)                              |
+//! | 62  | `PBMTE` | `menvcfg` (`h`: 30)  | Page-based memory types (Svpbmt)                 |
+//! | 63  | `STCE`  | `menvcfg` (`h`: 31)  | `stimecmp` in supervisor mode (Sstc)             |
+//!
+//! Note that the bits only affect the less privileged modes: the `sstc` feature requires `STCE`
+//! to be set in `menvcfg` by the SBI implementation.
+//!
+//! If omitted, the masks default to 0 and the registers are not accessed, which is required on
+//! cores that do not implement them (privileged spec older than 1.12).
+//!
+//! #### Example
+//!
+//! Enabling the cache-block management instructions for user mode:
+//!
+//! ``` text
+//! _envcfg_init_mask = 0xf0;
+//! _envcfg_init_value = 0xf0;
+//! ```
+//!
+//! ### `_mie_init`
+//!
+//! This symbol selects the interrupts enabled from the start: right after `_setup_interrupts`
+//! returns, each hart sets the bits of `_mie_init` in `mie` (`sie` with the `s-mode` feature).
+//! Interrupts are still globally disabled (`mstatus.MIE`) when `main` is called.
+//!
+//! | Bit | Field  | Interrupt                 |
+//! |-----|--------|---------------------------|
+//! | 1   | `SSIE` | Supervisor software       |
+//! | 3   | `MSIE` | Machine software          |
+//! | 5   | `STIE` | Supervisor timer          |
+//! | 7   | `MTIE` | Machine timer             |
+//! | 9   | `SEIE` | Supervisor external       |
+//! | 11  | `MEIE` | Machine external          |
+//! | 16+ |        | Platform-specific (local) |
+//!
+//! In CLIC mode `mie` is ignored, the interrupts are enabled in the CLIC (see `clic::Clic`).
+//!
+//! If omitted this symbol value will default to 0 and all the interrupts stay disabled.
+//!
+//! #### Example
+//!
+//! Enabling the machine timer and external interrupts:
+//!
+//! ``` text
+//! _mie_init = 0x880;
+//! ```
+//!
+//! ### `_ram_test_background`
+//!
+//! This symbol provides the background pattern of the RAM test of the `ram-test` feature: the
+//! March C- elements write its lower 32 bits and their complement. If omitted this symbol value
+//! will default to 0. A pattern such as `0x55555555` also detects faults between the bits of a
+//! word.
+//!
+//! ### `_emergency_stack_size`
+//!
+//! This symbol defines the size of the emergency stack of *one* hart, reserved with the
+//! `emergency-stack` feature. It must be a multiple of 16. If omitted this symbol value will
+//! default to 256.
+//!
+//! ### `_interrupt_stack_size`
+//!
+//! This symbol defines the size of the interrupt stack of *one* hart, reserved with the
+//! `interrupt-stack` feature. It must be a multiple of 16. If omitted this symbol value will
+//! default to 1K.
+//!
+//! ### `_hart_local_size`
+//!
+//! This symbol defines the size of the private data block of *one* hart, see [`hart_local`].
+//! `_max_hart_id + 1` blocks are reserved at the end of `.bss`. It must be a multiple of 16.
+//! If omitted this symbol value will default to 0, i.e. no per-hart data.
+//!
+//! ``` text
+//! _hart_local_size = 64;
+//! ```
+//!
+//! ### `_flash_block_size`
+//!
+//! This symbol defines the size of the flash erase blocks. The end of `.text` and the end of
+//! `.rodata` (i.e. the load address of `.data`) are aligned to it, so that the code, the constants
+//! and the initial values of `.data` never share an erase block and can be updated independently.
+//! Each boundary wastes up to `_flash_block_size - 4` bytes of padding. With the
+//! `single-load-segment` feature, the padding is part of the single segment, i.e. it is written
+//! to flash.
+//!
+//! If omitted this symbol value will default to 4, i.e. no extra alignment.
 //!
 //! ### `_heap_size`
 //!
-//! This symbol provides the size of a heap region. The default value is 0. You can set `_heap_size`
-//! to a non-zero value if you are planning to use heap allocations.
+//! This symbol provides the size of a heap region. You can set `_heap_size` to a non-zero value
+//! if you are planning to use heap allocations.
+//!
+//! A definition in `memory.x` takes precedence. If omitted this symbol value will default to the
+//! value of the `RISCV_RT_HEAP_SIZE` environment variable at build time (a linker script
+//! expression such as `16K`), or 0 if it is not set. The linker checks that the heap fits into
+//! `REGION_HEAP`.
 //!
 //! ### `_sheap`
 //!
@@ -243,7 +449,168 @@
 //! }
 //! ```
 //!
-//! Default implementation of this function wakes hart 0 and busy-loops all the other harts.
+//! or, with the signature checked at compile time, using the `#[mp_hook]` attribute:
+//!
+//! ``` no_run
+//! #[riscv_rt::mp_hook]
+//! fn mp_hook(hartid: usize) -> bool {
+//!    // ...
+//! }
+//! ```
+//!
+//! `#[mp_hook(boot_hart = N)]` generates the default policy for a boot hart other than hart 0.
+//!
+//! Default implementation of this function wakes the hart `_boot_hartid` (hart 0 by default)
+//! and busy-loops all the other harts.
+//!
+//! ### `_boot_hartid`
+//!
+//! This symbol selects the hart that initializes `.bss` and `.data` and runs `main` with the
+//! default `_mp_hook`, e.g. on SoCs where hart 0 is a management core. If omitted this symbol
+//! value will default to 0.
+//!
+//! ``` text
+//! _boot_hartid = 1;
+//! ```
+//!
+//! It is only used by the default `_mp_hook` and by the `init-stack` feature: a custom `_mp_hook`
+//! decides on its own, and `#[mp_hook(boot_hart = N)]` ignores this symbol. The link fails if it
+//! is greater than `_max_hart_id`.
+//!
+//! ### `_excess_hart`
+//!
+//! `_start` jumps to this function on the harts whose id is greater than `_max_hart_id`, with
+//! the hart id as argument. These harts have no stack: `sp` is 0 and the function must neither
+//! use the stack nor return, so it is best written in assembly. Default implementation of this
+//! function is `abort`, which stucks in a busy-loop.
+//!
+//! For example, parking the excess harts in a low-power state:
+//!
+//! ``` no_run
+//! core::arch::global_asm!(
+//!     ".section .text._excess_hart",
+//!     ".global _excess_hart",
+//!     "_excess_hart:",
+//!     "wfi",
+//!     "j _excess_hart",
+//! );
+//! ```
+//!
+//! ### `abort`
+//!
+//! The crate provides an `abort` function that stucks in a busy-loop. It is only used if no other
+//! object defines `abort`, so a definition of the application or of another library (e.g. a
+//! libc shim) takes precedence without a duplicate symbol error:
+//!
+//! ``` no_run
+//! #[no_mangle]
+//! pub extern "C" fn abort() -> ! {
+//!     loop {}
+//! }
+//! ```
+//!
+//! With the `no-default-abort` feature, the crate does not provide `abort` at all, and the link
+//! fails if something references it without defining it.
+//!
+//! ### `_watchdog_feed`
+//!
+//! This function is called repeatedly from the busy-loops of this crate, so that platforms with a
+//! hardware watchdog can keep it fed while a hart is parked. It is invoked from:
+//!
+//! * the default `ExceptionHandler`
+//! * the default `DefaultHandler`
+//! * the default `_mp_hook`, each time a parked hart wakes up from `wfi`
+//!
+//! This function can be redefined in the following way:
+//!
+//! ``` no_run
+//! #[export_name = "_watchdog_feed"]
+//! pub extern "Rust" fn watchdog_feed() {
+//!    // ...
+//! }
+//! ```
+//!
+//! Default implementation of this function does nothing.
+//!
+//! ### `_stack_overflow`
+//!
+//! This function is called by the trap entry of the `stack-overflow-hook` feature when a stack
+//! overflow is detected. It receives the interrupted stack pointer and the faulting address
+//! (`mtval`), and runs on the emergency stack of the hart, which is small (see
+//! `_emergency_stack_size`).
+//!
+//! Default implementation of this function stucks in a busy-loop.
+//!
+//! ``` no_run
+//! #[export_name = "_stack_overflow"]
+//! extern "C" fn stack_overflow(sp: usize, fault_addr: usize) -> ! {
+//!     // report the overflow, then reset
+//!     loop {}
+//! }
+//! ```
+//!
+//! ### `_max_nesting_depth` and `_max_nesting_exceeded`
+//!
+//! With the `nesting-limit` feature, `_max_nesting_depth` is the deepest nesting level of
+//! `#[interrupt_handler]` handlers that runs with interrupts enabled. If omitted this symbol value
+//! will default to 4.
+//!
+//! ``` text
+//! _max_nesting_depth = 2;
+//! ```
+//!
+//! When the depth is exceeded, the wrapper calls `_max_nesting_exceeded` with the current depth
+//! and then runs the handler with interrupts disabled. Default implementation of this function
+//! does nothing.
+//!
+//! ``` no_run
+//! #[export_name = "_max_nesting_exceeded"]
+//! extern "C" fn max_nesting_exceeded(depth: usize) {
+//!     // log the event
+//! }
+//! ```
+//!
+//! ### `_setup_failed`
+//!
+//! This function is called by [`verify_tvec`] when the trap vector written by `_setup_interrupts`
+//! did not stick, e.g. because the core does not implement the vectored or CLIC mode. The default
+//! implementation does nothing, so the boot continues with the value the core accepted.
+//!
+//! ``` no_run
+//! #[export_name = "_setup_failed"]
+//! pub extern "Rust" fn setup_failed() {
+//!     // report the misconfiguration, e.g. by halting
+//!     loop {}
+//! }
+//! ```
+//!
+//! ### `_selftest_failed`
+//!
+//! With the `boot-selftest` feature, this function is called by `start_rust` when the self-test
+//! of the trap path fails. The default implementation stucks in a busy-loop. If it returns, the
+//! boot continues.
+//!
+//! ``` no_run
+//! #[export_name = "_selftest_failed"]
+//! pub extern "Rust" fn selftest_failed() {
+//!     // report the failure, e.g. by blinking a LED
+//!     loop {}
+//! }
+//! ```
+//!
+//! ### `_ram_test_failed`
+//!
+//! With the `ram-test` feature, this function is called by `start_rust` with the address of the
+//! first word of `.data` or `.bss` that failed the RAM test. The default implementation stucks in
+//! a busy-loop. If it returns, the boot continues with the faulty RAM.
+//!
+//! ``` no_run
+//! #[export_name = "_ram_test_failed"]
+//! pub extern "Rust" fn ram_test_failed(addr: usize) {
+//!     // report the failure, e.g. through a status register of the SoC
+//!     loop {}
+//! }
+//! ```
 //!
 //! ### `ExceptionHandler`
 //!
@@ -265,9 +632,68 @@
 //!     // ...
 //! }
 //! ```
+//! or, with the signature checked at compile time:
+//! ``` no_run
+//! #[riscv_rt::exception_handler]
+//! fn custom_exception_handler(trap_frame: &riscv_rt::TrapFrame) -> ! {
+//!     // ...
+//! }
+//! ```
+//!
+//! or, with `#[exception]`:
+//! ``` no_run
+//! #[riscv_rt::exception]
+//! fn custom_exception_handler(trap_frame: &riscv_rt::TrapFrame) -> ! {
+//!     // ...
+//! }
+//! ```
+//!
+//! The handler may also return `()` to resume the trapped code (see below).
 //!
 //! Default implementation of this function stucks in a busy-loop.
 //!
+//! ### Exception handlers by cause
+//!
+//! The exceptions with a standard code can be handled separately, by defining a handler with one
+//! of the following names (the other exceptions still go to `ExceptionHandler`):
+//! * `InstructionMisaligned` (0)
+//! * `InstructionFault` (1)
+//! * `IllegalInstruction` (2)
+//! * `Breakpoint` (3)
+//! * `LoadMisaligned` (4)
+//! * `LoadFault` (5)
+//! * `StoreMisaligned` (6)
+//! * `StoreFault` (7)
+//! * `UserEnvCall` (8)
+//! * `SupervisorEnvCall` (9)
+//! * `MachineEnvCall` (11)
+//! * `InstructionPageFault` (12)
+//! * `LoadPageFault` (13)
+//! * `StorePageFault` (15)
+//!
+//! `#[exception]` takes the code or the name as argument and checks the signature:
+//!
+//! ``` no_run
+//! #[riscv_rt::exception(IllegalInstruction)]
+//! fn illegal_instruction(trap_frame: &riscv_rt::TrapFrame) {
+//!     // emulate the instruction, then advance `mepc` past it
+//! }
+//! ```
+//!
+//! If a handler returns, the trapped code resumes at `mepc`/`sepc`, which still points to the
+//! instruction that raised the exception: the handler must advance it, unless it fixed the cause.
+//! [`skip_instruction`] advances it past the instruction, compressed or not:
+//!
+//! ``` no_run
+//! #[riscv_rt::exception(MachineEnvCall)]
+//! fn ecall(trap_frame: &riscv_rt::TrapFrame) {
+//!     // serve the call, then return after the `ecall`
+//!     unsafe { riscv_rt::skip_instruction() };
+//! }
+//! ```
+//!
+//! [`with_exception_handler`] temporarily replaces all these handlers while a closure runs, e.g.
+//! to probe memory that may fault.
 //!
 //! ### Core interrupt handlers
 //!
@@ -300,6 +726,37 @@
 //!
 //! If interrupt handler is not explicitly defined, `DefaultHandler` is called.
 //!
+//! With the `clic` feature, the same holds for the `int_0` to `int_264` entries of the vector
+//! table: only the interrupts in use need an `#[interrupt_handler]`, the linker script routes the
+//! other ones to `DefaultHandler`. In vectored mode they go through a wrapper that saves the
+//! context and returns with `mret`, as the ones generated by `#[interrupt_handler]`, so a
+//! `DefaultHandler` that returns resumes the interrupted code.
+//!
+//! With the `s-mode` feature, the dispatch table follows the `scause` interrupt codes, which are
+//! identical to the `mcause` ones. Machine level interrupts are never delegated to supervisor mode,
+//! so `MachineSoft`, `MachineTimer` and `MachineExternal` are not dispatched and not linked;
+//! use `SupervisorSoft`, `SupervisorTimer` and `SupervisorExternal` instead.
+//!
+//! ### Reserved interrupt handlers
+//!
+//! The interrupt codes 2, 6 and 10 are reserved by the privileged specification. On standard
+//! cores they never fire, so by default they are routed to `DefaultHandler` as well.
+//! Non-standard cores that use these codes for custom core-local interrupts can install
+//! a handler with one of the following names:
+//! * `CoreInterrupt2`
+//! * `CoreInterrupt6`
+//! * `CoreInterrupt10`
+//!
+//! For example:
+//! ``` no_run
+//! #[export_name = "CoreInterrupt10"]
+//! fn custom_core_local_handler() {
+//!     // ...
+//! }
+//! ```
+//!
+//! These handlers are only dispatched when the `clic` feature is disabled.
+//!
 //! ### `DefaultHandler`
 //!
 //! This function is called when interrupt without defined interrupt handler is occured.
@@ -320,9 +777,59 @@
 //!     // ...
 //! }
 //! ```
+//! or, with the signature checked at compile time:
+//! ``` no_run
+//! #[riscv_rt::default_handler]
+//! fn custom_interrupt_handler() {
+//!     // ...
+//! }
+//! ```
+//! or, receiving the code of the unhandled interrupt:
+//! ``` no_run
+//! #[riscv_rt::unhandled_interrupt]
+//! fn unhandled(code: usize) {
+//!     // log `code`, e.g. with `riscv_rt::interrupt_description(code)`
+//! }
+//! ```
 //!
 //! Default implementation of this function stucks in a busy-loop.
 //!
+//! ### Required interrupt handlers
+//!
+//! Interrupts without handler silently end up in `DefaultHandler`. For interrupts that must not
+//! go unhandled, `require_handlers!` turns a missing `#[interrupt_handler]` into a link error:
+//!
+//! ``` ignore
+//! riscv_rt::require_handlers!(5, 7);
+//!
+//! #[riscv_rt::interrupt_handler(5)]
+//! fn watchdog() {
+//!     // ...
+//! }
+//! ```
+//!
+//! Without a handler for interrupt 7, the link fails with an undefined reference to
+//! `__riscv_rt_handled_int_7`.
+//!
+//! For checks outside of the build, each `#[interrupt_handler]` also records its interrupt number
+//! and names in the `.handler_map` section of the ELF file, which [`handler_map::records`] decodes.
+//!
+//! ### Shared interrupt handlers
+//!
+//! Interrupts that run the same logic (e.g. the lines of several GPIO banks) can share one
+//! `#[interrupt_handler]` by listing their numbers. One `int_<number>` wrapper is generated per
+//! interrupt, each passing its number to the handler, which must therefore take it as argument:
+//!
+//! ``` ignore
+//! #[riscv_rt::interrupt_handler(3, 4, 5)]
+//! fn gpio_bank(line: usize) {
+//!     // `line` is 3, 4 or 5
+//! }
+//! ```
+//!
+//! The argument is also available to handlers of a single interrupt number, and
+//! `clear_pending_first` clears the pending bit of the line that fired.
+//!
 //! # Features
 //!
 //! ## `s-mode`
@@ -354,91 +861,807 @@
 //!   FLASH : ORIGIN = 0x20000000, LENGTH = 16M
 //! }
 //! ```
-
-// NOTE: Adapted from cortex-m/src/lib.rs
-#![no_std]
-#![deny(missing_docs)]
-
-use core::arch::global_asm;
-
-#[cfg(feature = "clic")]
-use riscv_clic as riscv_crate;
-
-#[cfg(not(feature = "clic"))]
-use ::riscv as riscv_crate;
-
-
-#[cfg(feature = "s-mode")]
-use riscv_crate::register::{scause as xcause, stvec as xtvec, stvec::TrapMode as xTrapMode};
-
-#[cfg(not(feature = "s-mode"))]
-use riscv_crate::register::{mcause as xcause, mhartid, mtvec as xtvec, mtvec::TrapMode as xTrapMode};
-
-// TODO: enable this for s-mode
-#[cfg(feature = "clic")]
-use riscv_crate::register::{mtvt as xtvt, mtvec::SubMode as xSubMode};
-
-
-pub use riscv_rt_macros::{entry, pre_init, interrupt_handler};
-
-#[export_name = "error: riscv-rt appears more than once in the dependency graph"]
-#[doc(hidden)]
-pub static __ONCE__: () = ();
-
-extern "C" {
-    // Boundaries of the .bss section
-    static mut _ebss: u32;
-    static mut _sbss: u32;
-
-    // Boundaries of the .data section
-    static mut _edata: u32;
-    static mut _sdata: u32;
-
-    // Initial values of the .data section (stored in Flash)
-    static _sidata: u32;
-}
-
-/// Rust entry point (_start_rust)
-///
-/// Zeros bss section, initializes data section and calls main. This function
-/// never returns.
-#[link_section = ".init.rust"]
-#[export_name = "_start_rust"]
-pub unsafe extern "C" fn start_rust(a0: usize, a1: usize, a2: usize) -> ! {
-    #[rustfmt::skip]
-    extern "Rust" {
-        // This symbol will be provided by the user via `#[entry]`
-        fn main(a0: usize, a1: usize, a2: usize) -> !;
-
-        // This symbol will be provided by the user via `#[pre_init]`
-        fn __pre_init();
-
-        fn _setup_interrupts();
-
-        fn _mp_hook(hartid: usize) -> bool;
+//!
+//! ## `reentrancy-check`
+//!
+//! Wraps the body of every `#[interrupt_handler]` with a guard that panics when the handler is
+//! entered again on the same hart before the previous invocation returned (e.g. when the handler
+//! accidentally re-enables its own interrupt). Each hart has its own flag, so several harts may run
+//! the same handler. The guard is only emitted in builds with `debug_assertions`, release builds
+//! pay nothing.
+//!
+//! `#[entry]` is not guarded: the entry point cannot be referenced by the program, and every hart
+//! legitimately runs it once.
+//!
+//! ## `clic-unmasked-boot`
+//!
+//! With the `clic` feature, the default `_setup_interrupts` sets the interrupt level threshold
+//! (`mintthresh`) to mask all interrupts, so that no interrupt can fire before the application
+//! has configured its handlers. The application unmasks them with [`clic::set_threshold`].
+//! This feature keeps the reset value of `mintthresh` instead.
+//!
+//! ## `single-load-segment`
+//!
+//! Places `.text`, `.rodata` and the initial values of `.data` contiguously in `REGION_TEXT`,
+//! ignoring `REGION_RODATA`. The image then consists of a single loadable segment, so
+//! `objcopy -O binary` produces a flat image without padding holes, which some flashing tools
+//! handle more reliably.
+//!
+//! ## `fast-bss`
+//!
+//! Replaces the word-by-word `.bss` zeroing loop with an unrolled routine clearing four `usize`
+//! words per iteration, which speeds up the boot of images with a large `.bss`.
+//!
+//! ## `payload`
+//!
+//! Adds a `.payload` output section placed in the `REGION_PAYLOAD` region, which must be defined
+//! in `memory.x`. All input sections named `.payload` are kept, so a bootloader can carry a
+//! separately linked application image. The bounds are exported as `_spayload`/`_epayload`
+//! and the contents are accessible with [`payload`].
+//!
+//! ``` text
+//! REGION_ALIAS("REGION_PAYLOAD", FLASH);
+//! ```
+//!
+//! ## `sbi-panic`
+//!
+//! Provides a `#[panic_handler]` that prints the panic message to the SBI debug console and
+//! shuts the system down through SBI, so QEMU/OpenSBI users get panic messages without any UART
+//! setup. Implies `s-mode`. As only one panic handler can exist, this feature cannot be combined
+//! with crates like `panic-halt`; the build fails with a duplicate `panic_impl` lang item error.
+//!
+//! ## `preserve-gp`
+//!
+//! In chain-loaded scenarios, a first boot stage may already have set up `gp` relative to a
+//! shared linker layout. With this feature, `_start` only loads `gp` with `__global_pointer$`
+//! if it is zero, trusting the value left by the bootloader otherwise. By default, `gp` is
+//! always loaded.
+//!
+//! ## `paint-stack`
+//!
+//! Turns the `.stack` section into a loadable section filled with the `0xCCCCCCCC` pattern, so that
+//! the stack high-water mark can be determined from a memory dump. The pattern is written by
+//! whatever loads the ELF (e.g. a debugger), so this is mostly useful for RAM-loaded images.
+//!
+//! ## `emergency-stack`
+//!
+//! Reserves a `.emergency_stack` section of `(_max_hart_id + 1) * _emergency_stack_size` bytes
+//! after `.bss`, ending at `_emergency_stack`. Before `_setup_interrupts` is called, each hart
+//! writes the top of its own emergency stack (`_emergency_stack - hartid * _emergency_stack_size`)
+//! to `mscratch` (`sscratch` with the `s-mode` feature).
+//!
+//! The default trap entry does not use it. The contract for a custom `_start_trap` that does is:
+//!
+//! - on entry, `csrrw sp, mscratch, sp` switches to the emergency stack and keeps the interrupted
+//!   `sp` in `mscratch`, so the context can be saved even if `sp` was corrupted;
+//! - before `mret`, the same swap restores `sp` and leaves the stack top in `mscratch` again;
+//! - the emergency stack is not reentrant: traps must stay disabled while it is in use, or the
+//!   handler must move to another stack before re-enabling them.
+//!
+//! ## `stack-overflow-hook`
+//!
+//! Replaces the default `_start_trap` with a trap entry that first switches to the emergency stack
+//! of the hart (implies `emergency-stack`). A load or store access fault or page fault taken while
+//! the interrupted `sp` is below `_estack` (e.g. when the stack ran into an unmapped or PMP-guarded
+//! region below `REGION_STACK`) is reported to `_stack_overflow` together with the faulting
+//! address. Any other trap continues on the interrupted stack with the default trap entry.
+//! Overflows into the stack of another hart are not detected.
+//!
+//! ## `interrupt-stack`
+//!
+//! Runs the trap handlers on a dedicated stack, so that they cannot overflow the stack of the
+//! interrupted code. Reserves an `.interrupt_stack` section of
+//! `(_max_hart_id + 1) * _interrupt_stack_size` bytes after `.bss`, ending at
+//! `_interrupt_stack_start`, and replaces the default `_start_trap` with a trap entry that swaps
+//! `sp` with `mscratch` (`sscratch` with the `s-mode` feature), which `start_rust` sets to the top
+//! of the interrupt stack of the hart before `_setup_interrupts`.
+//!
+//! `mscratch` is 0 while the hart runs on its interrupt stack, so a trap nested into a handler
+//! (e.g. an exception, or an interrupt with interrupts re-enabled) stays on the interrupt stack
+//! below the interrupted handler. `mscratch` must not be used for anything else.
+//!
+//! Only the traps that go through `_start_trap` are covered, so this feature cannot be combined
+//! with `clic`, nor with `emergency-stack` and `fp-trap-save`, which also use `mscratch` or
+//! replace `_start_trap`. A frame is 20 registers, the handlers also need room for their own
+//! stack usage.
+//!
+//! ## `init-barrier`
+//!
+//! Makes the harts whose `_mp_hook` returns `false` wait in `start_rust` until the initializing
+//! hart has completed the initialization of `.bss` and `.data`, instead of proceeding right away.
+//! This guarantees that no hart touches a `static` before it is initialized, even with a custom
+//! `_mp_hook` that does not park the secondary harts. `_watchdog_feed` is called while waiting.
+//!
+//! The flag lives in `.bss` and is released by writing a magic value. After a warm reset that did
+//! not clear RAM, it still holds the magic value of the previous boot, so the initializing hart
+//! clears it first, before `#[pre_init]`. A hart reaching the barrier before it is cleared proceeds
+//! early.
+//!
+//! ## `defer`
+//!
+//! Provides [`defer`] to queue up to [`DEFER_CAPACITY`] functions that the initializing hart runs
+//! once, after `_setup_interrupts` and right before `main`. This allows `#[pre_init]` or a custom
+//! `_setup_interrupts` to schedule initialization that needs initialized statics or the interrupt
+//! setup. The queue is placed in the `.uninit` section, so it survives the `.bss` initialization.
+//!
+//! ## `std`
+//!
+//! Builds the crate for a hosted target, for tools that generate or validate `memory.x` files with
+//! the description of the linker script in [`layout`]. The runtime itself (`_start_rust`, the
+//! CSR helpers, ...) is left out, so this feature must not be enabled for a RISC-V application.
+//!
+//! ## `vectors-region`
+//!
+//! With the `clic` feature, places the CLIC vector table (`interrupt_vector`, pointed to by `mtvt`)
+//! in a `.vectors` output section in the `REGION_VECTORS` region, which must be defined in
+//! `memory.x`, instead of within `.text`. This allows pinning the table to the address required by
+//! the core:
+//!
+//! ``` text
+//! MEMORY
+//! {
+//!   VECTORS : ORIGIN = 0x1C000000, LENGTH = 1K
+//!   ...
+//! }
+//!
+//! REGION_ALIAS("REGION_VECTORS", VECTORS);
+//! ```
+//!
+//! In both cases, the table is aligned to the value of the `RISCV_RT_VECTOR_ALIGN` environment
+//! variable at build time, or 1024 bytes if it is not set.
+//!
+//! ## `zero-fpu`
+//!
+//! On targets with the F extension, `start_rust` always enables the FPU (`mstatus.FS`/`sstatus.FS`
+//! set to Initial) and clears `fcsr` before `_setup_interrupts`. This feature additionally clears
+//! `f0` to `f31`, so that no code can observe the undefined reset values of the register file.
+//! With the D extension, the registers are cleared as 64-bit `+0.0` (`fcvt.d.w`), otherwise as
+//! 32-bit `+0.0` (`fmv.w.x`). This costs 32 instructions per hart at boot. Without the F
+//! extension, the feature has no effect.
+//!
+//! ## `run-init-array`
+//!
+//! Collects the C/C++ static constructors (`.preinit_array`, `.init_array`) and destructors
+//! (`.fini_array`) into `.rodata`, with the bounds `__preinit_array_start`/`__preinit_array_end`,
+//! `__init_array_start`/`__init_array_end` and `__fini_array_start`/`__fini_array_end`.
+//!
+//! The initializing hart calls the constructors in order after `#[pre_init]` and the initialization
+//! of `.bss` and `.data`, and before `_setup_interrupts`, the deferred calls of the `defer` feature
+//! and `main`. With `init-barrier`, the other harts are released only after the constructors ran.
+//! The destructors are never run, as `main` does not return.
+//!
+//! ## `image-crc`
+//!
+//! Reserves a CRC-32 field at the end of `.rodata` and provides [`verify_image`], which recomputes
+//! the CRC-32 of `.text` and `.rodata` (up to the field) at run time and compares it with the stored
+//! value, e.g. as a boot-time self-test.
+//!
+//! Cargo build scripts run before the final link, so the CRC cannot be computed at build time.
+//! Store it into the linked ELF with the `patch-image-crc.py` script of this repository before
+//! converting or flashing the image:
+//!
+//! ``` text
+//! $ ./patch-image-crc.py target/riscv32imac-unknown-none-elf/release/app
+//! ```
+//!
+//! The field cannot cover itself, as storing the CRC would change it. Until the ELF is patched,
+//! [`verify_image`] returns `false`.
+//!
+//! ## `nesting-limit`
+//!
+//! Bounds the nesting of `#[interrupt_handler]` handlers, and with it their worst-case stack usage.
+//! The wrapper generated for every handler increments a per-hart depth counter in `.bss` on entry
+//! and decrements it on exit. Once the depth exceeds `_max_nesting_depth`, the wrapper calls
+//! `_max_nesting_exceeded` instead of enabling interrupts, so the handler runs to completion without
+//! being preempted. The counter is indexed by `mhartid`, so the link fails if `_max_hart_id` is
+//! greater than `RISCV_RT_MAX_HART_ID`.
+//!
+//! It has no effect with the `nxti` feature, whose handlers are called without a per-handler wrapper.
+//!
+//! ## `fp-trap-save`
+//!
+//! Replaces the trap entry (`_start_trap`) with one that also saves and restores the caller-saved
+//! floating-point registers and `fcsr`, so that trap handlers can use the FPU without corrupting the
+//! state of the interrupted code. Requires a target with the F extension.
+//!
+//! The floating-point state is saved lazily: only if `mstatus.FS` (`sstatus.FS`) is Dirty when the
+//! trap is taken. Code that did not touch the FPU since `FS` was last set to Clean or Initial (e.g. by
+//! the `zero-fpu` feature) pays no extra cost. The saved registers are not part of [`TrapFrame`].
+//!
+//! The `#[interrupt_handler]` wrappers of the CLIC are not affected and only save the integer
+//! registers. It cannot be combined with `stack-overflow-hook`, which also replaces the trap entry.
+//!
+//! ## `no-nested-interrupts-default`
+//!
+//! By default, the wrapper generated by `#[interrupt_handler]` enables interrupts globally
+//! (`mstatus.MIE`) before calling the handler, so that handlers can be preempted. With this feature,
+//! handlers run with interrupts disabled and cannot be preempted, unless they opt in with the
+//! `nested` option:
+//!
+//! ``` ignore
+//! #[riscv_rt::interrupt_handler(7, nested)]
+//! fn uart() {
+//!     // ...
+//! }
+//! ```
+//!
+//! With the CLIC, the interrupt level and `mintthresh` only decide which interrupts may be taken
+//! while `mstatus.MIE` is set: a handler without `nested` is not preempted even by interrupts of a
+//! higher level, and raising `mintthresh` inside it has no effect on its own preemption. The
+//! `nesting-limit` counter is only maintained by `nested` handlers.
+//!
+//! ## `bootrom`
+//!
+//! Checks at link time that the image can run from a mask ROM or OTP memory mapped as
+//! `REGION_TEXT`: the linker fails if `.data`, `.bss`, `.uninit`, `.heap` or `.stack` overlap
+//! `REGION_TEXT`, and the `.got` check already rejects code that needs relocations written at run
+//! time. The startup code never writes to `.text`: `gp` and the stack pointers are computed
+//! with `auipc`-relative sequences and link-time constants, so RAM must be within ±2 GiB of the
+//! ROM.
+//!
+//! Only read-only contents can live in the ROM: `.init`, `.trap`, `.text`, and, if
+//! `REGION_RODATA` is the ROM as well, `.rodata` and the initial values of `.data`, which
+//! `start_rust` copies to RAM. Code that has to be written or patched at run time must not be placed
+//! in `.text` with `#[link_section]`; functions that must run from RAM use `#[ramfunc]`. The
+//! `paint-stack` feature cannot be combined with `bootrom`, as nothing loads the pattern into RAM.
+//!
+//! ## `stack-sections`
+//!
+//! Emits the stack of every hart as its own `.stack.hartN` section, from `_estack_hartN` (lowest
+//! address) up to `_sstack_hartN`, so debuggers can label the stacks of the harts. The sections are
+//! generated by the build script from the `RISCV_RT_MAX_HART_ID` environment variable; the linker
+//! checks that `_max_hart_id` has not been redefined to another value.
+//!
+//! ## `init-stack`
+//!
+//! Initializes the whole `.stack` section, i.e. the stacks of all the harts, with a defined pattern
+//! at boot, e.g. for safety certifications requiring all RAM to be initialized. Unlike
+//! `paint-stack`, which relies on the loader and is meant for high-water mark measurements, this is
+//! done by `_start` itself on every boot, before any hart uses its stack: every hart fills its own
+//! `_hart_stack_size` slice with the 32-bit value of `_stack_init_pattern` (0 by default), and the
+//! hart `_boot_hartid` also fills the rest of the section below the slices of all harts.
+//!
+//! ``` text
+//! _stack_init_pattern = 0xDEADBEEF;
+//! ```
+//!
+//! As no hart writes to the stack of another one, the harts do not synchronize, also after a warm
+//! reset. The slices of the harts up to `_max_hart_id` that never start are left uninitialized.
+//! The two features cannot be combined.
+//!
+//! ## `no-default-abort`
+//!
+//! Omits the `abort` function provided by the crate, for projects where `abort` must come from
+//! elsewhere (see the `abort` symbol interface above). `_excess_hart` defaults to `abort`, so
+//! either of them must then be defined by another object.
+//!
+//! ## `sstc`
+//!
+//! Programs the supervisor timer through the `stimecmp` CSR of the Sstc extension, with
+//! [`set_stimecmp`], instead of an SBI call. [`set_timer`] then writes `stimecmp` directly, which
+//! avoids the trap into machine mode. The harts must implement Sstc, and the SBI implementation
+//! must have enabled it for supervisor mode (`menvcfg.STCE`), otherwise accessing `stimecmp` raises
+//! an illegal instruction exception. If the machine-mode firmware is built with this crate, it can
+//! set `STCE` with `_envcfgh_init_mask` or `_envcfg_init_mask`. Enables the `s-mode` feature.
+//!
+//! ## `boot-selftest`
+//!
+//! Checks the trap path at boot, e.g. when bringing up a new board. Before `main`, after
+//! `_setup_interrupts`, the initializing hart executes an `ebreak` with known values in `a0`-`a2`.
+//! `_start_trap_rust` intercepts the trap before the dispatch if the cause decodes as a breakpoint
+//! exception and the values reached the [`TrapFrame`], so the real `ExceptionHandler` is not
+//! called. Back from the trap, the hart checks that the trap path restored the registers. If a
+//! check fails, `_selftest_failed` is called.
+//!
+//! Any other trap taken during the test is dispatched as usual. This includes a breakpoint with a
+//! wrong cause or trap frame, which therefore reaches the exception handlers instead of
+//! `_selftest_failed`. A trap vector that does not reach `_start_trap` at all (e.g. a wrong
+//! `mtvec`) cannot be reported either: the hart then does not come back from the `ebreak`.
+//!
+//! ## `zicbom` and `zicboz`
+//!
+//! Enable the `cache` module, with the cache-block management operations of the Zicbom extension
+//! (`cache::clean`, `cache::invalidate`, `cache::flush`) and the `cache::zero` operation of the
+//! Zicboz extension, e.g. to keep DMA buffers coherent. The block size is taken from the
+//! `_cache_block_size` symbol. Below machine mode, the instructions must be enabled in `menvcfg`
+//! (`CBIE`, `CBCFE`, `CBZE`), see `_envcfg_init_mask`.
+//!
+//! ## `ram`
+//!
+//! For images loaded entirely into RAM, e.g. by QEMU (`-kernel`) or a debugger. `.data` is linked
+//! with its load address equal to its run address (`_sidata == _sdata`), so the loader puts the
+//! initial values in place and `start_rust` skips the copy. `.bss` is still zeroed.
+//!
+//! All the regions are then typically aliases of the same RAM region:
+//!
+//! ``` text
+//! MEMORY
+//! {
+//!   RAM : ORIGIN = 0x80000000, LENGTH = 16M
+//! }
+//!
+//! REGION_ALIAS("REGION_TEXT", RAM);
+//! REGION_ALIAS("REGION_RODATA", RAM);
+//! REGION_ALIAS("REGION_DATA", RAM);
+//! REGION_ALIAS("REGION_BSS", RAM);
+//! REGION_ALIAS("REGION_HEAP", RAM);
+//! REGION_ALIAS("REGION_STACK", RAM);
+//! ```
+//!
+//! The initial values are not restored by a soft reset or [`soft_restart`]: the image must be
+//! loaded again. Cannot be combined with `single-load-segment`.
+//!
+//! ## `ram-test`
+//!
+//! Runs a March C- test over `.data` and `.bss` on the initializing hart, after `__pre_init` (which
+//! may have to set up the memory controller) and before `.bss` and `.data` are initialized. The
+//! first faulty word is reported to `_ram_test_failed`. The background pattern is taken from the
+//! `_ram_test_background` symbol. `.uninit`, the heap and the stacks are not tested.
+//!
+//! The test destroys the contents of the sections, so `__pre_init` must not leave anything in them
+//! (e.g. deferred calls of the `defer` feature are kept in `.uninit` and survive). It runs on the
+//! stack of the hart, which `link.x` checks to be outside of the tested sections, and the other
+//! harts must not use the tested sections before the initialization is completed (see
+//! `init-barrier`).
+//!
+//! Each word of `.data` and `.bss` is read or written 10 times, so the test significantly extends
+//! the boot time on large memories. `_watchdog_feed` is called every 1024 words.
+//!
+//! Cannot be combined with the `ram` feature, whose `.data` is loaded in place.
+//!
+//! ## `full-trap-frame`
+//!
+//! Replaces the trap entry (`_start_trap`) with one that also saves the interrupted `sp` and the
+//! values of `mepc`, `mcause` and `mtval` (`sepc`, `scause`, `stval` with the `s-mode` feature)
+//! after the [`TrapFrame`], so that an exception handler can report the full fault context with
+//! `TrapFrame::full` (`unsafe`, as only the frame received by a handler is followed by it). The
+//! `Debug` implementation of `FullTrapFrame` prints all the registers in hexadecimal and decodes
+//! the cause:
+//!
+//! ``` ignore
+//! #[riscv_rt::exception]
+//! fn exception_handler(trap_frame: &riscv_rt::TrapFrame) -> ! {
+//!     // e.g. `FullTrapFrame { cause: Exception(2, "Illegal instruction"), epc: 0x20000104, .. }`
+//!     log::error!("{:?}", unsafe { trap_frame.full() });
+//!     loop {}
+//! }
+//! ```
+//!
+//! The frame grows from 16 to 20 registers, which keeps the stack 16-byte aligned. The saved values
+//! are only for inspection: the handlers change `mepc` through the CSR (see `skip_instruction`).
+//! Cannot be combined with the other features replacing `_start_trap`: `fp-trap-save`,
+//! `interrupt-stack` and `stack-overflow-hook`.
+//!
+//! ## `clic` and `nxti`
+//!
+//! The `clic` feature sets up the CLIC, with `mtvec` in CLIC mode and `mtvt` pointing to
+//! `interrupt_vector`, whose entry `N` jumps to `int_N`. The `nxti` feature selects how the
+//! handlers defined with `#[interrupt_handler]` are entered: without it, they save the context
+//! themselves and return with `mret`, so they can only be reached through hardware vectoring; with
+//! it, they are plain functions called by a dispatcher. The dispatcher is chosen at run time by the
+//! `_setup_interrupts` function, e.g. with `#[setup_interrupts(mode = "..")]`:
+//!
+//! | `nxti` | mode                | `mtvec`              | non-vectored interrupts             |
+//! |--------|---------------------|----------------------|-------------------------------------|
+//! | no     | `clic` (default)    | `_start_trap`        | `DefaultHandler`                    |
+//! | no     | `clic_shv`          | `_start_trap`        | none: `shv` is set for every line   |
+//! | yes    | `clic`, `clic_nxti` | `_nxti_trap_handler` | all pending ones, through `mnxti`   |
+//! | yes    | `clic_direct`       | `_start_trap`        | one per trap, by `_start_trap_rust` |
+//!
+//! The lines with `shv` set (see `clic::Clic::set_shv`) are vectored by the hardware to their
+//! `int_N` in every mode, which requires the handlers without `nxti`. In `clic_nxti` mode, the
+//! exceptions also go to `_nxti_trap_handler`, which does not dispatch them, while `clic_direct`
+//! handles them in `_start_trap_rust` like the interrupts, trading the back-to-back handling of
+//! pending interrupts for the trap frame and exception handlers of the regular trap path.
+//! Edge-triggered interrupts are only acknowledged by the hardware when vectored or through
+//! `mnxti`: in `clic_direct` mode their handlers must clear the pending bit (`clic::set_pending`).
+//!
+//! ## `v-trap`
+//!
+//! Without the CLIC, sets up the trap vector in vectored mode, where an interrupt with code `N`
+//! jumps to entry `N` of `_vector_table`. Each core interrupt (`SupervisorSoft`, `MachineTimer`,
+//! `MachineExternal`, ...) gets a trampoline `_vtrap_<Handler>` that saves the caller-saved
+//! registers, calls the handler directly and returns from the trap, skipping the decoding of
+//! `mcause` and the table lookup of `_start_trap_rust`. The exceptions, the user software interrupt
+//! (entry 0) and the interrupts above the core ones still go through `_start_trap`. With the
+//! `s-mode` feature, the entries of the machine-level interrupts go to `_start_trap` as well.
+//!
+//! From the trap to the first instruction of the handler, a trampoline executes 18 instructions
+//! (the jump of the table entry, the stack adjustment and the 16 stores, plus the call), against
+//! the same 18 followed by the prologue of `_start_trap_rust`, the read and decoding of `mcause`,
+//! the bounds check, the load from `__INTERRUPTS` and the indirect call on the `_start_trap` path.
+//! The `vtrap_timer` example, run on QEMU by `check-qemu.sh`, prints the entry latency of
+//! `MachineSoft` in both modes.
+//!
+//! The trampolines bypass `_start_trap` and `_start_trap_rust`, so the features acting there do not
+//! apply to the core interrupts: the FPU state of `fp-trap-save`, the interrupt stack of
+//! `interrupt-stack`, the emergency stack of `stack-overflow-hook` and the fault context of
+//! `full-trap-frame`. These features, as well as `clic` and `nesting-limit` (whose depth is only
+//! counted by the handler wrappers of the CLIC), cannot be combined with `v-trap`. The exceptions
+//! still go through `_start_trap`, so `chain_to_previous_handler` and the traps of the
+//! `boot-selftest` feature are not affected. `set_trap_mode` can still switch back to the direct
+//! mode at run time.
+
+// NOTE: Adapted from cortex-m/src/lib.rs
+#![cfg_attr(not(feature = "std"), no_std)]
+#![deny(missing_docs)]
+
+use core::arch::{asm, global_asm};
+
+#[cfg(all(feature = "fp-trap-save", not(target_feature = "f")))]
+compile_error!("the `fp-trap-save` feature requires a target with the F extension");
+
+#[cfg(feature = "clic")]
+use riscv_clic as riscv_crate;
+
+#[cfg(not(feature = "clic"))]
+use ::riscv as riscv_crate;
+
+#[cfg(feature = "s-mode")]
+use riscv_crate::register::{scause as xcause, stvec as xtvec, stvec::TrapMode as xTrapMode};
+
+#[cfg(not(feature = "s-mode"))]
+use riscv_crate::register::{
+    mcause as xcause, mhartid, mtvec as xtvec, mtvec::TrapMode as xTrapMode,
+};
+
+// TODO: enable this for s-mode
+#[cfg(feature = "clic")]
+use riscv_crate::register::{mtvec::SubMode as xSubMode, mtvt as xtvt};
+
+pub use riscv_rt_macros::{
+    default_handler, entry, exception, exception_handler, interrupt_handler, mp_hook, pre_init,
+    ramfunc, require_handlers, setup_interrupts, unhandled_interrupt,
+};
+
+#[cfg(not(feature = "std"))]
+mod backtrace;
+#[cfg(not(feature = "std"))]
+mod boot;
+#[cfg(any(feature = "zicbom", feature = "zicboz"))]
+pub mod cache;
+mod cause;
+#[cfg(not(feature = "std"))]
+mod chain;
+// also built for the host unit tests, which access fake registers
+#[cfg(any(feature = "clic", all(test, feature = "std")))]
+pub mod clic;
+#[cfg(all(not(feature = "s-mode"), not(feature = "std")))]
+pub mod clint;
+#[cfg(not(feature = "s-mode"))]
+mod cpu;
+#[cfg(feature = "defer")]
+mod defer;
+mod exception;
+pub mod fdt;
+#[cfg(all(feature = "fp-trap-save", target_feature = "f"))]
+mod fptrap;
+#[cfg(target_feature = "f")]
+mod fpu;
+#[cfg(feature = "full-trap-frame")]
+mod fullframe;
+#[cfg(feature = "stack-overflow-hook")]
+mod guard;
+pub mod handler_map;
+#[cfg(target_feature = "a")]
+mod hart;
+#[cfg(feature = "image-crc")]
+mod image;
+#[cfg(not(feature = "std"))]
+mod init;
+#[cfg(feature = "interrupt-stack")]
+mod istack;
+pub mod layout;
+mod memory;
+pub mod mmio;
+#[cfg(all(not(feature = "clic"), not(feature = "std")))]
+mod pending;
+#[cfg(not(feature = "std"))]
+pub mod perf;
+mod privilege;
+#[cfg(feature = "ram-test")]
+mod ramtest;
+#[cfg(feature = "s-mode")]
+pub mod sbi;
+#[cfg(feature = "boot-selftest")]
+mod selftest;
+#[cfg(feature = "s-mode")]
+mod timer;
+#[cfg(all(not(feature = "s-mode"), not(feature = "std")))]
+mod user;
+#[cfg(feature = "v-trap")]
+mod vtrap;
+
+#[cfg(not(feature = "std"))]
+pub use backtrace::backtrace;
+#[cfg(not(feature = "std"))]
+pub use boot::{jump_to, soft_restart};
+pub use cause::{exception_description, interrupt_description};
+#[cfg(not(feature = "std"))]
+pub use chain::{chain_to_previous_handler, previous_handler, save_previous_handler};
+#[cfg(not(feature = "s-mode"))]
+pub use cpu::{cpu_id, mconfigptr, CpuId};
+#[cfg(feature = "defer")]
+pub use defer::{defer, DEFER_CAPACITY};
+pub use exception::{skip_instruction, with_exception_handler};
+#[cfg(feature = "full-trap-frame")]
+pub use fullframe::FullTrapFrame;
+#[cfg(target_feature = "a")]
+pub use hart::{hart_online, harts_online, wait_for_harts};
+#[cfg(feature = "image-crc")]
+pub use image::verify_image;
+#[cfg(feature = "payload")]
+pub use memory::payload;
+pub use memory::{
+    current_stack_bounds, hart_local, hart_stack_size, max_hart_id, memory_map, sizes,
+    total_stack_required, validate_layout, LayoutError, MemoryMap, Sizes,
+};
+#[cfg(all(not(feature = "clic"), not(feature = "std")))]
+pub use pending::{poll_interrupts, trigger_interrupt};
+pub use privilege::{current_privilege, Privilege};
+#[cfg(feature = "sstc")]
+pub use timer::set_stimecmp;
+#[cfg(feature = "s-mode")]
+pub use timer::set_timer;
+#[cfg(all(not(feature = "s-mode"), not(feature = "std")))]
+pub use user::enter_user;
+
+include!(concat!(env!("OUT_DIR"), "/config.rs"));
+
+#[export_name = "error: riscv-rt appears more than once in the dependency graph"]
+#[doc(hidden)]
+pub static __ONCE__: () = ();
+
+extern "C" {
+    // Boundaries of the .bss section
+    static mut _ebss: u32;
+    static mut _sbss: u32;
+
+    // Boundaries of the .data section
+    static mut _edata: u32;
+    static mut _sdata: u32;
+
+    // Initial values of the .data section (stored in Flash)
+    static _sidata: u32;
+}
+
+extern "Rust" {
+    // Called from the busy-loops of this crate, see `_watchdog_feed`
+    fn _watchdog_feed();
+}
+
+/// Value of `INIT_BARRIER` once the `.bss` and `.data` sections are initialized
+#[cfg(feature = "init-barrier")]
+const INIT_BARRIER_DONE: usize = 0x1b17_d0e5;
+
+/// Released by the initializing hart, see the `init-barrier` feature. Cleared explicitly before
+/// the memory initialization, as it may still be released after a warm reset.
+#[cfg(feature = "init-barrier")]
+static mut INIT_BARRIER: usize = 0;
+
+/// Rust entry point (_start_rust)
+///
+/// Zeros bss section, initializes data section and calls main. This function
+/// never returns.
+#[cfg(not(feature = "std"))]
+#[link_section = ".init.rust"]
+#[export_name = "_start_rust"]
+pub unsafe extern "C" fn start_rust(a0: usize, a1: usize, a2: usize) -> ! {
+    #[rustfmt::skip]
+    extern "Rust" {
+        // This symbol will be provided by the user via `#[entry]`
+        fn main(a0: usize, a1: usize, a2: usize) -> !;
+
+        // This symbol will be provided by the user via `#[pre_init]`
+        fn __pre_init();
+
+        fn _setup_interrupts();
+
+        fn _mp_hook(hartid: usize) -> bool;
     }
 
-    // sbi passes hartid as first parameter (a0)
+    // sbi passes hartid as first parameter (a0), keep it in `tp` for `hart_id()`
     #[cfg(feature = "s-mode")]
     let hartid = a0;
+    #[cfg(feature = "s-mode")]
+    asm!("mv tp, {0}", in(reg) hartid);
     #[cfg(not(feature = "s-mode"))]
     let hartid = mhartid::read();
 
-    if _mp_hook(hartid) {
+    let initializer = _mp_hook(hartid);
+    if initializer {
+        #[cfg(feature = "init-barrier")]
+        {
+            core::ptr::write_volatile(core::ptr::addr_of_mut!(INIT_BARRIER), 0);
+            core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+        }
+
+        #[cfg(feature = "defer")]
+        defer::reset();
+
         __pre_init();
 
-        r0::zero_bss(&mut _sbss, &mut _ebss);
-        r0::init_data(&mut _sdata, &mut _edata, &_sidata);
+        #[cfg(feature = "ram-test")]
+        ramtest::run();
+
+        #[cfg(not(feature = "fast-bss"))]
+        init::zero_bss(&mut _sbss, &mut _ebss);
+        #[cfg(feature = "fast-bss")]
+        init::zero_bss_fast(&mut _sbss, &mut _ebss);
+        #[cfg(not(feature = "ram"))]
+        {
+            extern "C" {
+                // `_eramfunc - _sramfunc`, stored in `.rodata` by `link.x`
+                static _ramfunc_size: u32;
+            }
+
+            init::init_data(&mut _sdata, &mut _edata, &_sidata);
+            // the instruction fetches must observe the `#[ramfunc]` code just copied
+            if _ramfunc_size != 0 {
+                asm!("fence.i");
+            }
+        }
+
+        #[cfg(feature = "run-init-array")]
+        init::run_init_array();
+
+        #[cfg(feature = "init-barrier")]
+        {
+            core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+            core::ptr::write_volatile(core::ptr::addr_of_mut!(INIT_BARRIER), INIT_BARRIER_DONE);
+        }
     }
 
-    // TODO: Enable FPU when available
+    #[cfg(feature = "init-barrier")]
+    if !initializer {
+        while core::ptr::read_volatile(core::ptr::addr_of!(INIT_BARRIER)) != INIT_BARRIER_DONE {
+            _watchdog_feed();
+        }
+        core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+    }
+
+    #[cfg(not(feature = "s-mode"))]
+    {
+        extern "C" {
+            static _mstatus_init_mask: u8;
+            static _mstatus_init_value: u8;
+        }
+
+        let mask = &_mstatus_init_mask as *const u8 as usize;
+        if mask != 0 {
+            let value = &_mstatus_init_value as *const u8 as usize & mask;
+            asm!("csrc mstatus, {0}", "csrs mstatus, {1}", in(reg) mask, in(reg) value);
+        }
+    }
+
+    {
+        extern "C" {
+            // `_envcfg_init_mask` and `_envcfg_init_value`, stored in `.rodata` by `link.x`
+            static _envcfg_init: [u64; 2];
+        }
+
+        // `menvcfg` (0x30a) and `senvcfg` (0x10a) are only accessed with a non-zero mask, cores
+        // older than privileged spec 1.12 do not implement them
+        let mask = _envcfg_init[0] as usize;
+        if mask != 0 {
+            let value = _envcfg_init[1] as usize & mask;
+            #[cfg(not(feature = "s-mode"))]
+            asm!("csrc 0x30a, {0}", "csrs 0x30a, {1}", in(reg) mask, in(reg) value);
+            #[cfg(feature = "s-mode")]
+            asm!("csrc 0x10a, {0}", "csrs 0x10a, {1}", in(reg) mask, in(reg) value);
+        }
+    }
+
+    #[cfg(all(target_arch = "riscv32", not(feature = "s-mode")))]
+    {
+        extern "C" {
+            // `_envcfgh_init_mask` and `_envcfgh_init_value`, stored in `.rodata` by `link.x`
+            static _envcfgh_init: [u32; 2];
+        }
+
+        // `menvcfgh` (0x31a), only accessed with a non-zero mask like `menvcfg`
+        let mask = _envcfgh_init[0] as usize;
+        if mask != 0 {
+            let value = _envcfgh_init[1] as usize & mask;
+            asm!("csrc 0x31a, {0}", "csrs 0x31a, {1}", in(reg) mask, in(reg) value);
+        }
+    }
+
+    #[cfg(feature = "emergency-stack")]
+    {
+        extern "C" {
+            static _emergency_stack: u8;
+            static _emergency_stack_size: u8;
+        }
+
+        // top of the emergency stack of this hart, stacks are laid out downwards by hart id
+        let size = &_emergency_stack_size as *const u8 as usize;
+        let top = &_emergency_stack as *const u8 as usize - hartid * size;
+        #[cfg(not(feature = "s-mode"))]
+        asm!("csrw mscratch, {0}", in(reg) top);
+        #[cfg(feature = "s-mode")]
+        asm!("csrw sscratch, {0}", in(reg) top);
+    }
+
+    #[cfg(feature = "interrupt-stack")]
+    {
+        extern "C" {
+            static _interrupt_stack_start: u8;
+            static _interrupt_stack_size: u8;
+        }
+
+        // top of the interrupt stack of this hart, as for the emergency stack
+        let size = &_interrupt_stack_size as *const u8 as usize;
+        let top = &_interrupt_stack_start as *const u8 as usize - hartid * size;
+        #[cfg(not(feature = "s-mode"))]
+        asm!("csrw mscratch, {0}", in(reg) top);
+        #[cfg(feature = "s-mode")]
+        asm!("csrw sscratch, {0}", in(reg) top);
+    }
+
+    #[cfg(target_feature = "f")]
+    fpu::enable();
+    #[cfg(all(feature = "zero-fpu", target_feature = "f"))]
+    fpu::zero_registers();
 
     _setup_interrupts();
 
+    {
+        extern "C" {
+            static _mie_init: u8;
+        }
+
+        // only once the trap vector is set up
+        let mie = &_mie_init as *const u8 as usize;
+        if mie != 0 {
+            #[cfg(not(feature = "s-mode"))]
+            asm!("csrs mie, {0}", in(reg) mie);
+            #[cfg(feature = "s-mode")]
+            asm!("csrs sie, {0}", in(reg) mie);
+        }
+    }
+
+    #[cfg(feature = "boot-selftest")]
+    if initializer {
+        selftest::run();
+    }
+
+    #[cfg(feature = "defer")]
+    if initializer {
+        defer::run();
+    }
+
     main(a0, a1, a2);
 }
 
+/// Returns the id of the calling hart.
+///
+/// In machine mode, the id is read from `mhartid`. `mhartid` is not accessible in supervisor mode,
+/// where `start_rust` stashes the hart id passed by the SBI implementation in `tp` instead (which
+/// is otherwise unused without thread-local storage). With the `s-mode` feature, the value is
+/// therefore only valid once `start_rust` ran, i.e. from `_mp_hook` on, and as long as `tp` is
+/// not repurposed.
+#[inline]
+pub fn hart_id() -> usize {
+    #[cfg(not(feature = "s-mode"))]
+    let hartid = mhartid::read();
+    #[cfg(feature = "s-mode")]
+    let hartid = {
+        let hartid: usize;
+        unsafe { asm!("mv {0}, tp", out(reg) hartid) };
+        hartid
+    };
+    hartid
+}
+
 /// Registers saved in trap handler
+///
+/// These are the caller-saved registers of the calling convention (all of them, on RV32 and
+/// RV64 alike). The trap handlers are ordinary Rust functions that may clobber any of them, so
+/// the trap entry cannot save fewer without corrupting the interrupted code. Latency-critical
+/// applications that need a smaller save set have to provide their own `_start_trap` together
+/// with handlers that respect it (e.g. written in assembly). With the `full-trap-frame` feature,
+/// the frame is part of a [`FullTrapFrame`](TrapFrame::full) that also holds the fault context.
 #[allow(missing_docs)]
 #[repr(C)]
 #[derive(Debug)]
@@ -464,21 +1687,35 @@ pub struct TrapFrame {
 /// Trap entry point rust (_start_trap_rust)
 ///
 /// `scause`/`mcause` is read to determine the cause of the trap. XLEN-1 bit indicates
-/// if it's an interrupt or an exception. The result is examined and ExceptionHandler
-/// or one of the core interrupt handlers is called.
+/// if it's an interrupt or an exception. The result is examined and one of the exception
+/// handlers (`ExceptionHandler` by default) or one of the core interrupt handlers is called.
 #[link_section = ".trap.rust"]
 #[export_name = "_start_trap_rust"]
 pub extern "C" fn start_trap_rust(trap_frame: *const TrapFrame) {
     extern "C" {
-        fn ExceptionHandler(trap_frame: &TrapFrame);
         fn DefaultHandler();
     }
 
     unsafe {
+        #[cfg(feature = "boot-selftest")]
+        if selftest::handle(&*trap_frame) {
+            return;
+        }
+
         let cause = xcause::read();
 
         if cause.is_exception() {
-            ExceptionHandler(&*trap_frame)
+            if let Some(handler) = exception::handler() {
+                return handler(&*trap_frame);
+            }
+
+            // masks the additional fields of `mcause` with the CLIC
+            let code = __interrupt_code();
+            if code < __EXCEPTIONS.len() {
+                (__EXCEPTIONS[code])(&*trap_frame)
+            } else {
+                ExceptionHandler(&*trap_frame)
+            }
         } else {
             #[cfg(not(feature = "clic"))]
             if cause.code() < __INTERRUPTS.len() {
@@ -491,7 +1728,24 @@ pub extern "C" fn start_trap_rust(trap_frame: *const TrapFrame) {
             } else {
                 DefaultHandler();
             }
-            #[cfg(feature = "clic")]
+            // the vector table entries are `j int_N` instructions, which the `nxti` handlers
+            // return from like functions; without `nxti` they return with `mret`
+            #[cfg(all(feature = "clic", feature = "nxti"))]
+            {
+                extern "C" {
+                    static interrupt_vector: u32;
+                }
+
+                let code = __interrupt_code();
+                if code < CLIC_VECTOR_LEN {
+                    let entry = core::ptr::addr_of!(interrupt_vector).add(code);
+                    let entry: extern "C" fn() = core::mem::transmute(entry);
+                    entry();
+                } else {
+                    DefaultHandler();
+                }
+            }
+            #[cfg(all(feature = "clic", not(feature = "nxti")))]
             DefaultHandler();
         }
     }
@@ -502,9 +1756,9 @@ pub extern "C" fn start_trap_rust(trap_frame: *const TrapFrame) {
 #[allow(unused_variables, non_snake_case)]
 pub fn DefaultExceptionHandler(trap_frame: &TrapFrame) -> ! {
     loop {
-        // Prevent this from turning into a UDF instruction
+        // Calling the hook also prevents this from turning into a UDF instruction
         // see rust-lang/rust#28728 for details
-        continue;
+        unsafe { _watchdog_feed() };
     }
 }
 
@@ -513,15 +1767,28 @@ pub fn DefaultExceptionHandler(trap_frame: &TrapFrame) -> ! {
 #[allow(unused_variables, non_snake_case)]
 pub fn DefaultInterruptHandler() {
     loop {
-        // Prevent this from turning into a UDF instruction
+        // Calling the hook also prevents this from turning into a UDF instruction
         // see rust-lang/rust#28728 for details
-        continue;
+        unsafe { _watchdog_feed() };
     }
 }
 
+/// Returns the code of the interrupt being handled, for `#[unhandled_interrupt]`.
+#[doc(hidden)]
+#[inline]
+pub fn __interrupt_code() -> usize {
+    // with the CLIC, `mcause` holds further fields above the 12-bit code
+    #[cfg(feature = "clic")]
+    let code = xcause::read().bits() & 0xfff;
+    #[cfg(not(feature = "clic"))]
+    let code = xcause::read().code();
+    code
+}
+
 /* Interrupts */
 #[cfg(not(feature = "clic"))]
 #[doc(hidden)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Interrupt {
     UserSoft,
     SupervisorSoft,
@@ -548,8 +1815,54 @@ extern "C" {
     fn UserExternal();
     fn SupervisorExternal();
     fn MachineExternal();
+
+    // Reserved by the privileged specification, may be used by non-standard cores
+    fn CoreInterrupt2();
+    fn CoreInterrupt6();
+    fn CoreInterrupt10();
+}
+
+extern "C" {
+    fn InstructionMisaligned(trap_frame: &TrapFrame);
+    fn InstructionFault(trap_frame: &TrapFrame);
+    fn IllegalInstruction(trap_frame: &TrapFrame);
+    fn Breakpoint(trap_frame: &TrapFrame);
+    fn LoadMisaligned(trap_frame: &TrapFrame);
+    fn LoadFault(trap_frame: &TrapFrame);
+    fn StoreMisaligned(trap_frame: &TrapFrame);
+    fn StoreFault(trap_frame: &TrapFrame);
+    fn UserEnvCall(trap_frame: &TrapFrame);
+    fn SupervisorEnvCall(trap_frame: &TrapFrame);
+    fn MachineEnvCall(trap_frame: &TrapFrame);
+    fn InstructionPageFault(trap_frame: &TrapFrame);
+    fn LoadPageFault(trap_frame: &TrapFrame);
+    fn StorePageFault(trap_frame: &TrapFrame);
+
+    fn ExceptionHandler(trap_frame: &TrapFrame);
 }
 
+/// Exception handlers by exception code, the reserved codes go to `ExceptionHandler`
+#[doc(hidden)]
+#[no_mangle]
+pub static __EXCEPTIONS: [unsafe extern "C" fn(&TrapFrame); 16] = [
+    InstructionMisaligned,
+    InstructionFault,
+    IllegalInstruction,
+    Breakpoint,
+    LoadMisaligned,
+    LoadFault,
+    StoreMisaligned,
+    StoreFault,
+    UserEnvCall,
+    SupervisorEnvCall,
+    ExceptionHandler,
+    MachineEnvCall,
+    InstructionPageFault,
+    LoadPageFault,
+    ExceptionHandler,
+    StorePageFault,
+];
+
 #[cfg(not(feature = "clic"))]
 #[doc(hidden)]
 pub union Vector {
@@ -557,7 +1870,7 @@ pub union Vector {
     pub reserved: usize,
 }
 
-#[cfg(not(feature = "clic"))]
+#[cfg(all(not(feature = "clic"), not(feature = "s-mode")))]
 #[doc(hidden)]
 #[no_mangle]
 pub static __INTERRUPTS: [Vector; 12] = [
@@ -565,7 +1878,9 @@ pub static __INTERRUPTS: [Vector; 12] = [
     Vector {
         handler: SupervisorSoft,
     },
-    Vector { reserved: 0 },
+    Vector {
+        handler: CoreInterrupt2,
+    },
     Vector {
         handler: MachineSoft,
     },
@@ -573,7 +1888,9 @@ pub static __INTERRUPTS: [Vector; 12] = [
     Vector {
         handler: SupervisorTimer,
     },
-    Vector { reserved: 0 },
+    Vector {
+        handler: CoreInterrupt6,
+    },
     Vector {
         handler: MachineTimer,
     },
@@ -583,25 +1900,120 @@ pub static __INTERRUPTS: [Vector; 12] = [
     Vector {
         handler: SupervisorExternal,
     },
-    Vector { reserved: 0 },
+    Vector {
+        handler: CoreInterrupt10,
+    },
     Vector {
         handler: MachineExternal,
     },
 ];
 
+// In supervisor mode, machine level interrupts are never delegated and therefore never dispatched.
+#[cfg(all(not(feature = "clic"), feature = "s-mode"))]
+#[doc(hidden)]
+#[no_mangle]
+pub static __INTERRUPTS: [Vector; 12] = [
+    Vector { handler: UserSoft },
+    Vector {
+        handler: SupervisorSoft,
+    },
+    Vector {
+        handler: CoreInterrupt2,
+    },
+    Vector { reserved: 0 },
+    Vector { handler: UserTimer },
+    Vector {
+        handler: SupervisorTimer,
+    },
+    Vector {
+        handler: CoreInterrupt6,
+    },
+    Vector { reserved: 0 },
+    Vector {
+        handler: UserExternal,
+    },
+    Vector {
+        handler: SupervisorExternal,
+    },
+    Vector {
+        handler: CoreInterrupt10,
+    },
+    Vector { reserved: 0 },
+];
+
 #[doc(hidden)]
 #[no_mangle]
 #[rustfmt::skip]
 pub unsafe extern "Rust" fn default_pre_init() {}
 
+#[doc(hidden)]
+#[no_mangle]
+#[rustfmt::skip]
+pub extern "Rust" fn default_watchdog_feed() {}
+
+#[doc(hidden)]
+#[no_mangle]
+#[rustfmt::skip]
+pub extern "Rust" fn default_setup_failed() {}
+
+#[doc(hidden)]
+#[no_mangle]
+#[cfg(feature = "boot-selftest")]
+pub extern "Rust" fn default_selftest_failed() {
+    loop {
+        unsafe { _watchdog_feed() };
+    }
+}
+
+#[doc(hidden)]
+#[no_mangle]
+#[allow(unused_variables)]
+#[cfg(feature = "ram-test")]
+pub extern "Rust" fn default_ram_test_failed(addr: usize) {
+    loop {
+        unsafe { _watchdog_feed() };
+    }
+}
+
+#[doc(hidden)]
+#[no_mangle]
+#[allow(unused_variables)]
+#[cfg(feature = "stack-overflow-hook")]
+pub extern "C" fn default_stack_overflow(sp: usize, fault_addr: usize) -> ! {
+    loop {
+        unsafe { _watchdog_feed() };
+    }
+}
+
+#[doc(hidden)]
+#[no_mangle]
+#[allow(unused_variables)]
+#[cfg(feature = "nesting-limit")]
+pub extern "C" fn default_max_nesting_exceeded(depth: usize) {}
+
+/// Nesting depth of the `#[interrupt_handler]` wrappers, per hart
+#[doc(hidden)]
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+#[cfg(feature = "nesting-limit")]
+pub static mut __riscv_rt_nesting_depth: [u32; MAX_HART_ID + 1] = [0; MAX_HART_ID + 1];
+
 #[doc(hidden)]
 #[no_mangle]
 #[rustfmt::skip]
 pub extern "Rust" fn default_mp_hook(hartid: usize) -> bool {
+    extern "C" {
+        static _boot_hartid: u8;
+    }
+
+    let boot_hartid = unsafe { &_boot_hartid as *const u8 as usize };
     match hartid {
-        0 => true,
+        _ if hartid == boot_hartid => true,
         _ => loop {
-            unsafe { riscv::asm::wfi() }
+            unsafe {
+                riscv::asm::wfi();
+                _watchdog_feed();
+            }
         },
     }
 }
@@ -612,125 +2024,342 @@ pub extern "Rust" fn default_mp_hook(hartid: usize) -> bool {
 #[rustfmt::skip]
 #[cfg(not(feature = "clic"))]
 pub unsafe extern "Rust" fn default_setup_interrupts() {
-    {
-        extern "C" {
-            fn _start_trap();
-        }   
-        xtvec::write(_start_trap as usize, xTrapMode::Direct);
-    }
+    #[cfg(not(feature = "v-trap"))]
+    __setup::direct();
+    #[cfg(feature = "v-trap")]
+    __setup::vectored();
 }
 
-/// Default implementation of `_setup_interrupts` for CLIC that
-/// 
+/// Default implementation of `_setup_interrupts` for CLIC that sets `mtvec` in CLIC mode and
+/// `mtvt` to the interrupt vector.
 #[doc(hidden)]
 #[no_mangle]
 #[rustfmt::skip]
 #[cfg(feature = "clic")]
 pub unsafe extern "Rust" fn default_setup_interrupts() {
+    __setup::clic();
+}
 
-    {
+/// Checks that `mtvec` (`stvec` with the `s-mode` feature) holds `expected`, i.e. the base
+/// address combined with the mode bits (0 for direct, 1 for vectored, 3 for CLIC mode).
+///
+/// Cores may not implement every mode, nor every bit of the base address, in which case the
+/// written value does not stick. If the value read back differs, `_setup_failed` is called and
+/// `false` is returned. The setups generated by `#[setup_interrupts(mode = "..")]`, which are
+/// also the default `_setup_interrupts`, verify the value they write.
+pub fn verify_tvec(expected: usize) -> bool {
+    extern "Rust" {
+        fn _setup_failed();
+    }
+
+    let ok = xtvec::read().bits() == expected;
+    if !ok {
+        unsafe { _setup_failed() };
+    }
+    ok
+}
+
+/// Trap vector mode of `mtvec` (`stvec` with the `s-mode` feature), see [`set_trap_mode`]
+#[cfg(not(feature = "clic"))]
+pub use xTrapMode as TrapMode;
+
+/// Switches the trap vector between direct and vectored mode at run time.
+///
+/// In direct mode, `mtvec` (`stvec` with the `s-mode` feature) points to `_start_trap`. In
+/// vectored mode, it points to `_vector_table`, whose entries all jump to `_start_trap` (to the
+/// trampolines of the core interrupts with the `v-trap` feature). Both modes therefore dispatch
+/// through the same handlers and can be switched at any time, e.g. to start in direct mode and
+/// move to vectored mode once the platform is set up. The vector table is only linked into images
+/// that use the vectored mode.
+///
+/// Returns whether the core accepted the mode, see [`verify_tvec`].
+///
+/// # Safety
+///
+/// This replaces any trap vector installed by a custom `_setup_interrupts`.
+#[cfg(all(not(feature = "clic"), not(feature = "std")))]
+pub unsafe fn set_trap_mode(mode: TrapMode) -> bool {
+    extern "C" {
+        fn _start_trap();
+        fn _vector_table();
+    }
+
+    match mode {
+        TrapMode::Direct => {
+            xtvec::write(_start_trap as usize, TrapMode::Direct);
+            verify_tvec(_start_trap as usize)
+        }
+        TrapMode::Vectored => {
+            xtvec::write(_vector_table as usize, TrapMode::Vectored);
+            verify_tvec(_vector_table as usize | 1)
+        }
+    }
+}
+
+/// Standard trap vector configurations, generated by `#[setup_interrupts(mode = "..")]`
+#[doc(hidden)]
+pub mod __setup {
+    use super::verify_tvec;
+    #[cfg(feature = "clic")]
+    use super::{clic, xSubMode, xTrapMode, xtvec, xtvt};
+    #[cfg(not(feature = "clic"))]
+    use super::{xTrapMode, xtvec};
+
+    extern "C" {
+        fn _start_trap();
+    }
+
+    /// `mtvec`/`stvec` points to `_start_trap` in direct mode.
+    #[cfg(not(feature = "clic"))]
+    pub unsafe fn direct() {
+        #[cfg(not(feature = "std"))]
+        crate::save_previous_handler();
+        xtvec::write(_start_trap as usize, xTrapMode::Direct);
+        verify_tvec(_start_trap as usize);
+    }
+
+    /// `mtvec`/`stvec` points to `_vector_table` in vectored mode.
+    #[cfg(not(feature = "clic"))]
+    pub unsafe fn vectored() {
         extern "C" {
-            fn _start_trap();
-            fn _nxti_trap_handler();
-        }   
+            fn _vector_table();
+        }
+        #[cfg(not(feature = "std"))]
+        crate::save_previous_handler();
+        xtvec::write(_vector_table as usize, xTrapMode::Vectored);
+        verify_tvec(_vector_table as usize | 1);
+    }
 
-        extern {
-            static interrupt_vector: usize;
+    /// `mtvec` in CLIC mode, `mtvt` points to `interrupt_vector`: `clic_nxti` with the `nxti`
+    /// feature, otherwise the non-vectored interrupts and the exceptions go to `_start_trap`.
+    #[cfg(feature = "clic")]
+    pub unsafe fn clic() {
+        #[cfg(feature = "nxti")]
+        clic_nxti();
+        #[cfg(not(feature = "nxti"))]
+        clic_mode(_start_trap as usize);
+    }
+
+    /// `mtvec` in CLIC mode pointing to `_nxti_trap_handler`, which handles context saving and
+    /// cycles through all pending interrupts with `mnxti`. The exceptions go there as well.
+    #[cfg(all(feature = "clic", feature = "nxti"))]
+    pub unsafe fn clic_nxti() {
+        extern "C" {
+            fn _nxti_trap_handler();
         }
 
-        if cfg!(feature = "nxti") {
-            // _nxti_trap_handler handles context saving and executes cycles through all pending interrupts via the nxti feature
-            xtvec::write(_nxti_trap_handler as usize, xSubMode::Default, xTrapMode::Clic);       
+        clic_mode(_nxti_trap_handler as usize);
+    }
+
+    /// `mtvec` in CLIC mode pointing to `_start_trap`: `_start_trap_rust` calls the handlers of
+    /// the non-vectored interrupts through `interrupt_vector`, one interrupt per trap.
+    #[cfg(all(feature = "clic", feature = "nxti"))]
+    pub unsafe fn clic_direct() {
+        clic_mode(_start_trap as usize);
+    }
+
+    /// Same as `clic` without `nxti`, and additionally selects hardware vectoring (`shv`) for
+    /// every interrupt the CLIC implements, so that `_start_trap` only receives the exceptions.
+    #[cfg(all(feature = "clic", not(feature = "nxti")))]
+    pub unsafe fn clic_shv() {
+        clic_mode(_start_trap as usize);
+
+        let mut clic = clic::Clic::new(clic::base());
+        let count = core::cmp::min(clic::interrupt_count(), crate::CLIC_VECTOR_LEN);
+        for id in 0..count {
+            clic.set_shv(id, true);
         }
-        else
-        {
-            // _start_trap handles all non vectored interrupts and all exceptions
-            xtvec::write(_start_trap as usize, xSubMode::Default, xTrapMode::Clic);       
+    }
+
+    /// `mtvec` in CLIC mode pointing to `handler`, `mtvt` to `interrupt_vector`.
+    #[cfg(feature = "clic")]
+    unsafe fn clic_mode(handler: usize) {
+        extern "C" {
+            static interrupt_vector: u32;
         }
 
-        let interrupt_vector_ptr:*const usize = &interrupt_vector;
-        xtvt::write_addr(interrupt_vector_ptr as usize);
+        crate::save_previous_handler();
+
+        // the trap entries are 64-byte aligned, an unaligned custom `_start_trap` fails the check
+        xtvec::write(handler, xSubMode::Default, xTrapMode::Clic);
+        verify_tvec(handler | 3);
+
+        xtvt::write_addr(core::ptr::addr_of!(interrupt_vector) as usize);
+
+        // mask all interrupts until the application lowers the threshold
+        #[cfg(not(feature = "clic-unmasked-boot"))]
+        clic::set_threshold(clic::THRESHOLD_MASK_ALL);
     }
 }
 
+/// Vector table for the vectored mode of `mtvec`/`stvec`, see `__setup::vectored`. Every entry
+/// goes through `_start_trap`, which dispatches on the cause like in direct mode. The `v-trap`
+/// feature replaces it, see `vtrap.rs`.
+#[cfg(all(not(feature = "clic"), not(feature = "std"), not(feature = "v-trap")))]
+global_asm!(
+    "
+.section .text.vector_table, \"ax\"
+.option push
+.option norvc
+.balign 64
+.global _vector_table
+_vector_table:
+.rept 16
+j _start_trap
+.endr
+.option pop
+"
+);
+
+/// Fallback of the `int_N` entries of the CLIC vector table without an `#[interrupt_handler]`.
+///
+/// The vector table jumps to the entries directly, so unlike in the CLINT dispatch `DefaultHandler`
+/// cannot be the entry itself: this wrapper saves the caller-saved registers around it and returns
+/// with `mret`. Same `$store`/`$load`/`$rb` arguments as `nxti_trap_handler!`.
+#[cfg(all(feature = "clic", not(feature = "nxti")))]
+macro_rules! default_int_wrapper {
+    ($store:literal, $load:literal, $rb:literal) => {
+        global_asm!(
+            ".section .text.default_int_wrapper",
+            ".global _default_int_wrapper",
+            "_default_int_wrapper:",
+            concat!("addi sp, sp, -(", $rb, " * 16)"),
+            concat!($store, " ra, 0*", $rb, "(sp)"),
+            concat!($store, " t0, 1*", $rb, "(sp)"),
+            concat!($store, " t1, 2*", $rb, "(sp)"),
+            concat!($store, " t2, 3*", $rb, "(sp)"),
+            concat!($store, " a0, 4*", $rb, "(sp)"),
+            concat!($store, " a1, 5*", $rb, "(sp)"),
+            concat!($store, " a2, 6*", $rb, "(sp)"),
+            concat!($store, " a3, 7*", $rb, "(sp)"),
+            concat!($store, " a4, 8*", $rb, "(sp)"),
+            concat!($store, " a5, 9*", $rb, "(sp)"),
+            concat!($store, " a6, 10*", $rb, "(sp)"),
+            concat!($store, " a7, 11*", $rb, "(sp)"),
+            concat!($store, " t3, 12*", $rb, "(sp)"),
+            concat!($store, " t4, 13*", $rb, "(sp)"),
+            concat!($store, " t5, 14*", $rb, "(sp)"),
+            concat!($store, " t6, 15*", $rb, "(sp)"),
+            "jal DefaultHandler",
+            concat!($load, " ra, 0*", $rb, "(sp)"),
+            concat!($load, " t0, 1*", $rb, "(sp)"),
+            concat!($load, " t1, 2*", $rb, "(sp)"),
+            concat!($load, " t2, 3*", $rb, "(sp)"),
+            concat!($load, " a0, 4*", $rb, "(sp)"),
+            concat!($load, " a1, 5*", $rb, "(sp)"),
+            concat!($load, " a2, 6*", $rb, "(sp)"),
+            concat!($load, " a3, 7*", $rb, "(sp)"),
+            concat!($load, " a4, 8*", $rb, "(sp)"),
+            concat!($load, " a5, 9*", $rb, "(sp)"),
+            concat!($load, " a6, 10*", $rb, "(sp)"),
+            concat!($load, " a7, 11*", $rb, "(sp)"),
+            concat!($load, " t3, 12*", $rb, "(sp)"),
+            concat!($load, " t4, 13*", $rb, "(sp)"),
+            concat!($load, " t5, 14*", $rb, "(sp)"),
+            concat!($load, " t6, 15*", $rb, "(sp)"),
+            concat!("addi sp, sp, (", $rb, " * 16)"),
+            "mret",
+        );
+    };
+}
+
+#[cfg(all(feature = "clic", not(feature = "nxti"), target_arch = "riscv32"))]
+default_int_wrapper!("sw", "lw", "4");
+#[cfg(all(feature = "clic", not(feature = "nxti"), target_arch = "riscv64"))]
+default_int_wrapper!("sd", "ld", "8");
+
+/// NXTI interrupt handler, with `REGBYTES` (`$rb`) slots saved and restored with `$store`/`$load`
 #[cfg(all(feature = "clic", feature = "nxti"))]
-global_asm!("
-/* NXTI interrupt handler */
-.section .text.nxti_trap_handler
-.global _nxti_trap_handler
-_nxti_trap_handler:
-/* store context */
-addi sp, sp, -(4 * 32)
-sw ra, 0(sp)
-sw t0, 4(sp)
-sw t1, 8(sp)
-sw t2, 12(sp)
-sw a0, 16(sp)
-sw a1, 20(sp)
-sw a2, 24(sp)
-sw a3, 28(sp)
-sw a4, 32(sp)
-sw a5, 36(sp)
-sw a6, 40(sp)
-sw a7, 44(sp)
-sw t3, 48(sp)
-sw t4, 52(sp)
-sw t5, 56(sp)
-sw t6, 60(sp)
-csrr t0, mcause
-csrr t1, mepc
-sw t0, 64(sp)
-sw t1, 68(sp)
-
-/* read out the address of the mtvt entry of the next pending interrupt */
-/* enables interrupts, and clears the pending bit of the found interrupt */
-1:
-csrrsi t0, 0x345, 8
-
-
-/* if no interrupt is pending, the received addr (t0) will be 0 */
-beqz t0, 2f
-
-/* jump to interrupt vector table */
-jalr t0
-
-/* repeat until no more interrupts are pending */
-j 1b
-
-2:
-
-csrci mstatus, 8 /* disable global interrupts*/
-
-/* load context */
-lw t0, 64(sp)
-lw t1, 68(sp)
-csrw mcause, t0
-csrw mepc, t1
-lw ra, 0(sp)
-lw t0, 4(sp)
-lw t1, 8(sp)
-lw t2, 12(sp)
-lw a0, 16(sp)
-lw a1, 20(sp)
-lw a2, 24(sp)
-lw a3, 28(sp)
-lw a4, 32(sp)
-lw a5, 36(sp)
-lw a6, 40(sp)
-lw a7, 44(sp)
-lw t3, 48(sp)
-lw t4, 52(sp)
-lw t5, 56(sp)
-lw t6, 60(sp)
-addi sp, sp, (4 * 32)
-
-/* return to previous code before context save */
-mret
-");
+macro_rules! nxti_trap_handler {
+    ($store:literal, $load:literal, $rb:literal) => {
+        global_asm!(
+            ".section .text.nxti_trap_handler",
+            // `mtvec` of the CLIC holds the mode in bits 5..0
+            ".balign 64",
+            ".global _nxti_trap_handler",
+            "_nxti_trap_handler:",
+            // store context
+            concat!("addi sp, sp, -(", $rb, " * 32)"),
+            concat!($store, " ra, 0*", $rb, "(sp)"),
+            concat!($store, " t0, 1*", $rb, "(sp)"),
+            concat!($store, " t1, 2*", $rb, "(sp)"),
+            concat!($store, " t2, 3*", $rb, "(sp)"),
+            concat!($store, " a0, 4*", $rb, "(sp)"),
+            concat!($store, " a1, 5*", $rb, "(sp)"),
+            concat!($store, " a2, 6*", $rb, "(sp)"),
+            concat!($store, " a3, 7*", $rb, "(sp)"),
+            concat!($store, " a4, 8*", $rb, "(sp)"),
+            concat!($store, " a5, 9*", $rb, "(sp)"),
+            concat!($store, " a6, 10*", $rb, "(sp)"),
+            concat!($store, " a7, 11*", $rb, "(sp)"),
+            concat!($store, " t3, 12*", $rb, "(sp)"),
+            concat!($store, " t4, 13*", $rb, "(sp)"),
+            concat!($store, " t5, 14*", $rb, "(sp)"),
+            concat!($store, " t6, 15*", $rb, "(sp)"),
+            "csrr t0, mcause",
+            "csrr t1, mepc",
+            concat!($store, " t0, 16*", $rb, "(sp)"),
+            concat!($store, " t1, 17*", $rb, "(sp)"),
+            // read out the address of the mtvt entry of the next pending interrupt,
+            // enables interrupts, and clears the pending bit of the found interrupt
+            "1:",
+            "csrrsi t0, 0x345, 8",
+            // if no interrupt is pending, the received addr (t0) will be 0
+            "beqz t0, 2f",
+            // an entry that was never written (0) goes to `DefaultHandler` instead of executing
+            // an illegal instruction, e.g. if `mtvt` points to zeroed memory
+            "lw t1, 0(t0)",
+            "bnez t1, 3f",
+            "la t0, DefaultHandler",
+            "3:",
+            // jump to interrupt vector table
+            "jalr t0",
+            // repeat until no more interrupts are pending
+            "j 1b",
+            "2:",
+            "csrci mstatus, 8", // disable global interrupts
+            // load context
+            concat!($load, " t0, 16*", $rb, "(sp)"),
+            concat!($load, " t1, 17*", $rb, "(sp)"),
+            "csrw mcause, t0",
+            "csrw mepc, t1",
+            concat!($load, " ra, 0*", $rb, "(sp)"),
+            concat!($load, " t0, 1*", $rb, "(sp)"),
+            concat!($load, " t1, 2*", $rb, "(sp)"),
+            concat!($load, " t2, 3*", $rb, "(sp)"),
+            concat!($load, " a0, 4*", $rb, "(sp)"),
+            concat!($load, " a1, 5*", $rb, "(sp)"),
+            concat!($load, " a2, 6*", $rb, "(sp)"),
+            concat!($load, " a3, 7*", $rb, "(sp)"),
+            concat!($load, " a4, 8*", $rb, "(sp)"),
+            concat!($load, " a5, 9*", $rb, "(sp)"),
+            concat!($load, " a6, 10*", $rb, "(sp)"),
+            concat!($load, " a7, 11*", $rb, "(sp)"),
+            concat!($load, " t3, 12*", $rb, "(sp)"),
+            concat!($load, " t4, 13*", $rb, "(sp)"),
+            concat!($load, " t5, 14*", $rb, "(sp)"),
+            concat!($load, " t6, 15*", $rb, "(sp)"),
+            concat!("addi sp, sp, (", $rb, " * 32)"),
+            // return to previous code before context save
+            "mret",
+        );
+    };
+}
+
+#[cfg(all(feature = "clic", feature = "nxti", target_arch = "riscv32"))]
+nxti_trap_handler!("sw", "lw", "4");
+#[cfg(all(feature = "clic", feature = "nxti", target_arch = "riscv64"))]
+nxti_trap_handler!("sd", "ld", "8");
+
+/// Number of entries of `interrupt_vector`
+#[cfg(feature = "clic")]
+const CLIC_VECTOR_LEN: usize = 265;
 
 #[cfg(feature = "clic")]
-global_asm!("
+global_asm!(
+    "
 .section .text.interrupt_vector
+.option push
 .option norvc
 .global interrupt_vector
 interrupt_vector:
@@ -999,4 +2628,6 @@ j int_261
 j int_262
 j int_263
 j int_264
-");
+.option pop
+"
+);
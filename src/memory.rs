@@ -0,0 +1,384 @@
+//! Runtime view of the memory layout defined by the linker script
+
+use core::fmt;
+use core::ops::Range;
+use core::ptr::{addr_of, addr_of_mut};
+
+use crate::{_ebss, _edata, _sbss, _sdata};
+
+extern "C" {
+    static _stext: u8;
+    static _sheap: u8;
+    static _heap_size: u8;
+    static _stack_start: u8;
+    static _hart_stack_size: u8;
+    static _max_hart_id: u8;
+    // `_hart_stack_size`, stored in `.rodata` by `link.x`: the compiler assumes that the address
+    // of a symbol is never 0
+    static _hart_stack_size_word: u32;
+}
+
+/// Returns the maximum hart id supported by the image (`_max_hart_id`).
+#[inline]
+pub fn max_hart_id() -> usize {
+    unsafe { &_max_hart_id as *const u8 as usize }
+}
+
+/// Returns the size of the stack of *one* hart (`_hart_stack_size`).
+#[inline]
+pub fn hart_stack_size() -> usize {
+    unsafe { &_hart_stack_size as *const u8 as usize }
+}
+
+/// Returns the total size of the stack memory reserved for all the harts, in bytes.
+///
+/// This adds up the `_max_hart_id + 1` hart stacks below `_stack_start`, and the per-hart stacks
+/// reserved after `.bss` by the `emergency-stack` (also enabled by `stack-overflow-hook`) and
+/// `interrupt-stack` features. Only the hart stacks are placed in `REGION_STACK`.
+pub fn total_stack_required() -> usize {
+    let harts = max_hart_id() + 1;
+    #[allow(unused_mut)]
+    let mut per_hart = hart_stack_size();
+
+    #[cfg(feature = "emergency-stack")]
+    {
+        extern "C" {
+            static _emergency_stack_size: u8;
+        }
+        per_hart += unsafe { &_emergency_stack_size as *const u8 as usize };
+    }
+
+    #[cfg(feature = "interrupt-stack")]
+    {
+        extern "C" {
+            static _interrupt_stack_size: u8;
+        }
+        per_hart += unsafe { &_interrupt_stack_size as *const u8 as usize };
+    }
+
+    harts * per_hart
+}
+
+/// Returns the address range of the stack of the calling hart.
+///
+/// Hart `N` owns the `N`-th slice of `_hart_stack_size` bytes below `_stack_start`, i.e.
+/// `_stack_start - (N + 1) * _hart_stack_size .. _stack_start - N * _hart_stack_size`.
+/// The stack grows downwards from the end of the range.
+#[inline]
+pub fn current_stack_bounds() -> Range<usize> {
+    let stack_start = unsafe { &_stack_start as *const u8 as usize };
+    let size = hart_stack_size();
+    let top = stack_start - crate::hart_id() * size;
+    top - size..top
+}
+
+/// Returns the private data block of the calling hart, interpreted as a `T`.
+///
+/// `_max_hart_id + 1` blocks of `_hart_local_size` bytes are reserved in `.bss`, 16-byte aligned,
+/// and hart `N` owns the `N`-th of them. The blocks are zeroed together with `.bss`, so `T` must be
+/// valid when all-zero (integers, atomics, arrays of them, ...) and must not need `Drop`. Every
+/// call on the same hart returns the same block, regardless of `T`.
+///
+/// # Safety
+///
+/// * No other reference to the block of the calling hart may be alive, including references held
+///   by interrupt handlers that may preempt the caller.
+/// * All-zero bytes, and whatever a previous user of the block left in it, must be a valid `T`.
+///
+/// # Panics
+///
+/// Panics if `T` is larger than `_hart_local_size` or more than 16-byte aligned.
+#[inline]
+pub unsafe fn hart_local<T>() -> &'static mut T {
+    extern "C" {
+        static mut _shart_local: u8;
+        // `_hart_local_size`, stored in `.rodata` by `link.x`: the compiler assumes that the
+        // address of a symbol is never 0, and would drop the check of the default size
+        static _hart_local_size_word: u32;
+    }
+
+    let size = _hart_local_size_word as usize;
+    assert!(core::mem::size_of::<T>() <= size && core::mem::align_of::<T>() <= 16);
+    let block = addr_of_mut!(_shart_local).add(crate::hart_id() * size);
+    &mut *(block as *mut T)
+}
+
+/// Snapshot of the key addresses of the memory layout
+///
+/// All values are read from the symbols provided by `link.x` and `memory.x`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryMap {
+    /// Loading address of the `.text` section (`_stext`)
+    pub stext: usize,
+    /// Start of the `.data` section (`_sdata`)
+    pub sdata: usize,
+    /// End of the `.data` section (`_edata`)
+    pub edata: usize,
+    /// Start of the `.bss` section (`_sbss`)
+    pub sbss: usize,
+    /// End of the `.bss` section (`_ebss`)
+    pub ebss: usize,
+    /// Start of the heap (`_sheap`)
+    pub sheap: usize,
+    /// Size of the heap (`_heap_size`)
+    pub heap_size: usize,
+    /// Initial stack pointer of hart 0 (`_stack_start`)
+    pub stack_start: usize,
+}
+
+/// Returns the memory layout the image has been linked with.
+///
+/// This is intended for diagnostics, e.g. printing the whole layout with a single log line:
+///
+/// ``` no_run
+/// let map = riscv_rt::memory_map();
+/// // println!("{}", map);
+/// ```
+#[inline]
+pub fn memory_map() -> MemoryMap {
+    unsafe {
+        MemoryMap {
+            stext: &_stext as *const u8 as usize,
+            sdata: addr_of!(_sdata) as usize,
+            edata: addr_of!(_edata) as usize,
+            sbss: addr_of!(_sbss) as usize,
+            ebss: addr_of!(_ebss) as usize,
+            sheap: &_sheap as *const u8 as usize,
+            heap_size: &_heap_size as *const u8 as usize,
+            stack_start: &_stack_start as *const u8 as usize,
+        }
+    }
+}
+
+impl fmt::Display for MemoryMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "text: {:#x} | data: {:#x}..{:#x} | bss: {:#x}..{:#x} | heap: {:#x} (+{:#x}) | stack: {:#x}",
+            self.stext,
+            self.sdata,
+            self.edata,
+            self.sbss,
+            self.ebss,
+            self.sheap,
+            self.heap_size,
+            self.stack_start,
+        )
+    }
+}
+
+/// Memory layout problem found by [`validate_layout`]
+///
+/// Ranges are given as `start`/`end` addresses, `end` being exclusive. A range that wraps around
+/// the address space has an `end` below its `start`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayoutError {
+    /// `_stack_start` is not 16-byte aligned
+    StackStartUnaligned(usize),
+    /// `_hart_stack_size` is zero or not a multiple of 16
+    HartStackSizeUnaligned(usize),
+    /// `_sdata` is above `_edata`
+    DataInverted {
+        /// Start address
+        start: usize,
+        /// End address
+        end: usize,
+    },
+    /// `_sbss` is above `_ebss`
+    BssInverted {
+        /// Start address
+        start: usize,
+        /// End address
+        end: usize,
+    },
+    /// The heap (`_sheap` plus `_heap_size`) wraps around or overlaps `.data` or `.bss`
+    HeapOverlap {
+        /// Start address
+        start: usize,
+        /// End address
+        end: usize,
+    },
+    /// The stacks of all the harts, below `_stack_start`, wrap around or overlap `.data`, `.bss`
+    /// or the heap
+    StackOverlap {
+        /// Start address
+        start: usize,
+        /// End address
+        end: usize,
+    },
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            LayoutError::StackStartUnaligned(addr) => {
+                write!(f, "_stack_start {:#x} is not 16-byte aligned", addr)
+            }
+            LayoutError::HartStackSizeUnaligned(size) => {
+                write!(
+                    f,
+                    "_hart_stack_size {:#x} is not a non-zero multiple of 16",
+                    size
+                )
+            }
+            LayoutError::DataInverted { start, end } => {
+                write!(f, "_sdata {:#x} is above _edata {:#x}", start, end)
+            }
+            LayoutError::BssInverted { start, end } => {
+                write!(f, "_sbss {:#x} is above _ebss {:#x}", start, end)
+            }
+            LayoutError::HeapOverlap { start, end } => {
+                write!(f, "heap {:#x}..{:#x} overlaps .data or .bss", start, end)
+            }
+            LayoutError::StackOverlap { start, end } => {
+                write!(
+                    f,
+                    "stacks {:#x}..{:#x} overlap .data, .bss or the heap",
+                    start, end
+                )
+            }
+        }
+    }
+}
+
+/// Checks the layout symbols for consistency at run time.
+///
+/// `link.x` already checks the layout at link time, but these checks can be defeated by a
+/// linker script that overrides `link.x` or by symbols redefined in `memory.x`. This function
+/// only reads symbol addresses and `.rodata`, so it can be called from `#[pre_init]`, before
+/// `.data` and `.bss` are initialized:
+///
+/// ``` ignore
+/// #[riscv_rt::pre_init]
+/// unsafe fn check_layout() {
+///     if riscv_rt::validate_layout().is_err() {
+///         loop {}
+///     }
+/// }
+/// ```
+///
+/// The first problem found is returned.
+pub fn validate_layout() -> Result<(), LayoutError> {
+    let map = memory_map();
+    let hart_stack_size = unsafe { _hart_stack_size_word } as usize;
+
+    if map.stack_start % 16 != 0 {
+        return Err(LayoutError::StackStartUnaligned(map.stack_start));
+    }
+    if hart_stack_size == 0 || hart_stack_size % 16 != 0 {
+        return Err(LayoutError::HartStackSizeUnaligned(hart_stack_size));
+    }
+    if map.sdata > map.edata {
+        return Err(LayoutError::DataInverted {
+            start: map.sdata,
+            end: map.edata,
+        });
+    }
+    if map.sbss > map.ebss {
+        return Err(LayoutError::BssInverted {
+            start: map.sbss,
+            end: map.ebss,
+        });
+    }
+
+    let data = map.sdata..map.edata;
+    let bss = map.sbss..map.ebss;
+    let heap = map.sheap..map.sheap.wrapping_add(map.heap_size);
+    if heap.end < heap.start || overlaps(&heap, &data) || overlaps(&heap, &bss) {
+        return Err(LayoutError::HeapOverlap {
+            start: heap.start,
+            end: heap.end,
+        });
+    }
+    let stacks_size = (max_hart_id() + 1).wrapping_mul(hart_stack_size);
+    let stacks = map.stack_start.wrapping_sub(stacks_size)..map.stack_start;
+    if stacks.end < stacks.start
+        || overlaps(&stacks, &data)
+        || overlaps(&stacks, &bss)
+        || overlaps(&stacks, &heap)
+    {
+        return Err(LayoutError::StackOverlap {
+            start: stacks.start,
+            end: stacks.end,
+        });
+    }
+    Ok(())
+}
+
+/// Returns whether two non-empty ranges have an address in common.
+fn overlaps(a: &Range<usize>, b: &Range<usize>) -> bool {
+    !a.is_empty() && !b.is_empty() && a.start < b.end && b.start < a.end
+}
+
+/// Sizes of the output sections of the image, in bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sizes {
+    /// Size of `.text` (`_text_size`)
+    pub text: usize,
+    /// Size of `.rodata` (`_rodata_size`)
+    pub rodata: usize,
+    /// Size of `.data` (`_data_size`), stored once in flash and once in RAM
+    pub data: usize,
+    /// Size of `.bss` (`_bss_size`)
+    pub bss: usize,
+}
+
+impl Sizes {
+    /// Returns the size of the image in flash, i.e. `.text`, `.rodata` and the initial values of
+    /// `.data`, without the alignment padding between them.
+    #[inline]
+    pub fn flash(&self) -> usize {
+        self.text + self.rodata + self.data
+    }
+
+    /// Returns the size of the statically allocated RAM, i.e. `.data` and `.bss`.
+    #[inline]
+    pub fn ram(&self) -> usize {
+        self.data + self.bss
+    }
+}
+
+/// Returns the sizes of the output sections, e.g. for logging the flash and RAM usage at boot.
+#[inline]
+pub fn sizes() -> Sizes {
+    extern "C" {
+        static _text_size: u8;
+        static _rodata_size: u8;
+        static _data_size: u8;
+        static _bss_size: u8;
+    }
+
+    unsafe {
+        Sizes {
+            text: &_text_size as *const u8 as usize,
+            rodata: &_rodata_size as *const u8 as usize,
+            data: &_data_size as *const u8 as usize,
+            bss: &_bss_size as *const u8 as usize,
+        }
+    }
+}
+
+/// Returns the contents of the `.payload` section, located in `REGION_PAYLOAD`.
+///
+/// The payload is placed with `#[link_section = ".payload"]`, e.g. by including a
+/// separately linked binary:
+///
+/// ``` ignore
+/// #[link_section = ".payload"]
+/// #[used]
+/// static APP: [u8; 4] = *include_bytes!("app.bin");
+/// ```
+#[cfg(feature = "payload")]
+#[inline]
+pub fn payload() -> &'static [u8] {
+    extern "C" {
+        static _spayload: u8;
+        static _epayload: u8;
+    }
+
+    unsafe {
+        let start = &_spayload as *const u8;
+        let end = &_epayload as *const u8;
+        core::slice::from_raw_parts(start, end as usize - start as usize)
+    }
+}
@@ -0,0 +1,118 @@
+//! Volatile access to memory-mapped registers
+//!
+//! [`MemoryMapper`] is the building block of the peripheral helpers of this crate
+//! (CLIC, CLINT, ...) and can be reused by downstream drivers.
+//!
+//! ``` no_run
+//! use riscv_rt::mmio::MemoryMapper;
+//!
+//! let uart = MemoryMapper::new(0x1000_0000);
+//! unsafe {
+//!     uart.write::<u8>(0x0, b'A');
+//!     uart.set_bits::<u8>(0x3, 0x80);
+//! }
+//! ```
+
+use core::ops::{BitAnd, BitOr, Not};
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for u8 {}
+    impl Sealed for u16 {}
+    impl Sealed for u32 {}
+    impl Sealed for u64 {}
+}
+
+/// Register widths supported by [`MemoryMapper`]: `u8`, `u16`, `u32` and `u64`.
+///
+/// On RV32 targets, `u64` accesses are split into two 32-bit accesses and are therefore
+/// *not* atomic.
+pub trait Width:
+    sealed::Sealed + Copy + BitOr<Output = Self> + BitAnd<Output = Self> + Not<Output = Self>
+{
+}
+
+impl Width for u8 {}
+impl Width for u16 {}
+impl Width for u32 {}
+impl Width for u64 {}
+
+/// Volatile accessor for the registers of a memory-mapped peripheral
+///
+/// All accesses are relative to the base address given on construction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryMapper {
+    base: usize,
+}
+
+impl MemoryMapper {
+    /// Creates an accessor for the peripheral located at `base`.
+    #[inline]
+    pub const fn new(base: usize) -> Self {
+        Self { base }
+    }
+
+    /// Returns the base address of the peripheral.
+    #[inline]
+    pub const fn base(&self) -> usize {
+        self.base
+    }
+
+    /// Returns the address of the register at `offset`.
+    #[inline]
+    pub const fn addr(&self, offset: usize) -> usize {
+        self.base + offset
+    }
+
+    /// Reads the register at `offset`.
+    ///
+    /// # Safety
+    ///
+    /// `base + offset` must be a valid, suitably aligned register of width `T`.
+    #[inline]
+    pub unsafe fn read<T: Width>(&self, offset: usize) -> T {
+        (self.addr(offset) as *const T).read_volatile()
+    }
+
+    /// Writes `value` to the register at `offset`.
+    ///
+    /// # Safety
+    ///
+    /// `base + offset` must be a valid, suitably aligned register of width `T`.
+    #[inline]
+    pub unsafe fn write<T: Width>(&self, offset: usize, value: T) {
+        (self.addr(offset) as *mut T).write_volatile(value)
+    }
+
+    /// Reads the register at `offset`, applies `f` and writes the result back.
+    ///
+    /// The read-modify-write sequence is not atomic.
+    ///
+    /// # Safety
+    ///
+    /// `base + offset` must be a valid, suitably aligned register of width `T`.
+    #[inline]
+    pub unsafe fn modify<T: Width, F: FnOnce(T) -> T>(&self, offset: usize, f: F) {
+        self.write(offset, f(self.read(offset)))
+    }
+
+    /// Sets the bits of `mask` in the register at `offset`.
+    ///
+    /// # Safety
+    ///
+    /// See [`MemoryMapper::modify`].
+    #[inline]
+    pub unsafe fn set_bits<T: Width>(&self, offset: usize, mask: T) {
+        self.modify(offset, |r: T| r | mask)
+    }
+
+    /// Clears the bits of `mask` in the register at `offset`.
+    ///
+    /// # Safety
+    ///
+    /// See [`MemoryMapper::modify`].
+    #[inline]
+    pub unsafe fn clear_bits<T: Width>(&self, offset: usize, mask: T) {
+        self.modify(offset, |r: T| r & !mask)
+    }
+}
@@ -0,0 +1,102 @@
+//! Pending interrupt helpers for the standard (CLINT) interrupt model
+
+use core::arch::asm;
+
+use crate::{Interrupt, __INTERRUPTS};
+
+/// Sets the pending bit of `interrupt`, so that its handler runs through the normal dispatch
+/// path once the interrupt is enabled. This allows testing handlers without the actual source.
+///
+/// Only the following interrupts can be triggered by software:
+///
+/// * `MachineSoft`: written to the `msip` register of the calling hart in the CLINT
+///   (located at `_clint_base`, machine mode only)
+/// * `SupervisorSoft`: the `SSIP` bit of `mip`/`sip`
+/// * `SupervisorTimer` and `SupervisorExternal`: the `STIP`/`SEIP` bits of `mip`
+///   (machine mode only)
+///
+/// For any other interrupt, nothing is written and the interrupt is returned as error.
+pub fn trigger_interrupt(interrupt: Interrupt) -> Result<(), Interrupt> {
+    match interrupt {
+        #[cfg(not(feature = "s-mode"))]
+        Interrupt::MachineSoft => unsafe {
+            let msip = crate::clint::base() + crate::clint::addr::msip(crate::mhartid::read());
+            (msip as *mut u32).write_volatile(1);
+        },
+        #[cfg(not(feature = "s-mode"))]
+        Interrupt::SupervisorSoft => unsafe { asm!("csrs mip, {0}", in(reg) 1 << 1) },
+        #[cfg(not(feature = "s-mode"))]
+        Interrupt::SupervisorTimer => unsafe { asm!("csrs mip, {0}", in(reg) 1 << 5) },
+        #[cfg(not(feature = "s-mode"))]
+        Interrupt::SupervisorExternal => unsafe { asm!("csrs mip, {0}", in(reg) 1 << 9) },
+        #[cfg(feature = "s-mode")]
+        Interrupt::SupervisorSoft => unsafe { asm!("csrs sip, {0}", in(reg) 1 << 1) },
+        _ => return Err(interrupt),
+    }
+    Ok(())
+}
+
+/// Calls the handlers of the pending and enabled core interrupts, without taking a trap.
+///
+/// This is meant for systems that poll for interrupts from their main loop instead of enabling
+/// them globally (`mstatus.MIE`/`sstatus.SIE`), while keeping the handler definitions. For every
+/// bit set in both `mip` and `mie` (`sip` and `sie` with the `s-mode` feature), in increasing
+/// order of cause, the handler of the interrupt is called as a normal function, or
+/// `DefaultHandler` for the reserved slots. Returns the number of handlers called.
+///
+/// The pending bits that software can clear are cleared before the handler is called: the
+/// `msip` register of the calling hart in the CLINT for `MachineSoft`, and the `SSIP`, `STIP`
+/// and `SEIP` bits (only `SSIP` in supervisor mode). The other sources must be cleared by their
+/// handler, e.g. by writing `mtimecmp` or claiming the interrupt from the PLIC.
+///
+/// The handlers must be poll-safe: plain functions that do not execute `mret`/`sret` nor assume
+/// a trap context (`mepc`, `mcause`, saved registers).
+pub fn poll_interrupts() -> usize {
+    extern "C" {
+        fn DefaultHandler();
+    }
+
+    let pending: usize;
+    let enabled: usize;
+    #[cfg(not(feature = "s-mode"))]
+    unsafe {
+        asm!("csrr {0}, mip", "csrr {1}, mie", out(reg) pending, out(reg) enabled)
+    };
+    #[cfg(feature = "s-mode")]
+    unsafe {
+        asm!("csrr {0}, sip", "csrr {1}, sie", out(reg) pending, out(reg) enabled)
+    };
+
+    let mut count = 0;
+    for (code, vector) in __INTERRUPTS.iter().enumerate() {
+        if pending & enabled & (1 << code) == 0 {
+            continue;
+        }
+        unsafe {
+            clear_pending(code);
+            if vector.reserved == 0 {
+                DefaultHandler();
+            } else {
+                (vector.handler)();
+            }
+        }
+        count += 1;
+    }
+    count
+}
+
+/// Clears the pending bit of interrupt `code` if software can clear it.
+unsafe fn clear_pending(code: usize) {
+    match code {
+        #[cfg(not(feature = "s-mode"))]
+        3 => {
+            let msip = crate::clint::base() + crate::clint::addr::msip(crate::mhartid::read());
+            (msip as *mut u32).write_volatile(0);
+        }
+        #[cfg(not(feature = "s-mode"))]
+        1 | 5 | 9 => asm!("csrc mip, {0}", in(reg) 1 << code),
+        #[cfg(feature = "s-mode")]
+        1 => asm!("csrc sip, {0}", in(reg) 1 << code),
+        _ => {}
+    }
+}
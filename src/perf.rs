@@ -0,0 +1,151 @@
+//! Hardware performance counters
+//!
+//! On RV32, the 64-bit counters are composed of two CSRs, which are re-read until the high
+//! word is stable, so that a rollover of the low word between the reads cannot produce a
+//! wrong value.
+//!
+//! Any counter may be hardwired to zero: `mcycle` and `minstret` are mandatory, but the
+//! `mhpmcounter3..31` are optional and count nothing unless an event is selected with
+//! [`configure_event`]. The available events are implementation specific.
+//!
+//! With the `s-mode` feature, the unprivileged shadows (`cycle`, `instret`, `hpmcounterN`)
+//! are read instead. They are only accessible if the corresponding bits of `mcounteren` have
+//! been set by the machine mode firmware; otherwise, reading them raises an illegal
+//! instruction exception.
+
+use core::arch::asm;
+
+macro_rules! read_csr {
+    ($csr:literal) => {{
+        let bits: usize;
+        unsafe { asm!(concat!("csrr {0}, ", $csr), out(reg) bits) };
+        bits
+    }};
+}
+
+#[cfg(not(feature = "s-mode"))]
+macro_rules! write_csr {
+    ($csr:literal, $value:expr) => {
+        unsafe { asm!(concat!("csrw ", $csr, ", {0}"), in(reg) $value) }
+    };
+}
+
+/// Reads a 64-bit counter given the machine mode (`$m`, `$mh`) and unprivileged (`$u`, `$uh`) CSRs.
+macro_rules! read64 {
+    ($m:literal, $mh:literal, $u:literal, $uh:literal) => {{
+        #[cfg(not(feature = "s-mode"))]
+        let value = read64!(@ $m, $mh);
+        #[cfg(feature = "s-mode")]
+        let value = read64!(@ $u, $uh);
+        value
+    }};
+    (@ $lo:literal, $hi:literal) => {{
+        #[cfg(target_pointer_width = "32")]
+        let value = loop {
+            let hi = read_csr!($hi);
+            let lo = read_csr!($lo);
+            if hi == read_csr!($hi) {
+                break ((hi as u64) << 32) | lo as u64;
+            }
+        };
+        #[cfg(not(target_pointer_width = "32"))]
+        let value = read_csr!($lo) as u64;
+        value
+    }};
+}
+
+/// Returns the number of clock cycles executed by the hart (`mcycle`).
+#[inline]
+pub fn cycle() -> u64 {
+    read64!("0xB00", "0xB80", "0xC00", "0xC80")
+}
+
+/// Returns the number of instructions retired by the hart (`minstret`).
+#[inline]
+pub fn instret() -> u64 {
+    read64!("0xB02", "0xB82", "0xC02", "0xC82")
+}
+
+/// Returns the value of the hardware performance counter `n` (`mhpmcounterN`).
+///
+/// # Panics
+///
+/// Panics if `n` is not in `3..=31`.
+pub fn hpm_counter(n: usize) -> u64 {
+    match n {
+        3 => read64!("0xB03", "0xB83", "0xC03", "0xC83"),
+        4 => read64!("0xB04", "0xB84", "0xC04", "0xC84"),
+        5 => read64!("0xB05", "0xB85", "0xC05", "0xC85"),
+        6 => read64!("0xB06", "0xB86", "0xC06", "0xC86"),
+        7 => read64!("0xB07", "0xB87", "0xC07", "0xC87"),
+        8 => read64!("0xB08", "0xB88", "0xC08", "0xC88"),
+        9 => read64!("0xB09", "0xB89", "0xC09", "0xC89"),
+        10 => read64!("0xB0A", "0xB8A", "0xC0A", "0xC8A"),
+        11 => read64!("0xB0B", "0xB8B", "0xC0B", "0xC8B"),
+        12 => read64!("0xB0C", "0xB8C", "0xC0C", "0xC8C"),
+        13 => read64!("0xB0D", "0xB8D", "0xC0D", "0xC8D"),
+        14 => read64!("0xB0E", "0xB8E", "0xC0E", "0xC8E"),
+        15 => read64!("0xB0F", "0xB8F", "0xC0F", "0xC8F"),
+        16 => read64!("0xB10", "0xB90", "0xC10", "0xC90"),
+        17 => read64!("0xB11", "0xB91", "0xC11", "0xC91"),
+        18 => read64!("0xB12", "0xB92", "0xC12", "0xC92"),
+        19 => read64!("0xB13", "0xB93", "0xC13", "0xC93"),
+        20 => read64!("0xB14", "0xB94", "0xC14", "0xC94"),
+        21 => read64!("0xB15", "0xB95", "0xC15", "0xC95"),
+        22 => read64!("0xB16", "0xB96", "0xC16", "0xC96"),
+        23 => read64!("0xB17", "0xB97", "0xC17", "0xC97"),
+        24 => read64!("0xB18", "0xB98", "0xC18", "0xC98"),
+        25 => read64!("0xB19", "0xB99", "0xC19", "0xC99"),
+        26 => read64!("0xB1A", "0xB9A", "0xC1A", "0xC9A"),
+        27 => read64!("0xB1B", "0xB9B", "0xC1B", "0xC9B"),
+        28 => read64!("0xB1C", "0xB9C", "0xC1C", "0xC9C"),
+        29 => read64!("0xB1D", "0xB9D", "0xC1D", "0xC9D"),
+        30 => read64!("0xB1E", "0xB9E", "0xC1E", "0xC9E"),
+        31 => read64!("0xB1F", "0xB9F", "0xC1F", "0xC9F"),
+        _ => panic!("invalid hardware performance counter"),
+    }
+}
+
+/// Selects the event counted by the hardware performance counter `n` (`mhpmeventN`).
+///
+/// Event ids are implementation specific, 0 means that no event is counted.
+/// Not available with the `s-mode` feature, as `mhpmevent` is only accessible in machine mode.
+///
+/// # Panics
+///
+/// Panics if `n` is not in `3..=31`.
+#[cfg(not(feature = "s-mode"))]
+pub fn configure_event(n: usize, event: usize) {
+    match n {
+        3 => write_csr!("0x323", event),
+        4 => write_csr!("0x324", event),
+        5 => write_csr!("0x325", event),
+        6 => write_csr!("0x326", event),
+        7 => write_csr!("0x327", event),
+        8 => write_csr!("0x328", event),
+        9 => write_csr!("0x329", event),
+        10 => write_csr!("0x32A", event),
+        11 => write_csr!("0x32B", event),
+        12 => write_csr!("0x32C", event),
+        13 => write_csr!("0x32D", event),
+        14 => write_csr!("0x32E", event),
+        15 => write_csr!("0x32F", event),
+        16 => write_csr!("0x330", event),
+        17 => write_csr!("0x331", event),
+        18 => write_csr!("0x332", event),
+        19 => write_csr!("0x333", event),
+        20 => write_csr!("0x334", event),
+        21 => write_csr!("0x335", event),
+        22 => write_csr!("0x336", event),
+        23 => write_csr!("0x337", event),
+        24 => write_csr!("0x338", event),
+        25 => write_csr!("0x339", event),
+        26 => write_csr!("0x33A", event),
+        27 => write_csr!("0x33B", event),
+        28 => write_csr!("0x33C", event),
+        29 => write_csr!("0x33D", event),
+        30 => write_csr!("0x33E", event),
+        31 => write_csr!("0x33F", event),
+        _ => panic!("invalid hardware performance counter"),
+    }
+}
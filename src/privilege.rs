@@ -0,0 +1,28 @@
+//! Privilege level of the image
+
+/// RISC-V privilege level, with the encoding of `mstatus.MPP`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Privilege {
+    /// User mode (U)
+    User = 0,
+    /// Supervisor mode (S)
+    Supervisor = 1,
+    /// Machine mode (M)
+    Machine = 3,
+}
+
+/// Returns the privilege level the image runs at: [`Privilege::Supervisor`] with the `s-mode`
+/// feature, [`Privilege::Machine`] otherwise.
+///
+/// The level is known at compile time, so this is meant for code shared between machine and
+/// supervisor mode builds that does not want to check the feature itself. The level cannot be
+/// wrong at run time: `_start` first writes a CSR of the selected level (`mie` in machine mode,
+/// `sie` in supervisor mode), which traps if the image has been started at a lower level.
+#[inline]
+pub const fn current_privilege() -> Privilege {
+    if cfg!(feature = "s-mode") {
+        Privilege::Supervisor
+    } else {
+        Privilege::Machine
+    }
+}
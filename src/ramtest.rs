@@ -0,0 +1,91 @@
+//! March C- test of the RAM, see the `ram-test` feature
+//!
+//! The test runs on the stack of the initializing hart, which lies outside of the tested
+//! sections, and accesses the memory by 32-bit words with volatile accesses, like the
+//! initialization of the sections (their bounds are only 4-byte aligned). The sequence of march
+//! elements, with `0` the background pattern `_ram_test_background` and `1` its complement:
+//!
+//! ``` text
+//! ⇕(w0) ⇑(r0, w1) ⇑(r1, w0) ⇓(r0, w1) ⇓(r1, w0) ⇕(r0)
+//! ```
+
+use core::ops::Range;
+use core::ptr::{addr_of_mut, read_volatile, write_volatile};
+
+use crate::{_ebss, _edata, _sbss, _sdata, _watchdog_feed};
+
+extern "C" {
+    static _ram_test_background: u8;
+}
+
+extern "Rust" {
+    fn _ram_test_failed(addr: usize);
+}
+
+/// Number of words between two calls of `_watchdog_feed`
+const FEED_INTERVAL: usize = 1024;
+
+/// Tests `.data` and `.bss`, calling `_ram_test_failed` with the address of the first faulty
+/// word. `.uninit` is not tested, as its contents must survive a reset.
+///
+/// The contents of the tested sections are destroyed, they must be initialized afterwards.
+pub(crate) unsafe fn run() {
+    let background = &_ram_test_background as *const u8 as usize as u32;
+    let sections = [
+        addr_of_mut!(_sdata) as *mut u32..addr_of_mut!(_edata) as *mut u32,
+        addr_of_mut!(_sbss) as *mut u32..addr_of_mut!(_ebss) as *mut u32,
+    ];
+    for section in sections.iter() {
+        if let Err(addr) = march(section.clone(), background) {
+            _ram_test_failed(addr);
+            return;
+        }
+    }
+}
+
+/// Runs March C- over the words of `range`, returning the address of the first word that does
+/// not read back the expected value.
+unsafe fn march(range: Range<*mut u32>, zero: u32) -> Result<(), usize> {
+    let one = !zero;
+    let len = (range.end as usize - range.start as usize) / core::mem::size_of::<u32>();
+    let word = |i: usize| range.start.add(i);
+
+    for i in 0..len {
+        feed(i);
+        write_volatile(word(i), zero);
+    }
+    for &(expected, value) in [(zero, one), (one, zero)].iter() {
+        for i in 0..len {
+            feed(i);
+            read_write(word(i), expected, value)?;
+        }
+    }
+    for &(expected, value) in [(zero, one), (one, zero)].iter() {
+        for i in (0..len).rev() {
+            feed(i);
+            read_write(word(i), expected, value)?;
+        }
+    }
+    for i in 0..len {
+        feed(i);
+        read_write(word(i), zero, zero)?;
+    }
+    Ok(())
+}
+
+/// Checks that `word` holds `expected` and writes `value` to it.
+#[inline(always)]
+unsafe fn read_write(word: *mut u32, expected: u32, value: u32) -> Result<(), usize> {
+    if read_volatile(word) != expected {
+        return Err(word as usize);
+    }
+    write_volatile(word, value);
+    Ok(())
+}
+
+#[inline(always)]
+unsafe fn feed(i: usize) {
+    if i % FEED_INTERVAL == 0 {
+        _watchdog_feed();
+    }
+}
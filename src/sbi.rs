@@ -0,0 +1,175 @@
+//! Supervisor Binary Interface (SBI) calls
+//!
+//! Only available with the `s-mode` feature, where the runtime is booted by an SBI
+//! implementation (e.g. OpenSBI).
+
+use core::arch::asm;
+
+/// Legacy console putchar extension
+const EID_CONSOLE_PUTCHAR: usize = 0x01;
+/// Legacy shutdown extension
+const EID_SHUTDOWN: usize = 0x08;
+/// System reset extension
+const EID_SRST: usize = 0x5352_5354;
+/// Timer extension
+const EID_TIME: usize = 0x5449_4d45;
+/// IPI extension
+const EID_IPI: usize = 0x0073_5049;
+/// RFENCE extension
+const EID_RFENCE: usize = 0x5246_4e43;
+
+/// Value returned by an SBI call
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SbiRet {
+    /// Error code, 0 on success
+    pub error: isize,
+    /// Return value
+    pub value: usize,
+}
+
+impl SbiRet {
+    /// Converts the returned error code into a `Result`.
+    #[inline]
+    pub fn into_result(self) -> Result<usize, isize> {
+        match self.error {
+            0 => Ok(self.value),
+            e => Err(e),
+        }
+    }
+}
+
+/// Performs an SBI call of function `fid` of extension `eid` with the arguments `args`.
+///
+/// # Safety
+///
+/// The effects of the call depend on the extension and function, the caller is responsible
+/// for their consequences.
+#[inline]
+pub unsafe fn ecall(eid: usize, fid: usize, args: [usize; 6]) -> SbiRet {
+    let error: isize;
+    let value: usize;
+    asm!(
+        "ecall",
+        inlateout("a0") args[0] => error,
+        inlateout("a1") args[1] => value,
+        in("a2") args[2],
+        in("a3") args[3],
+        in("a4") args[4],
+        in("a5") args[5],
+        in("a6") fid,
+        in("a7") eid,
+    );
+    SbiRet { error, value }
+}
+
+/// Writes `c` to the debug console (legacy `console_putchar` extension).
+#[inline]
+pub fn console_putchar(c: u8) {
+    unsafe { ecall(EID_CONSOLE_PUTCHAR, 0, [c as usize, 0, 0, 0, 0, 0]) };
+}
+
+/// Shuts the system down.
+///
+/// The system reset extension is used if available, with `failure` selecting the
+/// system failure reset reason. Otherwise, the legacy shutdown extension is used.
+pub fn shutdown(failure: bool) -> ! {
+    unsafe {
+        ecall(EID_SRST, 0, [0, failure as usize, 0, 0, 0, 0]);
+        ecall(EID_SHUTDOWN, 0, [0; 6]);
+    }
+    loop {
+        // Prevent this from turning into a UDF instruction
+        // see rust-lang/rust#28728 for details
+        continue;
+    }
+}
+
+/// Programs the clock for the next timer event at absolute time `stime_value` (TIME extension).
+///
+/// The pending supervisor timer interrupt is cleared until the event fires. To only clear it,
+/// pass `u64::MAX`.
+#[inline]
+pub fn set_timer(stime_value: u64) -> SbiRet {
+    // on RV32, the 64-bit value is passed in a0 (low) and a1 (high)
+    #[cfg(target_pointer_width = "32")]
+    let high = (stime_value >> 32) as usize;
+    #[cfg(target_pointer_width = "64")]
+    let high = 0;
+    unsafe { ecall(EID_TIME, 0, [stime_value as usize, high, 0, 0, 0, 0]) }
+}
+
+/// Sends a supervisor software interrupt to the harts of `hart_mask` (IPI extension).
+///
+/// Bit `i` of `hart_mask` selects hart `hart_mask_base + i`. A `hart_mask_base` of `usize::MAX`
+/// selects all the harts, `hart_mask` is then ignored.
+#[inline]
+pub fn send_ipi(hart_mask: usize, hart_mask_base: usize) -> SbiRet {
+    unsafe { ecall(EID_IPI, 0, [hart_mask, hart_mask_base, 0, 0, 0, 0]) }
+}
+
+/// Executes a `fence.i` on the harts of `hart_mask` (RFENCE extension).
+///
+/// The harts are selected as with [`send_ipi`].
+#[inline]
+pub fn remote_fence_i(hart_mask: usize, hart_mask_base: usize) -> SbiRet {
+    unsafe { ecall(EID_RFENCE, 0, [hart_mask, hart_mask_base, 0, 0, 0, 0]) }
+}
+
+/// Executes an `sfence.vma` covering the virtual addresses `start..start + size` on the harts of
+/// `hart_mask` (RFENCE extension).
+///
+/// The harts are selected as with [`send_ipi`]. A `start` and `size` of 0, or a `size` of
+/// `usize::MAX`, flush the whole address space.
+#[inline]
+pub fn remote_sfence_vma(
+    hart_mask: usize,
+    hart_mask_base: usize,
+    start: usize,
+    size: usize,
+) -> SbiRet {
+    unsafe {
+        ecall(
+            EID_RFENCE,
+            1,
+            [hart_mask, hart_mask_base, start, size, 0, 0],
+        )
+    }
+}
+
+/// Same as [`remote_sfence_vma`], only for the address space `asid`.
+#[inline]
+pub fn remote_sfence_vma_asid(
+    hart_mask: usize,
+    hart_mask_base: usize,
+    start: usize,
+    size: usize,
+    asid: usize,
+) -> SbiRet {
+    unsafe {
+        ecall(
+            EID_RFENCE,
+            2,
+            [hart_mask, hart_mask_base, start, size, asid, 0],
+        )
+    }
+}
+
+/// `core::fmt::Write` adapter over [`console_putchar`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Console;
+
+impl core::fmt::Write for Console {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        s.bytes().for_each(console_putchar);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sbi-panic")]
+#[panic_handler]
+fn panic(info: &core::panic::PanicInfo) -> ! {
+    use core::fmt::Write;
+
+    let _ = writeln!(Console, "{}", info);
+    shutdown(true)
+}
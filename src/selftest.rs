@@ -0,0 +1,64 @@
+//! Boot self-test of the trap path, see the `boot-selftest` feature
+
+use core::arch::asm;
+use core::ptr::{addr_of, addr_of_mut};
+
+use crate::{xcause, TrapFrame};
+
+/// Values placed in `a0`-`a2` before the `ebreak`, checked in the trap frame
+const PATTERN: [usize; 3] = [0x5e1f_7e57, 0x0123_4567, 0x7654_3210];
+
+/// Self-test states
+const IDLE: usize = 0;
+const ARMED: usize = 1;
+const PASSED: usize = 2;
+
+/// State of the self-test, only written by the hart running it
+static mut STATE: usize = IDLE;
+
+extern "Rust" {
+    fn _selftest_failed();
+}
+
+/// Triggers a breakpoint exception and checks that it went through `_start_trap_rust` with the
+/// expected cause and trap frame, and that the trap path restored the registers.
+///
+/// Calls `_selftest_failed` if one of the checks fails.
+pub(crate) unsafe fn run() {
+    core::ptr::write_volatile(addr_of_mut!(STATE), ARMED);
+
+    let (mut a0, mut a1, mut a2) = (PATTERN[0], PATTERN[1], PATTERN[2]);
+    asm!("ebreak", inout("a0") a0, inout("a1") a1, inout("a2") a2);
+
+    let passed = core::ptr::read_volatile(addr_of!(STATE)) == PASSED;
+    core::ptr::write_volatile(addr_of_mut!(STATE), IDLE);
+    if !passed || [a0, a1, a2] != PATTERN {
+        _selftest_failed();
+    }
+}
+
+/// Handles the breakpoint of [`run`], called by `_start_trap_rust` before the dispatch.
+///
+/// Returns `false`, so that the trap is dispatched as usual, unless a self-test is running and
+/// the trap is its breakpoint: any other trap taken meanwhile, including a breakpoint whose trap
+/// frame does not hold the expected values, goes to its regular handler.
+#[inline]
+pub(crate) unsafe fn handle(trap_frame: &TrapFrame) -> bool {
+    if core::ptr::read_volatile(addr_of!(STATE)) != ARMED {
+        return false;
+    }
+
+    // breakpoint exception (3), and the values `run` placed in the registers
+    if !(xcause::read().is_exception()
+        && crate::__interrupt_code() == 3
+        && [trap_frame.a0, trap_frame.a1, trap_frame.a2] == PATTERN)
+    {
+        return false;
+    }
+
+    core::ptr::write_volatile(addr_of_mut!(STATE), PASSED);
+    // resume after the `ebreak`, which is 2 bytes long if compressed
+    crate::skip_instruction();
+
+    true
+}
@@ -0,0 +1,50 @@
+//! Supervisor timer programming
+//!
+//! Only available with the `s-mode` feature. Without the Sstc extension, the supervisor timer is
+//! programmed through the SBI TIME extension; with the `sstc` feature, `stimecmp` is written
+//! directly, without the round trip through machine mode.
+
+#[cfg(feature = "sstc")]
+use core::arch::asm;
+
+/// Writes `value` to the `stimecmp` register (`stimecmp`/`stimecmph` on RV32) of the calling
+/// hart.
+///
+/// The supervisor timer interrupt is pending while `time >= stimecmp`, writing a value in the
+/// future therefore also clears a pending timer interrupt. On RV32 the low word is first set to
+/// its maximum, so that no intermediate value below both the old and the new compare value
+/// triggers a spurious interrupt.
+///
+/// The hart must implement the Sstc extension, and machine mode must have enabled it
+/// (`menvcfg.STCE`), otherwise the access raises an illegal instruction exception.
+#[cfg(feature = "sstc")]
+#[inline]
+pub fn set_stimecmp(value: u64) {
+    #[cfg(target_arch = "riscv32")]
+    unsafe {
+        asm!(
+            "csrw 0x14d, {max}",
+            "csrw 0x15d, {high}",
+            "csrw 0x14d, {low}",
+            max = in(reg) u32::MAX,
+            high = in(reg) (value >> 32) as u32,
+            low = in(reg) value as u32,
+        );
+    }
+    #[cfg(not(target_arch = "riscv32"))]
+    unsafe {
+        asm!("csrw 0x14d, {0}", in(reg) value);
+    }
+}
+
+/// Programs the supervisor timer of the calling hart to fire at `time >= value`.
+///
+/// With the `sstc` feature, this is [`set_stimecmp`], otherwise the SBI TIME extension
+/// ([`sbi::set_timer`](crate::sbi::set_timer)) is used.
+#[inline]
+pub fn set_timer(value: u64) {
+    #[cfg(feature = "sstc")]
+    set_stimecmp(value);
+    #[cfg(not(feature = "sstc"))]
+    crate::sbi::set_timer(value);
+}
@@ -0,0 +1,61 @@
+//! Transition to user mode
+
+use core::arch::asm;
+
+/// Mask of `mstatus.MPP`
+const MSTATUS_MPP: usize = 0b11 << 11;
+
+/// `pmpcfg` value granting read, write and execute access to a NAPOT region
+const PMP_NAPOT_RWX: usize = 0b11 << 3 | 0b111;
+
+/// Enters user mode, running `entry` on the stack whose top is `stack`.
+///
+/// `mstatus.MPP` is set to user mode, `mepc` to `entry` and `sp` to `stack`, then `mret` jumps
+/// to `entry`. PMP entry 15 is configured to grant user mode read, write and execute access to
+/// the whole address space, as user mode cannot access any memory without a matching entry if
+/// PMP is implemented. Being the last of the 16 entries this function assumes, it has the lowest
+/// priority, so the application can restrict the user region with entries 0 to 14 beforehand.
+///
+/// Traps taken in user mode are handled by the machine mode handlers (`_start_trap`), after
+/// which `mret` returns to user mode.
+///
+/// # Safety
+///
+/// For the split between the runtime and the user code to be effective:
+///
+/// - the trap entry must switch to a machine mode stack (e.g. through `mscratch`), as the default
+///   `_start_trap` saves the context on the interrupted stack, i.e. the user stack;
+/// - the PMP entries 0 to 14 must deny user access to the machine mode code, data and stacks;
+/// - with supervisor mode present, `medeleg`/`mideleg` must not delegate the traps of user mode
+///   to a supervisor that is not set up.
+///
+/// `stack` must be the 16-byte aligned top of a stack accessible in user mode.
+pub unsafe fn enter_user(entry: extern "C" fn() -> !, stack: usize) -> ! {
+    // NAPOT region covering the whole address space
+    asm!("csrw pmpaddr15, {0}", in(reg) usize::MAX);
+    #[cfg(target_arch = "riscv32")]
+    asm!(
+        "csrc pmpcfg3, {0}",
+        "csrs pmpcfg3, {1}",
+        in(reg) 0xffusize << 24,
+        in(reg) PMP_NAPOT_RWX << 24,
+    );
+    #[cfg(target_arch = "riscv64")]
+    asm!(
+        "csrc pmpcfg2, {0}",
+        "csrs pmpcfg2, {1}",
+        in(reg) 0xffusize << 56,
+        in(reg) PMP_NAPOT_RWX << 56,
+    );
+
+    asm!(
+        "csrc mstatus, {mpp}",
+        "csrw mepc, {entry}",
+        "mv sp, {stack}",
+        "mret",
+        mpp = in(reg) MSTATUS_MPP,
+        entry = in(reg) entry,
+        stack = in(reg) stack,
+        options(noreturn),
+    );
+}
@@ -0,0 +1,106 @@
+//! Vectored trap mode with per-interrupt trampolines, see the `v-trap` feature
+//!
+//! Replaces the `_vector_table` whose entries all jump to `_start_trap`: the entry of each core
+//! interrupt jumps to a trampoline `_vtrap_<Handler>`, which saves the caller-saved registers,
+//! calls the handler (e.g. `MachineTimer`) directly and returns from the trap. Entry 0, which
+//! receives the exceptions and the user software interrupt, and the entries of the reserved
+//! interrupts still jump to `_start_trap`.
+//!
+//! Compared to `_start_trap`, the trampolines skip `_start_trap_rust`: no read and decoding of
+//! `mcause`, no table lookup and no indirect call. The saved registers are the same 16.
+
+use core::arch::global_asm;
+
+macro_rules! vtrap {
+    // entries 0 to 15 of the table, `_start_trap` or the name of the handler of the interrupt
+    ($store:literal, $load:literal, $rb:literal, "mret") => {
+        vtrap!(
+            @emit $store, $load, $rb, "mret",
+            _start_trap, SupervisorSoft, CoreInterrupt2, MachineSoft,
+            UserTimer, SupervisorTimer, CoreInterrupt6, MachineTimer,
+            UserExternal, SupervisorExternal, CoreInterrupt10, MachineExternal,
+            _start_trap, _start_trap, _start_trap, _start_trap
+        );
+    };
+    // the machine-level interrupts are not delegated to supervisor mode
+    ($store:literal, $load:literal, $rb:literal, "sret") => {
+        vtrap!(
+            @emit $store, $load, $rb, "sret",
+            _start_trap, SupervisorSoft, CoreInterrupt2, _start_trap,
+            UserTimer, SupervisorTimer, CoreInterrupt6, _start_trap,
+            UserExternal, SupervisorExternal, CoreInterrupt10, _start_trap,
+            _start_trap, _start_trap, _start_trap, _start_trap
+        );
+    };
+    (@emit $store:literal, $load:literal, $rb:literal, $ret:literal, $($entry:ident),*) => {
+        global_asm!(
+            ".macro VTRAP_TRAMPOLINE handler",
+            ".ifnc \\handler, _start_trap",
+            ".section .trap, \"ax\"",
+            ".global _vtrap_\\handler",
+            "_vtrap_\\handler:",
+            concat!("addi sp, sp, -(", $rb, " * 16)"),
+            concat!($store, " ra, 0*", $rb, "(sp)"),
+            concat!($store, " t0, 1*", $rb, "(sp)"),
+            concat!($store, " t1, 2*", $rb, "(sp)"),
+            concat!($store, " t2, 3*", $rb, "(sp)"),
+            concat!($store, " t3, 4*", $rb, "(sp)"),
+            concat!($store, " t4, 5*", $rb, "(sp)"),
+            concat!($store, " t5, 6*", $rb, "(sp)"),
+            concat!($store, " t6, 7*", $rb, "(sp)"),
+            concat!($store, " a0, 8*", $rb, "(sp)"),
+            concat!($store, " a1, 9*", $rb, "(sp)"),
+            concat!($store, " a2, 10*", $rb, "(sp)"),
+            concat!($store, " a3, 11*", $rb, "(sp)"),
+            concat!($store, " a4, 12*", $rb, "(sp)"),
+            concat!($store, " a5, 13*", $rb, "(sp)"),
+            concat!($store, " a6, 14*", $rb, "(sp)"),
+            concat!($store, " a7, 15*", $rb, "(sp)"),
+            "call \\handler",
+            concat!($load, " ra, 0*", $rb, "(sp)"),
+            concat!($load, " t0, 1*", $rb, "(sp)"),
+            concat!($load, " t1, 2*", $rb, "(sp)"),
+            concat!($load, " t2, 3*", $rb, "(sp)"),
+            concat!($load, " t3, 4*", $rb, "(sp)"),
+            concat!($load, " t4, 5*", $rb, "(sp)"),
+            concat!($load, " t5, 6*", $rb, "(sp)"),
+            concat!($load, " t6, 7*", $rb, "(sp)"),
+            concat!($load, " a0, 8*", $rb, "(sp)"),
+            concat!($load, " a1, 9*", $rb, "(sp)"),
+            concat!($load, " a2, 10*", $rb, "(sp)"),
+            concat!($load, " a3, 11*", $rb, "(sp)"),
+            concat!($load, " a4, 12*", $rb, "(sp)"),
+            concat!($load, " a5, 13*", $rb, "(sp)"),
+            concat!($load, " a6, 14*", $rb, "(sp)"),
+            concat!($load, " a7, 15*", $rb, "(sp)"),
+            concat!("addi sp, sp, (", $rb, " * 16)"),
+            $ret,
+            ".endif",
+            ".endm",
+            $(concat!("VTRAP_TRAMPOLINE ", stringify!($entry)),)*
+            ".section .text.vector_table, \"ax\"",
+            ".option push",
+            ".option norvc",
+            ".balign 64",
+            ".global _vector_table",
+            "_vector_table:",
+            $(
+                concat!(".ifc ", stringify!($entry), ", _start_trap"),
+                "j _start_trap",
+                ".else",
+                concat!("j _vtrap_", stringify!($entry)),
+                ".endif",
+            )*
+            ".option pop",
+        );
+    };
+}
+
+#[cfg(all(target_arch = "riscv32", not(feature = "s-mode")))]
+vtrap!("sw", "lw", "4", "mret");
+#[cfg(all(target_arch = "riscv32", feature = "s-mode"))]
+vtrap!("sw", "lw", "4", "sret");
+#[cfg(all(target_arch = "riscv64", not(feature = "s-mode")))]
+vtrap!("sd", "ld", "8", "mret");
+#[cfg(all(target_arch = "riscv64", feature = "s-mode"))]
+vtrap!("sd", "ld", "8", "sret");